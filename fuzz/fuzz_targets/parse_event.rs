@@ -0,0 +1,14 @@
+#![no_main]
+use boxercrab::{Event, TableMapCache};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let mut cache = TableMapCache::new();
+    let mut i = data;
+    while let Ok((remain, _)) = Event::parse_with_cache(i, &mut cache) {
+        if remain.len() == i.len() {
+            break;
+        }
+        i = remain;
+    }
+});
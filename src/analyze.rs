@@ -0,0 +1,279 @@
+use crate::{
+    error::Error,
+    events::{event_type::EventType, Event},
+    stream::{BinlogStream, StreamEvent},
+};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt,
+};
+
+/// offsets of rows events grouped by table, keyed by `schema.table`
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TableIndex {
+    tables: HashMap<String, Vec<u64>>,
+}
+
+impl TableIndex {
+    /// scan a whole binlog file, record offset of every rows event per table
+    pub fn build(input: &[u8]) -> Result<Self, Error> {
+        let mut stream = BinlogStream::new(input);
+        let mut names: HashMap<u64, String> = HashMap::new();
+        let mut tables: HashMap<String, Vec<u64>> = HashMap::new();
+        loop {
+            let offset = stream.offset() as u64;
+            let event = match stream.next() {
+                Some(Ok(StreamEvent::Event(event))) => event,
+                Some(Ok(StreamEvent::RotateTo { .. }))
+                | Some(Ok(StreamEvent::Heartbeat { .. }))
+                | Some(Ok(StreamEvent::Skipped { .. })) => continue,
+                Some(Ok(StreamEvent::Stop { .. })) | None => break,
+                Some(Err(e)) => return Err(e),
+            };
+            match event {
+                Event::TableMap {
+                    table_id,
+                    schema,
+                    table_name,
+                    ..
+                } => {
+                    names.insert(table_id, format!("{}.{}", schema, table_name));
+                }
+                Event::WriteRowsV2 { table_id, .. }
+                | Event::UpdateRowsV2 { table_id, .. }
+                | Event::DeleteRowsV2 { table_id, .. } => {
+                    if let Some(name) = names.get(&table_id) {
+                        tables.entry(name.clone()).or_default().push(offset);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(Self { tables })
+    }
+
+    /// offsets of rows events touched `schema.table`
+    pub fn positions(&self, table: &str) -> Option<&[u64]> {
+        self.tables.get(table).map(|p| p.as_slice())
+    }
+
+    pub fn into_inner(self) -> HashMap<String, Vec<u64>> {
+        self.tables
+    }
+}
+
+/// count and size of events of one type
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TypeStats {
+    pub count: u64,
+    pub bytes: u64,
+    pub min_size: u64,
+    pub max_size: u64,
+}
+
+impl TypeStats {
+    fn record(&mut self, size: u64) {
+        if self.count == 0 || size < self.min_size {
+            self.min_size = size;
+        }
+        self.max_size = self.max_size.max(size);
+        self.count += 1;
+        self.bytes += size;
+    }
+
+    fn merge(&mut self, other: &TypeStats) {
+        if other.count == 0 {
+            return;
+        }
+        if self.count == 0 || other.min_size < self.min_size {
+            self.min_size = other.min_size;
+        }
+        self.max_size = self.max_size.max(other.max_size);
+        self.count += other.count;
+        self.bytes += other.bytes;
+    }
+
+    /// average event size, 0 if no event recorded
+    pub fn avg_size(&self) -> u64 {
+        if self.count == 0 {
+            0
+        } else {
+            self.bytes / self.count
+        }
+    }
+}
+
+/// event statistics of a binlog file grouped by event type
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Stats {
+    types: BTreeMap<EventType, TypeStats>,
+}
+
+impl Stats {
+    /// stats of given event type
+    pub fn get(&self, kind: EventType) -> Option<&TypeStats> {
+        self.types.get(&kind)
+    }
+
+    /// stats of all events
+    pub fn total(&self) -> TypeStats {
+        let mut total = TypeStats::default();
+        self.types.values().for_each(|s| total.merge(s));
+        total
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&EventType, &TypeStats)> {
+        self.types.iter()
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let row = |f: &mut fmt::Formatter<'_>, name: &str, s: &TypeStats| {
+            writeln!(
+                f,
+                "{:<20} {:>10} {:>12} {:>10} {:>10} {:>10}",
+                name,
+                s.count,
+                s.bytes,
+                s.min_size,
+                s.max_size,
+                s.avg_size()
+            )
+        };
+        writeln!(
+            f,
+            "{:<20} {:>10} {:>12} {:>10} {:>10} {:>10}",
+            "event type", "count", "bytes", "min", "max", "avg"
+        )?;
+        for (kind, s) in self.types.iter() {
+            row(f, &format!("{:?}", kind), s)?;
+        }
+        row(f, "total", &self.total())
+    }
+}
+
+/// scan a whole binlog file, tally count and size of events per type
+pub fn collect_stats(input: &[u8]) -> Result<Stats, Error> {
+    let mut types: BTreeMap<EventType, TypeStats> = BTreeMap::new();
+    for item in BinlogStream::new(input) {
+        let header = match item? {
+            StreamEvent::Event(event) => event.header().clone(),
+            StreamEvent::Stop { header }
+            | StreamEvent::RotateTo { header, .. }
+            | StreamEvent::Heartbeat { header, .. } => header,
+            StreamEvent::Skipped { .. } => continue,
+        };
+        types
+            .entry(header.kind())
+            .or_default()
+            .record(header.event_size as u64);
+    }
+    Ok(Stats { types })
+}
+
+/// group transactions into batches which can be applied in parallel, by logical clock
+/// of Gtid and AnonymousGtid events, other events are ignored
+///
+/// batches hold `sequence_number` of transactions in commit order, a transaction joins
+/// current batch if it does not depend on any of them, i.e. its `last_committed` is
+/// smaller than `sequence_number` of every transaction in the batch
+pub fn commit_order<'a, I>(events: I) -> Vec<Vec<i64>>
+where
+    I: IntoIterator<Item = &'a Event>,
+{
+    let mut batches: Vec<Vec<i64>> = Vec::new();
+    for event in events {
+        let (last_committed, sequence_number) = match event {
+            Event::Gtid {
+                last_committed,
+                sequence_number,
+                ..
+            }
+            | Event::AnonymousGtid {
+                last_committed,
+                sequence_number,
+                ..
+            } => (*last_committed, *sequence_number),
+            _ => continue,
+        };
+        // sequence numbers grow within a binlog file, first one is the smallest
+        match batches.last_mut() {
+            Some(batch) if last_committed < batch[0] => batch.push(sequence_number),
+            _ => batches.push(vec![sequence_number]),
+        }
+    }
+    batches
+}
+
+/// table_ids modified by rows events of a transaction, names can be looked up in
+/// `TableMapCache` or TableMap events of the transaction
+pub fn affected_tables(events: &[Event]) -> HashSet<u64> {
+    events
+        .iter()
+        .filter_map(|event| match event {
+            Event::WriteRowsV2 { table_id, .. }
+            | Event::UpdateRowsV2 { table_id, .. }
+            | Event::DeleteRowsV2 { table_id, .. } => Some(*table_id),
+            _ => None,
+        })
+        .collect()
+}
+
+/// GNOs `[start, end)` of source `sid` missing from executed set
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GtidGap {
+    pub sid: String,
+    pub start: i64,
+    pub end: i64,
+}
+
+/// find holes in executed GTID set of every source, built from PreviousGtids events and
+/// GNOs of Gtid events, holes are likely caused by truncated or corrupted binlog
+///
+/// GNOs before the first executed one of a source are not checked
+pub fn check_gtid_gaps(events: &[Event]) -> Vec<GtidGap> {
+    let mut executed: BTreeMap<&str, Vec<(i64, i64)>> = BTreeMap::new();
+    for event in events {
+        match event {
+            Event::PreviousGtids { gtid_set, .. } => {
+                for sid in gtid_set.sids.iter() {
+                    executed
+                        .entry(&sid.sid)
+                        .or_default()
+                        .extend_from_slice(&sid.intervals);
+                }
+            }
+            Event::Gtid {
+                source_id,
+                transaction_id,
+                ..
+            } => {
+                executed
+                    .entry(source_id)
+                    .or_default()
+                    .push((*transaction_id, transaction_id + 1));
+            }
+            _ => {}
+        }
+    }
+    let mut gaps = vec![];
+    for (sid, mut intervals) in executed {
+        intervals.sort_unstable();
+        let mut end = match intervals.first() {
+            Some((_, end)) => *end,
+            None => continue,
+        };
+        for (start, next_end) in intervals {
+            if start > end {
+                gaps.push(GtidGap {
+                    sid: sid.to_string(),
+                    start: end,
+                    end: start,
+                });
+            }
+            end = end.max(next_end);
+        }
+    }
+    gaps
+}
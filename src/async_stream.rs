@@ -0,0 +1,77 @@
+use crate::{
+    error::Error,
+    events::ParseOptions,
+    frame::{is_last, FrameBuffer},
+    stream::StreamEvent,
+};
+use futures::Stream;
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::io::AsyncRead;
+
+/// async version of `BinlogStream`, read events from an `AsyncRead` source
+///
+/// events are framed by `event_size` in header, stream ends after a Stop event
+/// or when reader reaches EOF between events, an error ends stream too
+pub struct AsyncBinlogStream<R> {
+    reader: R,
+    frame: FrameBuffer,
+    done: bool,
+}
+
+impl<R: AsyncRead + Unpin> AsyncBinlogStream<R> {
+    /// reader should start with binlog magic number
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            frame: FrameBuffer::new(),
+            done: false,
+        }
+    }
+
+    /// parse events with `options`, event larger than `max_event_size` is rejected
+    /// before its body is buffered
+    pub fn with_options(mut self, options: ParseOptions) -> Self {
+        self.frame.set_options(options);
+        self
+    }
+
+    /// offset of next event in source
+    pub fn offset(&self) -> usize {
+        self.frame.offset()
+    }
+
+    fn poll_event(&mut self, cx: &mut Context<'_>) -> Poll<Result<Option<StreamEvent>, Error>> {
+        loop {
+            if let Some(buf) = self.frame.spare()? {
+                match Pin::new(&mut self.reader).poll_read(cx, buf) {
+                    Poll::Pending => return Poll::Pending,
+                    Poll::Ready(Err(e)) => return Poll::Ready(Err(Error::Io(e.kind()))),
+                    Poll::Ready(Ok(0)) => return Poll::Ready(self.frame.eof()),
+                    Poll::Ready(Ok(n)) => self.frame.fill(n),
+                }
+            } else if let Some(event) = self.frame.parse()? {
+                return Poll::Ready(Ok(Some(event)));
+            }
+        }
+    }
+}
+
+impl<R: AsyncRead + Unpin> Stream for AsyncBinlogStream<R> {
+    type Item = Result<StreamEvent, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.done {
+            return Poll::Ready(None);
+        }
+        let item = match this.poll_event(cx) {
+            Poll::Pending => return Poll::Pending,
+            Poll::Ready(item) => item,
+        };
+        this.done = is_last(&item);
+        Poll::Ready(item.transpose())
+    }
+}
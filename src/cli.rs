@@ -1,4 +1,6 @@
-use boxercrab::{Connection, Event};
+use boxercrab::{
+    read_binlog_path, BinlogStream, Connection, Event, EventType, Filter, StreamEvent,
+};
 use log::LevelFilter;
 use log4rs::{
     append::console::{ConsoleAppender, Target},
@@ -36,6 +38,20 @@ enum Cmd {
         format: Format,
     },
 
+    /// Dump a binlog file as JSON lines, one event per line
+    Dump {
+        /// Binlog file path
+        input: String,
+
+        /// Only dump events of given types, e.g. Query, TableMap, can be repeated
+        #[structopt(short, long = "type", parse(try_from_str = parse_type))]
+        types: Vec<EventType>,
+
+        /// Skip malformed bytes and resume at next valid event instead of failing
+        #[structopt(long)]
+        resync: bool,
+    },
+
     /// Show bin log desc msg
     Desc {
         /// Binlog file path
@@ -100,6 +116,39 @@ fn parse_from_file(path: &str) -> Result<Vec<Event>, String> {
     }
 }
 
+fn parse_type(name: &str) -> Result<EventType, String> {
+    EventType::from_name(name).ok_or_else(|| format!("unknown event type {}", name))
+}
+
+/// write events of binlog file as JSON lines, gzipped file is detected by `read_binlog_path`
+fn dump(path: &str, types: &[EventType], resync: bool) -> Result<(), String> {
+    let data = read_binlog_path(path).map_err(|e| format!("failed to read {}: {}", path, e))?;
+    let mut stream = BinlogStream::new(&data);
+    if let Some(filter) = types
+        .iter()
+        .map(|t| Filter::event_type(*t))
+        .reduce(Filter::or)
+    {
+        stream = stream.with_filter(filter);
+    }
+    if resync {
+        stream = stream.with_resync();
+    }
+
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+    for item in stream {
+        let item = item.map_err(|e| format!("failed to parse {}: {}", path, e))?;
+        let written = match &item {
+            StreamEvent::Event(event) => serde_json::to_writer(&mut out, event),
+            item => serde_json::to_writer(&mut out, item),
+        };
+        written.map_err(|e| format!("failed to write event: {}", e))?;
+        writeln!(out).map_err(|e| format!("failed to write event: {}", e))?;
+    }
+    Ok(())
+}
+
 fn main() {
     let args = Args::from_args();
     let _handle = init_log(args.debug);
@@ -140,6 +189,16 @@ fn main() {
                 }
             }
         },
+        Cmd::Dump {
+            input,
+            types,
+            resync,
+        } => {
+            if let Err(e) = dump(&input, &types, resync) {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
         Cmd::Desc { input } => match parse_from_file(&input) {
             Err(e) => println!("{}", e),
             Ok(events) => {
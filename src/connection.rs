@@ -63,7 +63,7 @@ impl Connection {
             com_bindump.put_u32_le(self.id);
             // binlog file name
             com_bindump.put(binlog_file.as_bytes());
-            println!("{:x?}", com_bindump.as_ref());
+            log::trace!("COM_BINLOG_DUMP packet: {:x?}", com_bindump.as_ref());
 
             self.conn
                 .as_mut()
@@ -0,0 +1,85 @@
+//! decode MySQL binary DECIMAL, the format of NEWDECIMAL column values and DECIMAL
+//! user variables
+//!
+//! digits are packed in groups of 9 into 4 bytes big endian integers, leading group of
+//! integer part and trailing group of fraction part may be shorter. highest bit of first
+//! byte is flipped sign bit, negative values have all bytes inverted
+//!
+//! ref: https://github.com/mysql/mysql-server/blob/8.0/strings/decimal.cc
+use alloc::{format, string::String};
+
+const DIG_PER_DEC: usize = 9;
+// bytes of a group of n digits
+const DIG2BYTES: [usize; 10] = [0, 1, 1, 2, 2, 3, 3, 4, 4, 4];
+
+/// read `len` bytes big endian, inverted if value is negative
+fn read_group(data: &[u8], len: usize, mask: u8) -> u32 {
+    data[..len]
+        .iter()
+        .fold(0u32, |acc, b| (acc << 8) | (b ^ mask) as u32)
+}
+
+/// bytes of binary DECIMAL(precision, scale), 4 bytes per 9 digits of integer and fraction
+/// part, plus leading group of integer part and trailing group of fraction part
+///
+/// precision smaller than scale is taken as no integer part
+pub fn binary_size(precision: u8, scale: u8) -> usize {
+    let intg = precision.saturating_sub(scale) as usize;
+    let scale = scale as usize;
+    (intg / DIG_PER_DEC) * 4
+        + DIG2BYTES[intg % DIG_PER_DEC]
+        + (scale / DIG_PER_DEC) * 4
+        + DIG2BYTES[scale % DIG_PER_DEC]
+}
+
+/// decode binary DECIMAL(precision, scale) to its text form, e.g. `-12.34`
+///
+/// return None if precision is smaller than scale or data is shorter than required
+pub fn decode(data: &[u8], precision: u8, scale: u8) -> Option<String> {
+    let intg = precision.checked_sub(scale)? as usize;
+    let scale = scale as usize;
+    let (intg0, intg0x) = (intg / DIG_PER_DEC, intg % DIG_PER_DEC);
+    let (frac0, frac0x) = (scale / DIG_PER_DEC, scale % DIG_PER_DEC);
+    let len = binary_size(precision, scale as u8);
+    if len == 0 || data.len() < len {
+        return None;
+    }
+    let mut buf = data[..len].to_vec();
+    let negative = buf[0] & 0x80 == 0;
+    let mask = if negative { 0xff } else { 0x00 };
+    buf[0] ^= 0x80;
+
+    let mut int_part = String::new();
+    let mut pos = 0;
+    if intg0x > 0 {
+        let size = DIG2BYTES[intg0x];
+        int_part.push_str(&format!("{}", read_group(&buf[pos..], size, mask)));
+        pos += size;
+    }
+    for _ in 0..intg0 {
+        int_part.push_str(&format!("{:09}", read_group(&buf[pos..], 4, mask)));
+        pos += 4;
+    }
+    let int_part = int_part.trim_start_matches('0');
+
+    let mut frac_part = String::new();
+    for _ in 0..frac0 {
+        frac_part.push_str(&format!("{:09}", read_group(&buf[pos..], 4, mask)));
+        pos += 4;
+    }
+    if frac0x > 0 {
+        let value = read_group(&buf[pos..], DIG2BYTES[frac0x], mask);
+        frac_part.push_str(&format!("{:0width$}", value, width = frac0x));
+    }
+
+    let mut text = String::with_capacity(precision as usize + 3);
+    if negative {
+        text.push('-');
+    }
+    text.push_str(if int_part.is_empty() { "0" } else { int_part });
+    if !frac_part.is_empty() {
+        text.push('.');
+        text.push_str(&frac_part);
+    }
+    Some(text)
+}
@@ -0,0 +1,157 @@
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+use nom::{
+    error::{ErrorKind, ParseError, VerboseError, VerboseErrorKind},
+    IResult,
+};
+
+/// result of parsers which tell which field failed, see `context`
+pub(crate) type VerboseResult<'a, T> = IResult<&'a [u8], T, VerboseError<&'a [u8]>>;
+
+/// tell `name` in error if parser failed, e.g. `context("reading column_count", int_lenenc(i))`
+pub(crate) fn context<'a, T>(
+    name: &'static str,
+    result: Result<T, nom::Err<(&'a [u8], ErrorKind)>>,
+) -> Result<T, nom::Err<VerboseError<&'a [u8]>>> {
+    add_context(name, verbose(result))
+}
+
+/// same as `context`, for parser which tells contexts of its own
+pub(crate) fn add_context<'a, T>(
+    name: &'static str,
+    result: Result<T, nom::Err<VerboseError<&'a [u8]>>>,
+) -> Result<T, nom::Err<VerboseError<&'a [u8]>>> {
+    result.map_err(|e| {
+        e.map(|e| {
+            let input = e.errors.last().map_or(&[][..], |(i, _)| *i);
+            VerboseError::add_context(input, name, e)
+        })
+    })
+}
+
+/// lift result of parser without context, so contexts can be added to its error
+pub(crate) fn verbose<T>(
+    result: Result<T, nom::Err<(&[u8], ErrorKind)>>,
+) -> Result<T, nom::Err<VerboseError<&[u8]>>> {
+    result.map_err(|e| e.map(|(i, kind)| VerboseError::from_error_kind(i, kind)))
+}
+
+/// drop contexts, error of innermost parser is kept
+pub(crate) fn plain<'a, T>(result: VerboseResult<'a, T>) -> IResult<&'a [u8], T> {
+    result.map_err(|e| {
+        e.map(|e| {
+            let input = e.errors.first().map_or(&[][..], |(i, _)| *i);
+            (input, innermost_kind(&e.errors))
+        })
+    })
+}
+
+fn innermost_kind<I>(errors: &[(I, VerboseErrorKind)]) -> ErrorKind {
+    match errors.first() {
+        Some((_, VerboseErrorKind::Nom(kind))) => *kind,
+        Some((_, VerboseErrorKind::Char(_))) => ErrorKind::Char,
+        _ => ErrorKind::Eof,
+    }
+}
+
+/// error returned by high level apis, eg. BinlogStream
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Error {
+    /// input is not started with binlog magic number
+    BadMagic,
+    /// input ends in the middle of an event, binlog may be truncated
+    Truncated { offset: usize },
+    /// event starting at offset can't be parsed, context tells which parser and field failed
+    /// if known, e.g. `parse_table_map: reading column_count`
+    Parse {
+        offset: usize,
+        kind: ErrorKind,
+        context: Option<String>,
+    },
+    /// string of event starting at offset is not followed by `[00]` terminator
+    UnexpectedTerminator { offset: usize },
+    /// `event_size` in header of event starting at offset exceeds `max_event_size`
+    TooLarge { offset: usize, size: usize },
+    /// bytes consumed by parser doesn't match `event_size` in header
+    SizeMismatch { expected: usize, actual: usize },
+    /// bytes left after the only event of input
+    TrailingBytes { len: usize },
+    /// reading from source failed
+    #[cfg(feature = "std")]
+    Io(std::io::ErrorKind),
+}
+
+impl Error {
+    /// convert nom error of event starting at offset, contexts are joined from outermost,
+    /// e.g. `parse_table_map: reading column_count`
+    pub(crate) fn from_nom(err: nom::Err<VerboseError<&[u8]>>, offset: usize) -> Self {
+        let errors = match err {
+            nom::Err::Error(e) | nom::Err::Failure(e) => e.errors,
+            nom::Err::Incomplete(_) => Vec::new(),
+        };
+        // only raised by `utils::terminator`
+        if let Some((_, VerboseErrorKind::Char('\0'))) = errors.first() {
+            return Error::UnexpectedTerminator { offset };
+        }
+        let kind = innermost_kind(&errors);
+        let names: Vec<_> = errors
+            .iter()
+            .rev()
+            .filter_map(|(_, kind)| match kind {
+                VerboseErrorKind::Context(name) => Some(*name),
+                _ => None,
+            })
+            .collect();
+        Error::Parse {
+            offset,
+            kind,
+            context: if names.is_empty() {
+                None
+            } else {
+                Some(names.join(": "))
+            },
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::BadMagic => write!(f, "invalid binlog magic number"),
+            Error::Truncated { offset } => write!(f, "event truncated at offset {}", offset),
+            Error::Parse {
+                offset,
+                kind,
+                context: None,
+            } => write!(f, "failed to parse event at offset {}: {:?}", offset, kind),
+            Error::Parse {
+                offset,
+                kind,
+                context: Some(context),
+            } => write!(
+                f,
+                "failed to parse event at offset {} in {}: {:?}",
+                offset, context, kind
+            ),
+            Error::UnexpectedTerminator { offset } => {
+                write!(f, "missing string terminator in event at offset {}", offset)
+            }
+            Error::TooLarge { offset, size } => write!(
+                f,
+                "event at offset {} claims {} bytes, exceeds max_event_size",
+                offset, size
+            ),
+            Error::SizeMismatch { expected, actual } => write!(
+                f,
+                "event_size is {} but parser consumed {} bytes",
+                expected, actual
+            ),
+            Error::TrailingBytes { len } => write!(f, "{} bytes left after event", len),
+            #[cfg(feature = "std")]
+            Error::Io(kind) => write!(f, "failed to read binlog: {:?}", kind),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
@@ -0,0 +1,65 @@
+use crate::utils::crc32;
+use serde::Serialize;
+
+/// checksum algorithm of events, recorded in `checksum_alg` of FormatDesc event
+///
+/// only CRC32 is used by MySQL, other values are kept in `Unknown`
+#[derive(Debug, Serialize, PartialEq, Eq, Clone, Copy)]
+pub enum ChecksumAlg {
+    Off,
+    Crc32,
+    Unknown(u8),
+}
+
+impl ChecksumAlg {
+    /// map `checksum_alg` of FormatDesc event
+    pub fn from_u8(alg: u8) -> Self {
+        match alg {
+            0 => ChecksumAlg::Off,
+            1 => ChecksumAlg::Crc32,
+            other => ChecksumAlg::Unknown(other),
+        }
+    }
+
+    pub fn as_u8(&self) -> u8 {
+        match self {
+            ChecksumAlg::Off => 0,
+            ChecksumAlg::Crc32 => 1,
+            ChecksumAlg::Unknown(alg) => *alg,
+        }
+    }
+
+    /// length of checksum appended to each event, 0 for unknown algorithm
+    pub fn checksum_len(&self) -> usize {
+        match self {
+            ChecksumAlg::Crc32 => 4,
+            ChecksumAlg::Off | ChecksumAlg::Unknown(_) => 0,
+        }
+    }
+
+    /// compute checksum of event bytes(header and body, without checksum)
+    pub fn compute(&self, data: &[u8]) -> Option<u32> {
+        match self {
+            ChecksumAlg::Crc32 => Some(crc32(data)),
+            ChecksumAlg::Off | ChecksumAlg::Unknown(_) => None,
+        }
+    }
+
+    /// verify raw bytes of a whole event, checksum included
+    ///
+    /// event can't be verified with unknown algorithm, always return false
+    pub fn verify(&self, event: &[u8]) -> bool {
+        if let ChecksumAlg::Unknown(_) = self {
+            return false;
+        }
+        let len = self.checksum_len();
+        if event.len() < len {
+            return false;
+        }
+        let (data, checksum) = event.split_at(event.len() - len);
+        match self.compute(data) {
+            Some(expected) => checksum == &expected.to_le_bytes()[..],
+            None => true,
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use serde::Serialize;
+
+/// binlog event type, stored as a byte in event header
+///
+/// ref: https://dev.mysql.com/doc/internals/en/binlog-event-type.html
+#[derive(Debug, Serialize, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum EventType {
+    Unknown,
+    StartV3,
+    Query,
+    Stop,
+    Rotate,
+    IntVar,
+    Load,
+    Slave,
+    CreateFile,
+    AppendBlock,
+    ExecLoad,
+    DeleteFile,
+    NewLoad,
+    Rand,
+    UserVar,
+    FormatDescription,
+    Xid,
+    BeginLoadQuery,
+    ExecuteLoadQuery,
+    TableMap,
+    PreGaWriteRows,
+    PreGaUpdateRows,
+    PreGaDeleteRows,
+    WriteRowsV1,
+    UpdateRowsV1,
+    DeleteRowsV1,
+    Incident,
+    Heartbeat,
+    Ignorable,
+    RowsQuery,
+    WriteRowsV2,
+    UpdateRowsV2,
+    DeleteRowsV2,
+    Gtid,
+    AnonymousGtid,
+    PreviousGtids,
+    TransactionContext,
+    ViewChange,
+    XaPrepareLog,
+    PartialUpdateRows,
+    // MariaDB only
+    MariaAnnotateRows,
+    MariaGtid,
+    // type code not defined by MySQL
+    Other(u8),
+}
+
+impl EventType {
+    pub fn from_u8(t: u8) -> Self {
+        match t {
+            0x00 => EventType::Unknown,
+            0x01 => EventType::StartV3,
+            0x02 => EventType::Query,
+            0x03 => EventType::Stop,
+            0x04 => EventType::Rotate,
+            0x05 => EventType::IntVar,
+            0x06 => EventType::Load,
+            0x07 => EventType::Slave,
+            0x08 => EventType::CreateFile,
+            0x09 => EventType::AppendBlock,
+            0x0a => EventType::ExecLoad,
+            0x0b => EventType::DeleteFile,
+            0x0c => EventType::NewLoad,
+            0x0d => EventType::Rand,
+            0x0e => EventType::UserVar,
+            0x0f => EventType::FormatDescription,
+            0x10 => EventType::Xid,
+            0x11 => EventType::BeginLoadQuery,
+            0x12 => EventType::ExecuteLoadQuery,
+            0x13 => EventType::TableMap,
+            0x14 => EventType::PreGaWriteRows,
+            0x15 => EventType::PreGaUpdateRows,
+            0x16 => EventType::PreGaDeleteRows,
+            0x17 => EventType::WriteRowsV1,
+            0x18 => EventType::UpdateRowsV1,
+            0x19 => EventType::DeleteRowsV1,
+            0x1a => EventType::Incident,
+            0x1b => EventType::Heartbeat,
+            0x1c => EventType::Ignorable,
+            0x1d => EventType::RowsQuery,
+            0x1e => EventType::WriteRowsV2,
+            0x1f => EventType::UpdateRowsV2,
+            0x20 => EventType::DeleteRowsV2,
+            0x21 => EventType::Gtid,
+            0x22 => EventType::AnonymousGtid,
+            0x23 => EventType::PreviousGtids,
+            0x24 => EventType::TransactionContext,
+            0x25 => EventType::ViewChange,
+            0x26 => EventType::XaPrepareLog,
+            0x27 => EventType::PartialUpdateRows,
+            0xa0 => EventType::MariaAnnotateRows,
+            0xa2 => EventType::MariaGtid,
+            t => EventType::Other(t),
+        }
+    }
+
+    /// type of variant name, case insensitive, e.g. `query` or `TableMap`
+    pub fn from_name(name: &str) -> Option<Self> {
+        let t = match name.to_ascii_lowercase().as_str() {
+            "unknown" => EventType::Unknown,
+            "startv3" => EventType::StartV3,
+            "query" => EventType::Query,
+            "stop" => EventType::Stop,
+            "rotate" => EventType::Rotate,
+            "intvar" => EventType::IntVar,
+            "load" => EventType::Load,
+            "slave" => EventType::Slave,
+            "createfile" => EventType::CreateFile,
+            "appendblock" => EventType::AppendBlock,
+            "execload" => EventType::ExecLoad,
+            "deletefile" => EventType::DeleteFile,
+            "newload" => EventType::NewLoad,
+            "rand" => EventType::Rand,
+            "uservar" => EventType::UserVar,
+            "formatdescription" => EventType::FormatDescription,
+            "xid" => EventType::Xid,
+            "beginloadquery" => EventType::BeginLoadQuery,
+            "executeloadquery" => EventType::ExecuteLoadQuery,
+            "tablemap" => EventType::TableMap,
+            "pregawriterows" => EventType::PreGaWriteRows,
+            "pregaupdaterows" => EventType::PreGaUpdateRows,
+            "pregadeleterows" => EventType::PreGaDeleteRows,
+            "writerowsv1" => EventType::WriteRowsV1,
+            "updaterowsv1" => EventType::UpdateRowsV1,
+            "deleterowsv1" => EventType::DeleteRowsV1,
+            "incident" => EventType::Incident,
+            "heartbeat" => EventType::Heartbeat,
+            "ignorable" => EventType::Ignorable,
+            "rowsquery" => EventType::RowsQuery,
+            "writerowsv2" => EventType::WriteRowsV2,
+            "updaterowsv2" => EventType::UpdateRowsV2,
+            "deleterowsv2" => EventType::DeleteRowsV2,
+            "gtid" => EventType::Gtid,
+            "anonymousgtid" => EventType::AnonymousGtid,
+            "previousgtids" => EventType::PreviousGtids,
+            "transactioncontext" => EventType::TransactionContext,
+            "viewchange" => EventType::ViewChange,
+            "xapreparelog" => EventType::XaPrepareLog,
+            "partialupdaterows" => EventType::PartialUpdateRows,
+            "mariaannotaterows" => EventType::MariaAnnotateRows,
+            "mariagtid" => EventType::MariaGtid,
+            _ => return None,
+        };
+        Some(t)
+    }
+
+    pub fn as_u8(&self) -> u8 {
+        match *self {
+            EventType::Unknown => 0x00,
+            EventType::StartV3 => 0x01,
+            EventType::Query => 0x02,
+            EventType::Stop => 0x03,
+            EventType::Rotate => 0x04,
+            EventType::IntVar => 0x05,
+            EventType::Load => 0x06,
+            EventType::Slave => 0x07,
+            EventType::CreateFile => 0x08,
+            EventType::AppendBlock => 0x09,
+            EventType::ExecLoad => 0x0a,
+            EventType::DeleteFile => 0x0b,
+            EventType::NewLoad => 0x0c,
+            EventType::Rand => 0x0d,
+            EventType::UserVar => 0x0e,
+            EventType::FormatDescription => 0x0f,
+            EventType::Xid => 0x10,
+            EventType::BeginLoadQuery => 0x11,
+            EventType::ExecuteLoadQuery => 0x12,
+            EventType::TableMap => 0x13,
+            EventType::PreGaWriteRows => 0x14,
+            EventType::PreGaUpdateRows => 0x15,
+            EventType::PreGaDeleteRows => 0x16,
+            EventType::WriteRowsV1 => 0x17,
+            EventType::UpdateRowsV1 => 0x18,
+            EventType::DeleteRowsV1 => 0x19,
+            EventType::Incident => 0x1a,
+            EventType::Heartbeat => 0x1b,
+            EventType::Ignorable => 0x1c,
+            EventType::RowsQuery => 0x1d,
+            EventType::WriteRowsV2 => 0x1e,
+            EventType::UpdateRowsV2 => 0x1f,
+            EventType::DeleteRowsV2 => 0x20,
+            EventType::Gtid => 0x21,
+            EventType::AnonymousGtid => 0x22,
+            EventType::PreviousGtids => 0x23,
+            EventType::TransactionContext => 0x24,
+            EventType::ViewChange => 0x25,
+            EventType::XaPrepareLog => 0x26,
+            EventType::PartialUpdateRows => 0x27,
+            EventType::MariaAnnotateRows => 0xa0,
+            EventType::MariaGtid => 0xa2,
+            EventType::Other(t) => t,
+        }
+    }
+}
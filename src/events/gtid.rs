@@ -0,0 +1,98 @@
+use alloc::{format, string::String, vec::Vec};
+use core::fmt;
+use nom::{
+    bytes::complete::take,
+    combinator::verify,
+    number::complete::{le_i64, le_u64},
+    IResult,
+};
+use serde::Serialize;
+
+/// transactions of one source, intervals are `[start, end)`
+#[derive(Debug, Serialize, PartialEq, Eq, Clone)]
+pub struct SidIntervals {
+    // server uuid, e.g. 3e11fa47-71ca-11e1-9e33-c80aa9429562
+    pub sid: String,
+    pub intervals: Vec<(i64, i64)>,
+}
+
+/// GTID set of PreviousGtids event
+///
+/// displayed in the text form used by MySQL, e.g. `3e11fa47-71ca-11e1-9e33-c80aa9429562:1-5:7`
+#[derive(Debug, Serialize, PartialEq, Eq, Clone, Default)]
+pub struct GtidSet {
+    pub sids: Vec<SidIntervals>,
+}
+
+impl GtidSet {
+    /// a fresh binlog without any executed transaction has an empty set
+    pub fn is_empty(&self) -> bool {
+        self.sids.iter().all(|sid| sid.intervals.is_empty())
+    }
+}
+
+impl fmt::Display for GtidSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, sid) in self.sids.iter().enumerate() {
+            if idx != 0 {
+                f.write_str(",")?;
+            }
+            f.write_str(&sid.sid)?;
+            for (start, end) in sid.intervals.iter() {
+                if end - start == 1 {
+                    write!(f, ":{}", start)?;
+                } else {
+                    write!(f, ":{}-{}", start, end - 1)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn uuid(s: &[u8]) -> String {
+    let hex: Vec<String> = s.iter().map(|b| format!("{:02x}", b)).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        hex[..4].concat(),
+        hex[4..6].concat(),
+        hex[6..8].concat(),
+        hex[8..10].concat(),
+        hex[10..].concat()
+    )
+}
+
+fn parse_sid(input: &[u8]) -> IResult<&[u8], SidIntervals> {
+    let (i, sid) = take(16usize)(input)?;
+    // every interval takes 16 bytes, avoid allocating for a corrupted count
+    let (mut i, count) = verify(le_u64, |&n| n <= (input.len() / 16) as u64)(i)?;
+    let mut intervals = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let (remain, start) = le_i64(i)?;
+        let (remain, end) = le_i64(remain)?;
+        intervals.push((start, end));
+        i = remain;
+    }
+    Ok((
+        i,
+        SidIntervals {
+            sid: uuid(sid),
+            intervals,
+        },
+    ))
+}
+
+/// parse encoded GTID set: sid count, then sid, interval count and intervals of every sid
+///
+/// source: https://github.com/mysql/mysql-server/blob/8.0/sql/rpl_gtid_set.cc
+pub fn parse_gtid_set(input: &[u8]) -> IResult<&[u8], GtidSet> {
+    // every sid takes at least 24 bytes
+    let (mut i, count) = verify(le_u64, |&n| n <= (input.len() / 24) as u64)(input)?;
+    let mut sids = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let (remain, sid) = parse_sid(i)?;
+        sids.push(sid);
+        i = remain;
+    }
+    Ok((i, GtidSet { sids }))
+}
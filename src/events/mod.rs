@@ -1,42 +1,105 @@
 use crate::{
+    error::{add_context, context, plain, verbose, VerboseResult},
+    json,
     mysql::{ColTypes, ColValues},
-    utils::{extract_string, int_lenenc, pu64, string_fixed, string_nul, string_var},
+    utils::{
+        extract_string, int_fixed, int_lenenc, int_lenenc_len, string_fixed, string_nul,
+        string_var, take_u48_le, terminator,
+    },
+};
+use alloc::{
+    string::{String, ToString},
+    vec,
+    vec::Vec,
 };
+use checksum::ChecksumAlg;
+use event_type::EventType;
+#[cfg(feature = "std")]
 use lazy_static::lazy_static;
 use nom::{
     bytes::complete::{tag, take},
-    combinator::map,
-    multi::{many0, many1, many_m_n},
+    combinator::{all_consuming, map, verify},
+    error::{ErrorKind, ParseError, VerboseError},
+    multi::{many0, many_m_n},
     number::complete::{le_i64, le_u16, le_u32, le_u64, le_u8},
     sequence::tuple,
     IResult,
 };
 use serde::Serialize;
-use std::{
-    collections::HashMap,
-    sync::{Arc, Mutex},
-};
+#[cfg(feature = "std")]
+use std::sync::{Arc, Mutex};
+use table_map::{TableInfo, TableMapCache};
 
+pub mod checksum;
+pub mod event_type;
+pub mod gtid;
 pub mod query;
 pub mod rows;
+pub mod table_map;
 
+#[cfg(feature = "std")]
 lazy_static! {
-    static ref TABLE_MAP: Arc<Mutex<HashMap<u64, Vec<ColTypes>>>> =
-        Arc::new(Mutex::new(HashMap::new()));
+    static ref TABLE_MAP: Arc<Mutex<TableMapCache>> = Arc::new(Mutex::new(TableMapCache::new()));
 }
 
-#[derive(Debug, Serialize, PartialEq, Eq, Clone)]
+#[derive(Debug, Serialize, PartialEq, Eq, Clone, Default)]
 pub struct EventFlag {
-    in_use: bool,
-    forced_rotate: bool,
-    thread_specific: bool,
-    suppress_use: bool,
-    update_table_map_version: bool,
-    artificial: bool,
-    relay_log: bool,
-    ignorable: bool,
-    no_filter: bool,
-    mts_isolate: bool,
+    pub in_use: bool,
+    pub forced_rotate: bool,
+    pub thread_specific: bool,
+    pub suppress_use: bool,
+    pub update_table_map_version: bool,
+    pub artificial: bool,
+    pub relay_log: bool,
+    pub ignorable: bool,
+    pub no_filter: bool,
+    pub mts_isolate: bool,
+}
+
+impl EventFlag {
+    /// decode `flags` of event header
+    pub fn from_u16(f: u16) -> Self {
+        EventFlag {
+            in_use: f & 1 == 1,
+            forced_rotate: (f >> 1) & 1 == 1,
+            thread_specific: (f >> 2) & 1 == 1,
+            suppress_use: (f >> 3) & 1 == 1,
+            update_table_map_version: (f >> 4) & 1 == 1,
+            artificial: (f >> 5) & 1 == 1,
+            relay_log: (f >> 6) & 1 == 1,
+            ignorable: (f >> 7) & 1 == 1,
+            no_filter: (f >> 8) & 1 == 1,
+            mts_isolate: (f >> 9) & 1 == 1,
+        }
+    }
+
+    /// event is written by replica into relay log
+    pub fn relay_log(&self) -> bool {
+        self.relay_log
+    }
+
+    /// event is generated by server, not by executed statement
+    pub fn artificial(&self) -> bool {
+        self.artificial
+    }
+
+    fn to_u16(&self) -> u16 {
+        let bits = [
+            self.in_use,
+            self.forced_rotate,
+            self.thread_specific,
+            self.suppress_use,
+            self.update_table_map_version,
+            self.artificial,
+            self.relay_log,
+            self.ignorable,
+            self.no_filter,
+            self.mts_isolate,
+        ];
+        bits.iter()
+            .enumerate()
+            .fold(0, |acc, (idx, &set)| if set { acc | 1 << idx } else { acc })
+    }
 }
 
 #[derive(Debug, Serialize, PartialEq, Eq, Clone)]
@@ -49,24 +112,32 @@ pub struct Header {
     pub flags: EventFlag,
 }
 
+impl Header {
+    /// typed event type of header
+    pub fn kind(&self) -> EventType {
+        EventType::from_u8(self.event_type)
+    }
+
+    /// header with default values, event_size is computed from body length
+    fn with_body_len(event_type: u8, body_len: usize) -> Self {
+        Header {
+            timestamp: 0,
+            event_type,
+            server_id: 1,
+            event_size: (19 + body_len + 4) as u32,
+            log_pos: 0,
+            flags: EventFlag::default(),
+        }
+    }
+}
+
 pub fn parse_header(input: &[u8]) -> IResult<&[u8], Header> {
     let (i, timestamp) = le_u32(input)?;
     let (i, event_type) = le_u8(i)?;
     let (i, server_id) = le_u32(i)?;
     let (i, event_size) = le_u32(i)?;
     let (i, log_pos) = le_u32(i)?;
-    let (i, flags) = map(le_u16, |f: u16| EventFlag {
-        in_use: (f >> 0) % 2 == 1,
-        forced_rotate: (f >> 1) % 2 == 1,
-        thread_specific: (f >> 2) % 2 == 1,
-        suppress_use: (f >> 3) % 2 == 1,
-        update_table_map_version: (f >> 4) % 2 == 1,
-        artificial: (f >> 5) % 2 == 1,
-        relay_log: (f >> 6) % 2 == 1,
-        ignorable: (f >> 7) % 2 == 1,
-        no_filter: (f >> 8) % 2 == 1,
-        mts_isolate: (f >> 9) % 2 == 1,
-    })(i)?;
+    let (i, flags) = map(le_u16, EventFlag::from_u16)(i)?;
     Ok((
         i,
         Header {
@@ -80,10 +151,67 @@ pub fn parse_header(input: &[u8]) -> IResult<&[u8], Header> {
     ))
 }
 
+/// encode header with event_size computed from body length
+fn encode_header(header: &Header, body_len: usize) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(19 + body_len + 4);
+    buf.extend_from_slice(&header.timestamp.to_le_bytes());
+    buf.push(header.event_type);
+    buf.extend_from_slice(&header.server_id.to_le_bytes());
+    buf.extend_from_slice(&((19 + body_len + 4) as u32).to_le_bytes());
+    buf.extend_from_slice(&header.log_pos.to_le_bytes());
+    buf.extend_from_slice(&header.flags.to_u16().to_le_bytes());
+    buf
+}
+
 pub fn check_start(i: &[u8]) -> IResult<&[u8], &[u8]> {
     tag([254, 98, 105, 110])(i)
 }
 
+/// length of rest part of event once `used` bytes(header and checksum included) are known,
+/// error if event_size is smaller than that
+fn rest_len<'a>(
+    input: &'a [u8],
+    header: &Header,
+    used: u64,
+) -> Result<usize, nom::Err<(&'a [u8], ErrorKind)>> {
+    match (header.event_size as u64).checked_sub(used) {
+        Some(len) => Ok(len as usize),
+        None => {
+            log::error!(
+                "event_size {} is less than {} bytes required",
+                header.event_size,
+                used
+            );
+            Err(nom::Err::Error((input, ErrorKind::LengthValue)))
+        }
+    }
+}
+
+/// split an event into (header, body, checksum) without decoding body
+pub fn event_slices(input: &[u8]) -> IResult<&[u8], (Header, &[u8], u32)> {
+    let (i, header) = parse_header(input)?;
+    let (i, body) = take(rest_len(i, &header, 19 + 4)?)(i)?;
+    let (i, checksum) = le_u32(i)?;
+    Ok((i, (header, body, checksum)))
+}
+
+/// whether body of event type is decoded by parser, types not listed here are
+/// rejected as `ErrorKind::Switch`
+#[cfg(feature = "std")]
+fn is_supported(t: EventType) -> bool {
+    match t {
+        EventType::StartV3
+        | EventType::WriteRowsV1
+        | EventType::UpdateRowsV1
+        | EventType::DeleteRowsV1
+        | EventType::TransactionContext
+        | EventType::XaPrepareLog
+        | EventType::Other(_) => false,
+        EventType::MariaAnnotateRows | EventType::MariaGtid => cfg!(feature = "mariadb"),
+        _ => true,
+    }
+}
+
 #[derive(Debug, Serialize, PartialEq, Clone)]
 pub enum Event {
     // ref: https://dev.mysql.com/doc/internals/en/ignored-events.html#unknown-event
@@ -157,14 +285,14 @@ pub enum Event {
     CreateFile {
         header: Header,
         file_id: u32,
-        block_data: String,
+        block_data: Vec<u8>,
         checksum: u32,
     },
     // ref: https://dev.mysql.com/doc/internals/en/append-block-event.html
     AppendBlock {
         header: Header,
         file_id: u32,
-        block_data: String,
+        block_data: Vec<u8>,
         checksum: u32,
     },
     // ref: https://dev.mysql.com/doc/internals/en/exec-load-event.html
@@ -239,7 +367,7 @@ pub enum Event {
         create_timestamp: u32,
         event_header_length: u8,
         supported_types: Vec<u8>,
-        checksum_alg: u8,
+        checksum_alg: ChecksumAlg,
         checksum: u32,
     },
     XID {
@@ -251,7 +379,7 @@ pub enum Event {
     BeginLoadQuery {
         header: Header,
         file_id: u32,
-        block_data: String,
+        block_data: Vec<u8>,
         checksum: u32,
     },
     ExecuteLoadQueryEvent {
@@ -259,7 +387,7 @@ pub enum Event {
         thread_id: u32,
         execution_time: u32,
         schema_length: u8,
-        error_code: u16,
+        error_code: ErrorCode,
         status_vars_length: u16,
         file_id: u32,
         start_pos: u32,
@@ -274,7 +402,7 @@ pub enum Event {
         header: Header,
         // table_id take 6 bytes in buffer
         table_id: u64,
-        flags: u16,
+        flags: TableMapFlags,
         schema_length: u8,
         schema: String,
         // [00] term sign in layout
@@ -287,6 +415,23 @@ pub enum Event {
         null_bits: Vec<u8>,
         checksum: u32,
     },
+    // source: https://github.com/mysql/mysql-server/blob/5.1/sql/log_event.h
+    // pre-GA rows events of MySQL 5.1, body is kept as raw bytes
+    PreGaWriteRows {
+        header: Header,
+        data: Vec<u8>,
+        checksum: u32,
+    },
+    PreGaUpdateRows {
+        header: Header,
+        data: Vec<u8>,
+        checksum: u32,
+    },
+    PreGaDeleteRows {
+        header: Header,
+        data: Vec<u8>,
+        checksum: u32,
+    },
     // ref: https://dev.mysql.com/doc/internals/en/incident-event.html
     Incident {
         header: Header,
@@ -300,6 +445,13 @@ pub enum Event {
         header: Header,
         checksum: u32,
     },
+    // ref: https://dev.mysql.com/doc/internals/en/ignored-events.html
+    // body layout is unknown, kept as raw bytes
+    Ignorable {
+        header: Header,
+        payload: Vec<u8>,
+        checksum: u32,
+    },
     // ref: https://dev.mysql.com/doc/internals/en/rows-query-event.html
     RowQuery {
         header: Header,
@@ -311,29 +463,73 @@ pub enum Event {
     Gtid {
         header: Header,
         rbr_only: bool,
+        // server uuid and transaction number, e.g. 3e11fa47-71ca-11e1-9e33-c80aa9429562 and 5
         source_id: String,
-        transaction_id: String,
+        transaction_id: i64,
         ts_type: u8,
         last_committed: i64,
         sequence_number: i64,
+        // microseconds since epoch and length of transaction, written since MySQL 8.0
+        original_commit_timestamp: Option<u64>,
+        immediate_commit_timestamp: Option<u64>,
+        transaction_length: Option<u64>,
         checksum: u32,
     },
     AnonymousGtid {
         header: Header,
         rbr_only: bool,
+        // server uuid and transaction number, e.g. 3e11fa47-71ca-11e1-9e33-c80aa9429562 and 5
         source_id: String,
-        transaction_id: String,
+        transaction_id: i64,
         ts_type: u8,
         last_committed: i64,
         sequence_number: i64,
+        // microseconds since epoch and length of transaction, written since MySQL 8.0
+        original_commit_timestamp: Option<u64>,
+        immediate_commit_timestamp: Option<u64>,
+        transaction_length: Option<u64>,
         checksum: u32,
     },
     // source: https://github.com/mysql/mysql-server/blob/a394a7e17744a70509be5d3f1fd73f8779a31424/libbinlogevents/include/control_events.h#L1073-L1103
     PreviousGtids {
         header: Header,
-        // TODO do more specify parse
+        // raw body, split at last 4 bytes
         gtid_sets: Vec<u8>,
         buf_size: u32,
+        gtid_set: gtid::GtidSet,
+        checksum: u32,
+    },
+    // source: https://github.com/mysql/mysql-server/blob/a394a7e17744a70509be5d3f1fd73f8779a31424/libbinlogevents/include/control_events.h
+    // see View_change_event, emitted by group replication
+    ViewChange {
+        header: Header,
+        // fixed 40 bytes, padded with [00]
+        view_id: String,
+        seq_number: u64,
+        cert_info_size: u32,
+        // (key, value) pairs, value is raw encoded gtid set
+        cert_info: Vec<(String, Vec<u8>)>,
+        checksum: u32,
+    },
+    // ref: https://mariadb.com/kb/en/annotate_rows_event/
+    // original statement of following rows events, MariaDB only
+    #[cfg(feature = "mariadb")]
+    MariaAnnotateRows {
+        header: Header,
+        query_text: String,
+        checksum: u32,
+    },
+    // ref: https://mariadb.com/kb/en/gtid_event/
+    // server_id is taken from header, MariaDB only
+    #[cfg(feature = "mariadb")]
+    MariaGtid {
+        header: Header,
+        sequence: u64,
+        domain_id: u32,
+        server_id: u32,
+        flags: u8,
+        // present if FL_GROUP_COMMIT_ID is set
+        commit_id: Option<u64>,
         checksum: u32,
     },
     // source https://github.com/mysql/mysql-server/blob/a394a7e17744a70509be5d3f1fd73f8779a31424/libbinlogevents/include/rows_event.h#L488-L613
@@ -349,6 +545,8 @@ pub enum Event {
         rows: Vec<Vec<ColValues>>,
         checksum: u32,
     },
+    // PartialUpdateRows event of MySQL 8.0 is decoded as UpdateRowsV2 too, with partial
+    // JSON updates applied to before image
     UpdateRowsV2 {
         header: Header,
         // table_id take 6 bytes in buffer
@@ -376,49 +574,971 @@ pub enum Event {
     },
 }
 
+/// options controlling how strictly events are parsed
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ParseOptions {
+    /// error if consumed length of an event doesn't match `header.event_size`
+    pub strict: bool,
+    /// error if `header.event_size` is larger than this, checked before body is read,
+    /// 256 MiB by default
+    pub max_event_size: usize,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            strict: false,
+            max_event_size: 256 << 20,
+        }
+    }
+}
+
+/// flavor of server which wrote the log
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Dialect {
+    MySQL,
+    /// detected by `MariaDB` in server version of FormatDesc event, e.g. `10.5.8-MariaDB-log`
+    MariaDB,
+}
+
+/// stream wide state required to parse events, derived from FormatDesc event
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ParseContext {
+    /// whether events end with a 4 bytes checksum
+    pub has_checksum: bool,
+    /// (major, minor, patch) of server which wrote the log, for slices parsed
+    /// without their FormatDesc event. unknown version is treated as the latest one
+    pub server_version: Option<(u8, u8, u8)>,
+    /// MySQL unless FormatDesc event says otherwise
+    pub dialect: Dialect,
+    /// `event_header_length` of FormatDesc event, bytes following the known 19 bytes of
+    /// header are skipped
+    pub header_len: u8,
+    /// limits and checks applied to every event
+    pub options: ParseOptions,
+}
+
+impl Default for ParseContext {
+    fn default() -> Self {
+        Self {
+            has_checksum: true,
+            server_version: None,
+            dialect: Dialect::MySQL,
+            header_len: 19,
+            options: ParseOptions::default(),
+        }
+    }
+}
+
+impl ParseContext {
+    /// follow `checksum_alg`, server version and header length of FormatDesc event,
+    /// other events are ignored
+    pub fn update(&mut self, event: &Event) {
+        if let Some(alg) = event.checksum_alg() {
+            self.has_checksum = alg != ChecksumAlg::Off;
+        }
+        if let Event::FormatDesc {
+            mysql_server_version,
+            event_header_length,
+            ..
+        } = event
+        {
+            self.header_len = *event_header_length;
+            if let Some(version) = parse_server_version(mysql_server_version) {
+                self.server_version = Some(version);
+            }
+            self.dialect = if mysql_server_version.contains("MariaDB") {
+                Dialect::MariaDB
+            } else {
+                Dialect::MySQL
+            };
+        }
+    }
+
+    /// whether MySQL server is older than `version`, false if server version is unknown
+    ///
+    /// MariaDB versions are numbered differently, e.g. 10.x, they are never compared
+    fn before(&self, version: (u8, u8, u8)) -> bool {
+        self.dialect == Dialect::MySQL && matches!(self.server_version, Some(v) if v < version)
+    }
+}
+
+/// leading `major.minor.patch` of version string, e.g. `8.0.21-log`
+fn parse_server_version(version: &str) -> Option<(u8, u8, u8)> {
+    let mut parts = version.splitn(3, '.').map(|part| {
+        part.split(|c: char| !c.is_ascii_digit())
+            .next()
+            .and_then(|n| n.parse::<u8>().ok())
+    });
+    Some((parts.next()??, parts.next()??, parts.next()??))
+}
+
 impl Event {
+    /// parse an event, rows events are decoded with table maps seen by previous calls
+    #[cfg(feature = "std")]
     pub fn parse<'a>(input: &'a [u8]) -> IResult<&'a [u8], Event> {
-        let (input, header) = parse_header(input)?;
-        match header.event_type {
-            0x00 => parse_unknown(input, header),
-            0x02 => parse_query(input, header),
-            0x03 => parse_stop(input, header),
-            0x04 => parse_rotate(input, header),
-            0x05 => parse_intvar(input, header),
-            0x06 => parse_load(input, header),
-            0x07 => parse_slave(input, header),
-            0x08 => parse_create_file(input, header),
-            0x09 => parse_append_block(input, header),
-            0x0a => parse_exec_load(input, header),
-            0x0b => parse_delete_file(input, header),
-            0x0c => parse_new_load(input, header),
-            0x0d => parse_rand(input, header),
-            0x0e => parse_user_var(input, header),
-            0x0f => parse_format_desc(input, header),
-            0x10 => parse_xid(input, header),
-            0x11 => parse_begin_load_query(input, header),
-            0x12 => parse_execute_load_query(input, header),
-            0x13 => parse_table_map(input, header),
-            0x1a => parse_incident(input, header),
-            0x1b => parse_heartbeat(input, header),
-            0x1d => parse_row_query(input, header),
-            0x14..=0x19 => unreachable!(),
-            0x1e => parse_write_rows_v2(input, header),
-            0x1f => parse_update_rows_v2(input, header),
-            0x20 => parse_delete_rows_v2(input, header),
-            0x21 => parse_gtid(input, header),
-            0x22 => parse_anonymous_gtid(input, header),
-            0x23 => parse_previous_gtids(input, header),
-            t @ _ => {
-                log::error!("unexpected event type: {:x}", t);
-                unreachable!();
+        Self::parse_with_options(input, ParseOptions::default())
+    }
+
+    /// same as `parse`, but event of type without parser is passed to `handler` with its
+    /// type byte and body(checksum excluded), and returned as `Event::Unknown` with its header
+    #[cfg(feature = "std")]
+    pub fn parse_with_handler<'a>(
+        input: &'a [u8],
+        handler: &mut impl FnMut(u8, &[u8]),
+    ) -> IResult<&'a [u8], Event> {
+        let (_, header) = parse_header(input)?;
+        if is_supported(header.kind()) {
+            return Self::parse(input);
+        }
+        let (i, (header, body, checksum)) = event_slices(input)?;
+        handler(header.event_type, body);
+        Ok((i, Event::Unknown { header, checksum }))
+    }
+
+    /// same as `parse`, in strict mode event which consumes more or less bytes
+    /// than its `event_size` is rejected
+    #[cfg(feature = "std")]
+    pub fn parse_with_options<'a>(
+        input: &'a [u8],
+        options: ParseOptions,
+    ) -> IResult<&'a [u8], Event> {
+        let ctx = ParseContext {
+            options,
+            ..Default::default()
+        };
+        let mut cache = TABLE_MAP.lock().unwrap_or_else(|e| e.into_inner());
+        Self::parse_with_context(input, &ctx, &mut cache)
+    }
+
+    /// check bytes of body consumed by parser against body length in header,
+    /// i.e. `header.event_size - 19`
+    pub fn validate_size(&self, consumed: usize) -> Result<(), crate::Error> {
+        let event_size = self.header().event_size as usize;
+        match event_size.checked_sub(19) {
+            Some(expected) if expected == consumed => Ok(()),
+            expected => Err(crate::Error::SizeMismatch {
+                expected: expected.unwrap_or(0),
+                actual: consumed,
+            }),
+        }
+    }
+
+    /// parse an event and return bytes consumed by parser,
+    /// mismatch between consumed bytes and `header.event_size` is reported as error
+    #[cfg(feature = "std")]
+    pub fn parse_checked<'a>(input: &'a [u8]) -> Result<(&'a [u8], Event, usize), crate::Error> {
+        let mut cache = TABLE_MAP.lock().unwrap_or_else(|e| e.into_inner());
+        let (i, event) = Self::parse_verbose(input, &ParseContext::default(), &mut cache)
+            .map_err(|e| crate::Error::from_nom(e, 0))?;
+        let actual = input.len() - i.len();
+        let expected = event.header().event_size as usize;
+        if actual != expected {
+            return Err(crate::Error::SizeMismatch { expected, actual });
+        }
+        Ok((i, event, actual))
+    }
+
+    /// parse input holding exactly one event, e.g. payload of a replication packet
+    ///
+    /// input shorter than `event_size` is reported as truncated, bytes left after
+    /// the event are reported as error too
+    #[cfg(feature = "std")]
+    pub fn parse_exact(input: &[u8]) -> Result<Event, crate::Error> {
+        if let Ok((_, header)) = parse_header(input) {
+            if (header.event_size as usize) > input.len() {
+                return Err(crate::Error::Truncated { offset: 0 });
+            }
+        }
+        let (i, event, _) = Self::parse_checked(input)?;
+        if !i.is_empty() {
+            return Err(crate::Error::TrailingBytes { len: i.len() });
+        }
+        Ok(event)
+    }
+
+    /// parse an event, TableMap events are recorded into cache and
+    /// rows events are decoded against it
+    pub fn parse_with_cache<'a>(
+        input: &'a [u8],
+        cache: &mut TableMapCache,
+    ) -> IResult<&'a [u8], Event> {
+        plain(Self::parse_event(input, &ParseContext::default(), cache))
+    }
+
+    fn parse_event<'a>(
+        input: &'a [u8],
+        ctx: &ParseContext,
+        cache: &mut TableMapCache,
+    ) -> VerboseResult<'a, Event> {
+        let (input, header) = verbose(parse_header(input))?;
+        #[cfg(feature = "tracing")]
+        let span = tracing::debug_span!(
+            "parse_event",
+            event_type = ?header.kind(),
+            event_size = header.event_size,
+            log_pos = header.log_pos
+        );
+        #[cfg(feature = "tracing")]
+        let _enter = span.enter();
+        let ret = Self::parse_body(input, header, ctx, cache);
+        #[cfg(feature = "tracing")]
+        {
+            if let Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) = &ret {
+                let kind = e.errors.first().map(|(_, kind)| kind);
+                tracing::error!(?kind, "failed to parse event");
             }
         }
+        ret
     }
 
+    /// dispatch event body to parser of its type
+    fn parse_body<'a>(
+        input: &'a [u8],
+        header: Header,
+        ctx: &ParseContext,
+        cache: &mut TableMapCache,
+    ) -> VerboseResult<'a, Event> {
+        match header.kind() {
+            EventType::Unknown => verbose(parse_unknown(input, header)),
+            EventType::Query => verbose(parse_query(input, header)),
+            EventType::Stop => verbose(parse_stop(input, header)),
+            EventType::Rotate => verbose(parse_rotate(input, header)),
+            EventType::IntVar => verbose(parse_intvar(input, header)),
+            EventType::Load => verbose(parse_load(input, header)),
+            EventType::Slave => verbose(parse_slave(input, header)),
+            EventType::CreateFile => verbose(parse_create_file(input, header)),
+            EventType::AppendBlock => verbose(parse_append_block(input, header)),
+            EventType::ExecLoad => verbose(parse_exec_load(input, header)),
+            EventType::DeleteFile => verbose(parse_delete_file(input, header)),
+            EventType::NewLoad => verbose(parse_new_load(input, header)),
+            EventType::Rand => verbose(parse_rand(input, header)),
+            EventType::UserVar => verbose(parse_user_var(input, header)),
+            EventType::FormatDescription => verbose(parse_format_desc(input, header)),
+            EventType::Xid => verbose(parse_xid(input, header)),
+            EventType::BeginLoadQuery => verbose(parse_begin_load_query(input, header)),
+            EventType::ExecuteLoadQuery => verbose(parse_execute_load_query(input, header)),
+            EventType::TableMap => {
+                add_context("parse_table_map", parse_table_map(input, header, cache))
+            }
+            EventType::Incident => verbose(parse_incident(input, header)),
+            EventType::Heartbeat => verbose(parse_heartbeat(input, header)),
+            EventType::Ignorable => verbose(parse_ignorable(input, header)),
+            EventType::RowsQuery => verbose(parse_row_query(input, header)),
+            EventType::PreGaWriteRows | EventType::PreGaUpdateRows | EventType::PreGaDeleteRows => {
+                verbose(parse_pre_ga_rows(input, header))
+            }
+            EventType::WriteRowsV1 | EventType::UpdateRowsV1 | EventType::DeleteRowsV1 => {
+                log::error!("rows event v1 is not supported yet");
+                Err(nom::Err::Error(VerboseError::from_error_kind(
+                    input,
+                    ErrorKind::Switch,
+                )))
+            }
+            EventType::WriteRowsV2 => add_context(
+                "parse_write_rows_v2",
+                parse_write_rows_v2(input, header, cache),
+            ),
+            EventType::UpdateRowsV2 | EventType::PartialUpdateRows => add_context(
+                "parse_update_rows_v2",
+                parse_update_rows_v2(input, header, cache),
+            ),
+            EventType::DeleteRowsV2 => add_context(
+                "parse_delete_rows_v2",
+                parse_delete_rows_v2(input, header, cache),
+            ),
+            EventType::Gtid => verbose(parse_gtid(input, header, ctx)),
+            EventType::AnonymousGtid => verbose(parse_anonymous_gtid(input, header, ctx)),
+            EventType::PreviousGtids => verbose(parse_previous_gtids(input, header)),
+            EventType::ViewChange => verbose(parse_view_change(input, header)),
+            #[cfg(feature = "mariadb")]
+            EventType::MariaAnnotateRows => verbose(parse_maria_annotate_rows(input, header)),
+            #[cfg(feature = "mariadb")]
+            EventType::MariaGtid => verbose(parse_maria_gtid(input, header)),
+            t => {
+                log::error!("unexpected event type: {:x}", t.as_u8());
+                Err(nom::Err::Error(VerboseError::from_error_kind(
+                    input,
+                    ErrorKind::Switch,
+                )))
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
     pub fn from_bytes<'a>(input: &'a [u8]) -> IResult<&'a [u8], Vec<Event>> {
         let (i, _) = check_start(input)?;
-        many1(Self::parse)(i)
+        nom::multi::many1(Self::parse)(i)
+    }
+
+    /// parse an event of a stream described by `ctx`, `ctx.options` are applied as
+    /// in `parse_with_options`
+    ///
+    /// without checksum, `checksum` of event is 0 and trailing bytes of event are
+    /// never taken as checksum, header bytes beyond the known 19 bytes are skipped
+    pub fn parse_with_context<'a>(
+        input: &'a [u8],
+        ctx: &ParseContext,
+        cache: &mut TableMapCache,
+    ) -> IResult<&'a [u8], Event> {
+        plain(Self::parse_verbose(input, ctx, cache))
+    }
+
+    /// `parse_with_context` telling which parser and field failed in error
+    pub(crate) fn parse_verbose<'a>(
+        input: &'a [u8],
+        ctx: &ParseContext,
+        cache: &mut TableMapCache,
+    ) -> VerboseResult<'a, Event> {
+        let (_, header) = verbose(parse_header(input))?;
+        if header.event_size as usize > ctx.options.max_event_size {
+            log::error!(
+                "event_size {} exceeds max_event_size {}",
+                header.event_size,
+                ctx.options.max_event_size
+            );
+            return Err(nom::Err::Error(VerboseError::from_error_kind(
+                input,
+                ErrorKind::TooLarge,
+            )));
+        }
+        let (i, event) = Self::parse_framed(input, &header, ctx, cache)?;
+        if ctx.options.strict {
+            let consumed = input.len() - i.len();
+            if let Err(crate::Error::SizeMismatch { expected, actual }) =
+                event.validate_size(consumed.saturating_sub(19))
+            {
+                log::error!(
+                    "event body is {} bytes, but parser consumed {}",
+                    expected,
+                    actual
+                );
+                return Err(nom::Err::Error(VerboseError::from_error_kind(
+                    input,
+                    ErrorKind::LengthValue,
+                )));
+            }
+        }
+        Ok((i, event))
+    }
+
+    /// parse event of `header`, reframed to what parsers expect if `ctx` says event has
+    /// no checksum or a longer header
+    fn parse_framed<'a>(
+        input: &'a [u8],
+        header: &Header,
+        ctx: &ParseContext,
+        cache: &mut TableMapCache,
+    ) -> VerboseResult<'a, Event> {
+        let format_desc = header.kind() == EventType::FormatDescription;
+        // FormatDesc always carries checksum_alg and checksum, even if checksum is off,
+        // it's written before header length is known, so its header is never extended
+        let add_checksum = !ctx.has_checksum && !format_desc;
+        let extra_header = if format_desc {
+            0
+        } else {
+            (ctx.header_len as usize).saturating_sub(19)
+        };
+        if !add_checksum && extra_header == 0 {
+            return Self::parse_event(input, ctx, cache);
+        }
+        let size = header.event_size as usize;
+        if size < 19 + extra_header || input.len() < size {
+            return Err(nom::Err::Error(VerboseError::from_error_kind(
+                input,
+                ErrorKind::Eof,
+            )));
+        }
+        // parsers expect 19 bytes header and always read a trailing checksum, feed them
+        // a copy without extra header bytes and with a zeroed checksum
+        let mut buf = Vec::with_capacity(size + 4);
+        buf.extend_from_slice(&input[..19]);
+        buf.extend_from_slice(&input[19 + extra_header..size]);
+        if add_checksum {
+            buf.extend_from_slice(&[0; 4]);
+        }
+        let len = buf.len() as u32;
+        buf[9..13].copy_from_slice(&len.to_le_bytes());
+        // errors point into copy, move them to start of input
+        let mut event = match Self::parse_event(&buf, ctx, cache) {
+            Ok((_, event)) => event,
+            Err(e) => {
+                return Err(e.map(|e| VerboseError {
+                    errors: e
+                        .errors
+                        .into_iter()
+                        .map(|(_, kind)| (input, kind))
+                        .collect(),
+                }))
+            }
+        };
+        event.header_mut().event_size = size as u32;
+        Ok((&input[size..], event))
+    }
+
+    /// common header of event
+    pub fn header(&self) -> &Header {
+        match self {
+            Event::Unknown { header, .. }
+            | Event::Query { header, .. }
+            | Event::Stop { header, .. }
+            | Event::Rotate { header, .. }
+            | Event::IntVar { header, .. }
+            | Event::Load { header, .. }
+            | Event::Slave { header, .. }
+            | Event::CreateFile { header, .. }
+            | Event::AppendBlock { header, .. }
+            | Event::ExecLoad { header, .. }
+            | Event::DeleteFile { header, .. }
+            | Event::NewLoad { header, .. }
+            | Event::Rand { header, .. }
+            | Event::UserVar { header, .. }
+            | Event::FormatDesc { header, .. }
+            | Event::XID { header, .. }
+            | Event::BeginLoadQuery { header, .. }
+            | Event::ExecuteLoadQueryEvent { header, .. }
+            | Event::TableMap { header, .. }
+            | Event::PreGaWriteRows { header, .. }
+            | Event::PreGaUpdateRows { header, .. }
+            | Event::PreGaDeleteRows { header, .. }
+            | Event::Incident { header, .. }
+            | Event::Heartbeat { header, .. }
+            | Event::Ignorable { header, .. }
+            | Event::RowQuery { header, .. }
+            | Event::Gtid { header, .. }
+            | Event::AnonymousGtid { header, .. }
+            | Event::PreviousGtids { header, .. }
+            | Event::ViewChange { header, .. }
+            | Event::WriteRowsV2 { header, .. }
+            | Event::UpdateRowsV2 { header, .. }
+            | Event::DeleteRowsV2 { header, .. } => header,
+            #[cfg(feature = "mariadb")]
+            Event::MariaAnnotateRows { header, .. } | Event::MariaGtid { header, .. } => header,
+        }
+    }
+
+    fn header_mut(&mut self) -> &mut Header {
+        match self {
+            Event::Unknown { header, .. }
+            | Event::Query { header, .. }
+            | Event::Stop { header, .. }
+            | Event::Rotate { header, .. }
+            | Event::IntVar { header, .. }
+            | Event::Load { header, .. }
+            | Event::Slave { header, .. }
+            | Event::CreateFile { header, .. }
+            | Event::AppendBlock { header, .. }
+            | Event::ExecLoad { header, .. }
+            | Event::DeleteFile { header, .. }
+            | Event::NewLoad { header, .. }
+            | Event::Rand { header, .. }
+            | Event::UserVar { header, .. }
+            | Event::FormatDesc { header, .. }
+            | Event::XID { header, .. }
+            | Event::BeginLoadQuery { header, .. }
+            | Event::ExecuteLoadQueryEvent { header, .. }
+            | Event::TableMap { header, .. }
+            | Event::PreGaWriteRows { header, .. }
+            | Event::PreGaUpdateRows { header, .. }
+            | Event::PreGaDeleteRows { header, .. }
+            | Event::Incident { header, .. }
+            | Event::Heartbeat { header, .. }
+            | Event::Ignorable { header, .. }
+            | Event::RowQuery { header, .. }
+            | Event::Gtid { header, .. }
+            | Event::AnonymousGtid { header, .. }
+            | Event::PreviousGtids { header, .. }
+            | Event::ViewChange { header, .. }
+            | Event::WriteRowsV2 { header, .. }
+            | Event::UpdateRowsV2 { header, .. }
+            | Event::DeleteRowsV2 { header, .. } => header,
+            #[cfg(feature = "mariadb")]
+            Event::MariaAnnotateRows { header, .. } | Event::MariaGtid { header, .. } => header,
+        }
+    }
+
+    /// deterministic hash of raw event of a stream described by `ctx`, same event read
+    /// from overlapping captures hashes equally
+    ///
+    /// event type, server_id and body are hashed, timestamp, log_pos, flags, event_size,
+    /// header bytes beyond the known 19 bytes and checksum are not, so re-encoding of an
+    /// event with checksum turned off doesn't change its hash. None if input is shorter
+    /// than event_size
+    pub fn content_hash(input: &[u8], ctx: &ParseContext) -> Option<u64> {
+        let (_, header) = parse_header(input).ok()?;
+        let size = header.event_size as usize;
+        let input = input.get(..size)?;
+        // see `parse_with_context` for framing of FormatDesc
+        let format_desc = header.kind() == EventType::FormatDescription;
+        let start = if format_desc {
+            19
+        } else {
+            (ctx.header_len as usize).max(19)
+        };
+        let end = if ctx.has_checksum || format_desc {
+            size.checked_sub(4)?
+        } else {
+            size
+        };
+        let body = input.get(start..end)?;
+        let mut hasher = Fnv64::default();
+        hasher.write(&[header.event_type]);
+        hasher.write(&header.server_id.to_le_bytes());
+        hasher.write(body);
+        Some(hasher.0)
+    }
+
+    /// position of next event in binlog file, i.e. `header.log_pos`
+    ///
+    /// this is the position a replica records after applying event, for Stop event
+    /// it's the end of file
+    pub fn next_position(&self) -> u32 {
+        self.header().log_pos
+    }
+
+    /// ordering key of event in binlog with sequence `file`, see `PositionKey::assign`
+    /// to track file through Rotate events
+    pub fn position_key(&self, file: u32) -> PositionKey {
+        PositionKey {
+            file,
+            pos: self.header().log_pos,
+        }
+    }
+
+    /// table_id of TableMap and rows events
+    pub fn table_id(&self) -> Option<u64> {
+        match self {
+            Event::TableMap { table_id, .. }
+            | Event::WriteRowsV2 { table_id, .. }
+            | Event::UpdateRowsV2 { table_id, .. }
+            | Event::DeleteRowsV2 { table_id, .. } => Some(*table_id),
+            _ => None,
+        }
+    }
+
+    /// (schema, table) of TableMap event, and default schema of Query events
+    ///
+    /// rows events only carry table_id, look it up in TableMapCache instead
+    pub fn schema_and_table(&self) -> Option<(&str, Option<&str>)> {
+        match self {
+            Event::TableMap {
+                schema, table_name, ..
+            } => Some((schema, Some(table_name))),
+            Event::Query { schema, .. } | Event::ExecuteLoadQueryEvent { schema, .. } => {
+                Some((schema, None))
+            }
+            _ => None,
+        }
+    }
+
+    /// text carried by event, e.g. sql, schema and table names, for searching
+    /// without matching every variant
+    pub fn text_fields(&self) -> Vec<&str> {
+        match self {
+            Event::Query { schema, query, .. }
+            | Event::ExecuteLoadQueryEvent { schema, query, .. } => vec![query, schema],
+            Event::Rotate { next_binlog, .. } => vec![next_binlog],
+            Event::Load {
+                field_names,
+                table_name,
+                schema_name,
+                file_name,
+                ..
+            }
+            | Event::NewLoad {
+                field_names,
+                table_name,
+                schema_name,
+                file_name,
+                ..
+            } => {
+                let mut fields = vec![schema_name.as_str(), table_name, file_name];
+                fields.extend(field_names.iter().map(|s| s.as_str()));
+                fields
+            }
+            Event::UserVar { name, .. } => vec![name],
+            Event::FormatDesc {
+                mysql_server_version,
+                ..
+            } => vec![mysql_server_version],
+            Event::TableMap {
+                schema, table_name, ..
+            } => vec![schema, table_name],
+            Event::Incident { message, .. } => vec![message],
+            Event::RowQuery { query_text, .. } => vec![query_text],
+            Event::ViewChange { view_id, .. } => vec![view_id],
+            #[cfg(feature = "mariadb")]
+            Event::MariaAnnotateRows { query_text, .. } => vec![query_text],
+            _ => vec![],
+        }
+    }
+
+    /// whether event is a row modification event
+    pub fn is_rows_event(&self) -> bool {
+        match self {
+            Event::PreGaWriteRows { .. }
+            | Event::PreGaUpdateRows { .. }
+            | Event::PreGaDeleteRows { .. }
+            | Event::WriteRowsV2 { .. }
+            | Event::UpdateRowsV2 { .. }
+            | Event::DeleteRowsV2 { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// whether event is a Query event of DDL statement, eg. CREATE, ALTER and DROP
+    pub fn is_ddl(&self) -> bool {
+        match self {
+            Event::Query { query, .. } => query::is_ddl(query),
+            _ => false,
+        }
+    }
+
+    /// whether event is the last one of a statement: rows event with `end_of_stmt` flag,
+    /// Query event, or event ending a transaction
+    pub fn ends_statement(&self) -> bool {
+        match self {
+            Event::WriteRowsV2 { flags, .. }
+            | Event::UpdateRowsV2 { flags, .. }
+            | Event::DeleteRowsV2 { flags, .. } => flags.end_of_stmt,
+            Event::Query { .. } => true,
+            event => event.ends_transaction(),
+        }
+    }
+
+    /// whether event is the last one of a transaction: XID event, or Query event of
+    /// COMMIT, ROLLBACK or DDL statement, which commits implicitly
+    pub fn ends_transaction(&self) -> bool {
+        match self {
+            Event::XID { .. } => true,
+            Event::Query { query, .. } => matches!(
+                query::StatementKind::classify(query),
+                query::StatementKind::Commit
+                    | query::StatementKind::Rollback
+                    | query::StatementKind::Ddl
+            ),
+            _ => false,
+        }
+    }
+
+    /// columns of TableMap event with their metadata and nullability
+    pub fn table_schema(&self) -> Option<table_map::TableSchema> {
+        match self {
+            Event::TableMap {
+                schema,
+                table_name,
+                columns_type,
+                null_bits,
+                ..
+            } => Some(table_map::TableSchema {
+                schema: schema.clone(),
+                table: table_name.clone(),
+                columns: columns_type
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, col)| table_map::ColumnDef {
+                        column_type: *col,
+                        metadata: col.meta_bytes(),
+                        nullable: null_bits
+                            .get(idx / 8)
+                            .is_some_and(|b| (b >> (idx % 8)) & 1 == 1),
+                    })
+                    .collect(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// pair values of rows event with column types of `table`, e.g. from `table_schema`
+    /// of its TableMap, return None if event is not a rows event
+    ///
+    /// NULL in not nullable column and value of another type are reported as warnings,
+    /// they are likely caused by stream desync or parser bug
+    ///
+    /// row image holds columns set in image bits only, e.g. primary key of minimal image
+    pub fn decode_rows_validated(
+        &self,
+        table: &table_map::TableSchema,
+    ) -> Option<rows::ValidatedRows> {
+        let (rows, column_count, images): (_, _, [&[u8]; 2]) = match self {
+            Event::WriteRowsV2 {
+                rows,
+                column_count,
+                inserted_image_bits,
+                ..
+            } => (
+                rows,
+                column_count,
+                [inserted_image_bits, inserted_image_bits],
+            ),
+            Event::UpdateRowsV2 {
+                rows,
+                column_count,
+                before_image_bits,
+                after_image_bits,
+                ..
+            } => (rows, column_count, [before_image_bits, after_image_bits]),
+            Event::DeleteRowsV2 {
+                rows,
+                column_count,
+                deleted_image_bits,
+                ..
+            } => (rows, column_count, [deleted_image_bits, deleted_image_bits]),
+            _ => return None,
+        };
+        let mut warnings = vec![];
+        let rows = rows
+            .iter()
+            .enumerate()
+            .map(|(row_idx, row)| {
+                let present: Vec<_> =
+                    rows::present_columns(images[row_idx % 2], *column_count).collect();
+                if row.len() != present.len() {
+                    warnings.push(rows::RowWarning::ColumnCount {
+                        row: row_idx,
+                        expected: present.len(),
+                        actual: row.len(),
+                    });
+                }
+                row.iter()
+                    .zip(present)
+                    .filter_map(|(value, col_idx)| {
+                        Some((value, col_idx, table.columns.get(col_idx)?))
+                    })
+                    .map(|(value, col_idx, col)| {
+                        if *value == ColValues::Null && !col.nullable {
+                            warnings.push(rows::RowWarning::UnexpectedNull {
+                                row: row_idx,
+                                column: col_idx,
+                            });
+                        } else if !value.matches_type(&col.column_type) {
+                            warnings.push(rows::RowWarning::TypeMismatch {
+                                row: row_idx,
+                                column: col_idx,
+                            });
+                        }
+                        rows::TypedValue {
+                            column_type: col.column_type,
+                            value: value.clone(),
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        Some(rows::ValidatedRows { rows, warnings })
+    }
+
+    /// post header length of given event type, recorded in `supported_types` of FormatDesc event
+    ///
+    /// return None if event is not FormatDesc or event type is out of range
+    pub fn post_header_len(&self, event_type: u8) -> Option<u8> {
+        match self {
+            // array starts from event type 1
+            Event::FormatDesc {
+                supported_types, ..
+            } => supported_types
+                .get((event_type as usize).checked_sub(1)?)
+                .copied(),
+            _ => None,
+        }
+    }
+
+    /// `domain-server-sequence` position of MariaDB GTID event
+    #[cfg(feature = "mariadb")]
+    pub fn maria_gtid(&self) -> Option<MariaGtidId> {
+        match self {
+            Event::MariaGtid {
+                domain_id,
+                server_id,
+                sequence,
+                ..
+            } => Some(MariaGtidId {
+                domain_id: *domain_id,
+                server_id: *server_id,
+                sequence: *sequence,
+            }),
+            _ => None,
+        }
+    }
+
+    /// GTIDs executed before current binlog, recorded by PreviousGtids event
+    pub fn previous_gtids(&self) -> Option<&gtid::GtidSet> {
+        match self {
+            Event::PreviousGtids { gtid_set, .. } => Some(gtid_set),
+            _ => None,
+        }
+    }
+
+    /// checksum algorithm of following events, only FormatDesc event carries it
+    pub fn checksum_alg(&self) -> Option<ChecksumAlg> {
+        match self {
+            Event::FormatDesc { checksum_alg, .. } => Some(*checksum_alg),
+            _ => None,
+        }
+    }
+
+    /// sql text of Query event along with schema and statement kind,
+    /// BEGIN and COMMIT are not classified as DDL or DML
+    pub fn as_statement(&self) -> Option<query::Statement> {
+        match self {
+            Event::Query { schema, query, .. } => Some(query::Statement {
+                schema: schema.clone(),
+                sql: query.clone(),
+                kind: query::StatementKind::classify(query),
+            }),
+            _ => None,
+        }
+    }
+
+    /// statement timestamp of Query event in microseconds, combines header timestamp
+    /// with Q_MICROSECONDS status var
+    ///
+    /// return None if event is not a Query event or status var is absent
+    pub fn query_timestamp_micros(&self) -> Option<u64> {
+        match self {
+            Event::Query {
+                header,
+                status_vars,
+                ..
+            } => status_vars.iter().find_map(|var| match var {
+                query::QueryStatusVar::Q_MICROSECONDS(micros) => {
+                    Some(header.timestamp as u64 * 1_000_000 + *micros as u64)
+                }
+                _ => None,
+            }),
+            _ => None,
+        }
+    }
+
+    /// value of DECIMAL UserVar event as text, e.g. `12.34`
+    ///
+    /// value of such event is precision and scale followed by binary decimal
+    pub fn user_var_decimal(&self) -> Option<String> {
+        match self {
+            Event::UserVar {
+                d_type: Some(UserVarType::DECIMAL),
+                value: Some(value),
+                ..
+            } if value.len() > 2 => crate::decimal::decode(&value[2..], value[0], value[1]),
+            _ => None,
+        }
+    }
+
+    /// build a Query event with default header, mainly used in tests
+    pub fn query(schema: &str, query: &str) -> Event {
+        let body_len = 4 + 4 + 1 + 2 + 2 + schema.len() + 1 + query.len();
+        Event::Query {
+            header: Header::with_body_len(0x02, body_len),
+            slave_proxy_id: 0,
+            execution_time: 0,
+            schema_length: schema.len() as u8,
+            error_code: 0,
+            status_vars_length: 0,
+            status_vars: vec![],
+            schema: schema.to_string(),
+            query: query.to_string(),
+            checksum: 0,
+        }
+    }
+
+    /// build a XID event with default header, mainly used in tests
+    pub fn xid(xid: u64) -> Event {
+        Event::XID {
+            header: Header::with_body_len(0x10, 8),
+            xid,
+            checksum: 0,
+        }
+    }
+
+    /// build a TableMap event with default header, all columns are nullable
+    pub fn table_map(
+        table_id: u64,
+        schema: &str,
+        table_name: &str,
+        columns_type: Vec<ColTypes>,
+    ) -> Event {
+        let column_count = columns_type.len() as u64;
+        let meta_len: usize = columns_type.iter().map(|c| c.meta().1 as usize).sum();
+        let null_bits = vec![0xff; (column_count as usize + 7) / 8];
+        let body_len = 6
+            + 2
+            + 1
+            + schema.len()
+            + 1
+            + 1
+            + table_name.len()
+            + 1
+            + int_lenenc_len(column_count)
+            + column_count as usize
+            + int_lenenc_len(meta_len as u64)
+            + meta_len
+            + null_bits.len();
+        Event::TableMap {
+            header: Header::with_body_len(0x13, body_len),
+            table_id,
+            flags: TableMapFlags::from_u16(0),
+            schema_length: schema.len() as u8,
+            schema: schema.to_string(),
+            table_name_length: table_name.len() as u8,
+            table_name: table_name.to_string(),
+            column_count,
+            columns_type,
+            null_bits,
+            checksum: 0,
+        }
+    }
+
+    /// serialize event back to binlog bytes, checksum is recomputed
+    ///
+    /// return None if event type is not supported yet
+    pub fn to_bytes(&self) -> Option<Vec<u8>> {
+        let (header, body) = match self {
+            Event::Stop { header, .. } | Event::Heartbeat { header, .. } => (header, vec![]),
+            Event::Rotate {
+                header,
+                position,
+                next_binlog,
+                ..
+            } => {
+                let mut body = position.to_le_bytes().to_vec();
+                body.extend_from_slice(next_binlog.as_bytes());
+                (header, body)
+            }
+            Event::XID { header, xid, .. } => (header, xid.to_le_bytes().to_vec()),
+            Event::Query {
+                header,
+                slave_proxy_id,
+                execution_time,
+                error_code,
+                status_vars,
+                schema,
+                query,
+                ..
+            } => {
+                let vars: Vec<u8> = status_vars
+                    .iter()
+                    .flat_map(query::encode_status_var)
+                    .collect();
+                let mut body = vec![];
+                body.extend_from_slice(&slave_proxy_id.to_le_bytes());
+                body.extend_from_slice(&execution_time.to_le_bytes());
+                body.push(schema.len() as u8);
+                body.extend_from_slice(&error_code.to_le_bytes());
+                body.extend_from_slice(&(vars.len() as u16).to_le_bytes());
+                body.extend(vars);
+                body.extend_from_slice(schema.as_bytes());
+                body.push(0x00);
+                body.extend_from_slice(query.as_bytes());
+                (header, body)
+            }
+            _ => return None,
+        };
+        let mut buf = encode_header(header, body.len());
+        buf.extend(body);
+        if let Some(checksum) = ChecksumAlg::Crc32.compute(&buf) {
+            buf.extend_from_slice(&checksum.to_le_bytes());
+        }
+        Some(buf)
     }
 }
 
@@ -427,23 +1547,74 @@ pub enum IntVarEventType {
     InvalidIntEvent,
     LastInsertIdEvent,
     InsertIdEvent,
+    // undefined subtype, eg. corrupted byte
+    Unknown(u8),
 }
 
 #[derive(Debug, Serialize, PartialEq, Eq, Clone)]
 pub struct EmptyFlags {
-    field_term_empty: bool,
-    enclosed_empty: bool,
-    line_term_empty: bool,
-    line_start_empty: bool,
-    escape_empty: bool,
+    pub field_term_empty: bool,
+    pub enclosed_empty: bool,
+    pub line_term_empty: bool,
+    pub line_start_empty: bool,
+    pub escape_empty: bool,
+}
+
+impl EmptyFlags {
+    /// decode `empty_flags` of Load event
+    pub fn from_u8(flags: u8) -> Self {
+        EmptyFlags {
+            field_term_empty: flags & 1 == 1,
+            enclosed_empty: (flags >> 1) & 1 == 1,
+            line_term_empty: (flags >> 2) & 1 == 1,
+            line_start_empty: (flags >> 3) & 1 == 1,
+            escape_empty: (flags >> 4) & 1 == 1,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, PartialEq, Eq, Clone)]
 pub struct OptFlags {
-    dump_file: bool,
-    opt_enclosed: bool,
-    replace: bool,
-    ignore: bool,
+    pub dump_file: bool,
+    pub opt_enclosed: bool,
+    pub replace: bool,
+    pub ignore: bool,
+}
+
+impl OptFlags {
+    /// decode `opt_flags` of Load and NewLoad events
+    pub fn from_u8(flags: u8) -> Self {
+        OptFlags {
+            dump_file: flags & 1 == 1,
+            opt_enclosed: (flags >> 1) & 1 == 1,
+            replace: (flags >> 2) & 1 == 1,
+            ignore: (flags >> 3) & 1 == 1,
+        }
+    }
+}
+
+/// flags of TableMap event
+///
+/// ref: https://github.com/mysql/mysql-server/blob/8.0/libbinlogevents/include/rows_event.h `enum_flag`
+#[derive(Debug, Serialize, PartialEq, Eq, Clone)]
+pub struct TableMapFlags {
+    // TM_BIT_LEN_EXACT_F
+    pub bit_len_exact: bool,
+    // TM_REFERRED_FK_DB_F
+    pub referred_fk_db: bool,
+    // TM_GENERATED_INVISIBLE_PK_F, since 8.0.30
+    pub generated_invisible_pk: bool,
+}
+
+impl TableMapFlags {
+    /// decode `flags` of TableMap event
+    pub fn from_u16(flags: u16) -> Self {
+        TableMapFlags {
+            bit_len_exact: flags & 1 == 1,
+            referred_fk_db: (flags >> 1) & 1 == 1,
+            generated_invisible_pk: (flags >> 2) & 1 == 1,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, PartialEq, Eq, Clone)]
@@ -453,6 +1624,263 @@ pub enum DupHandlingFlags {
     Replace,
 }
 
+/// MariaDB GTID, displayed as `domain-server-sequence`
+#[cfg(feature = "mariadb")]
+#[derive(Debug, Serialize, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct MariaGtidId {
+    pub domain_id: u32,
+    pub server_id: u32,
+    pub sequence: u64,
+}
+
+#[cfg(feature = "mariadb")]
+impl core::fmt::Display for MariaGtidId {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}-{}-{}", self.domain_id, self.server_id, self.sequence)
+    }
+}
+
+/// position of event across binlog files, ordered by file sequence then `log_pos`,
+/// used to sort or merge events parsed in parallel
+#[derive(Debug, Serialize, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub struct PositionKey {
+    pub file: u32,
+    pub pos: u32,
+}
+
+impl PositionKey {
+    /// sequence number of binlog file, the numeric extension of its name,
+    /// e.g. 2 of `mysql-bin.000002`
+    pub fn file_seq(binlog: &str) -> Option<u32> {
+        binlog.rsplit('.').next()?.parse().ok()
+    }
+
+    /// keys of events read in order starting from binlog `file`
+    ///
+    /// events after a Rotate event are in its `next_binlog`, whose sequence is
+    /// `file + 1` if the name has no numeric extension
+    pub fn assign<'a, I>(file: u32, events: I) -> Vec<PositionKey>
+    where
+        I: IntoIterator<Item = &'a Event>,
+    {
+        let mut file = file;
+        events
+            .into_iter()
+            .map(|event| {
+                let key = event.position_key(file);
+                if let Event::Rotate { next_binlog, .. } = event {
+                    file = PositionKey::file_seq(next_binlog).unwrap_or(file + 1);
+                }
+                key
+            })
+            .collect()
+    }
+}
+
+/// FNV-1a, stable across platforms and releases unlike `DefaultHasher`
+struct Fnv64(u64);
+
+impl Default for Fnv64 {
+    fn default() -> Self {
+        Fnv64(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Fnv64 {
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 = (self.0 ^ *b as u64).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+/// Debug of event with rows of rows events summarized as `<N rows, M bytes>`, see
+/// `Event::redacted`
+pub struct Redacted<'a>(&'a Event);
+
+impl Event {
+    /// wrap event for logging, decoded rows are not printed
+    pub fn redacted(&self) -> Redacted<'_> {
+        Redacted(self)
+    }
+}
+
+/// size of encoded rows, i.e. event_size minus header, fixed part, image bits and checksum
+fn rows_size(header: &Header, extra_data_len: u16, column_count: u64, bits: usize) -> u64 {
+    let fixed = 19 + 6 + 2 + extra_data_len as u64 + int_lenenc_len(column_count) as u64 + 4;
+    (header.event_size as u64).saturating_sub(fixed + bits as u64)
+}
+
+impl core::fmt::Debug for Redacted<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.0 {
+            Event::WriteRowsV2 {
+                header,
+                table_id,
+                flags,
+                extra_data_len,
+                extra_data,
+                column_count,
+                inserted_image_bits,
+                rows,
+                checksum,
+            } => {
+                let size = rows_size(
+                    header,
+                    *extra_data_len,
+                    *column_count,
+                    inserted_image_bits.len(),
+                );
+                f.debug_struct("WriteRowsV2")
+                    .field("header", header)
+                    .field("table_id", table_id)
+                    .field("flags", flags)
+                    .field("extra_data_len", extra_data_len)
+                    .field("extra_data", extra_data)
+                    .field("column_count", column_count)
+                    .field("inserted_image_bits", inserted_image_bits)
+                    .field(
+                        "rows",
+                        &format_args!("<{} rows, {} bytes>", rows.len(), size),
+                    )
+                    .field("checksum", checksum)
+                    .finish()
+            }
+            Event::UpdateRowsV2 {
+                header,
+                table_id,
+                flags,
+                extra_data_len,
+                extra_data,
+                column_count,
+                before_image_bits,
+                after_image_bits,
+                rows,
+                checksum,
+            } => {
+                let size = rows_size(
+                    header,
+                    *extra_data_len,
+                    *column_count,
+                    before_image_bits.len() + after_image_bits.len(),
+                );
+                f.debug_struct("UpdateRowsV2")
+                    .field("header", header)
+                    .field("table_id", table_id)
+                    .field("flags", flags)
+                    .field("extra_data_len", extra_data_len)
+                    .field("extra_data", extra_data)
+                    .field("column_count", column_count)
+                    .field("before_image_bits", before_image_bits)
+                    .field("after_image_bits", after_image_bits)
+                    .field(
+                        "rows",
+                        &format_args!("<{} rows, {} bytes>", rows.len(), size),
+                    )
+                    .field("checksum", checksum)
+                    .finish()
+            }
+            Event::DeleteRowsV2 {
+                header,
+                table_id,
+                flags,
+                extra_data_len,
+                extra_data,
+                column_count,
+                deleted_image_bits,
+                rows,
+                checksum,
+            } => {
+                let size = rows_size(
+                    header,
+                    *extra_data_len,
+                    *column_count,
+                    deleted_image_bits.len(),
+                );
+                f.debug_struct("DeleteRowsV2")
+                    .field("header", header)
+                    .field("table_id", table_id)
+                    .field("flags", flags)
+                    .field("extra_data_len", extra_data_len)
+                    .field("extra_data", extra_data)
+                    .field("column_count", column_count)
+                    .field("deleted_image_bits", deleted_image_bits)
+                    .field(
+                        "rows",
+                        &format_args!("<{} rows, {} bytes>", rows.len(), size),
+                    )
+                    .field("checksum", checksum)
+                    .finish()
+            }
+            event => core::fmt::Debug::fmt(event, f),
+        }
+    }
+}
+
+/// server error code of a statement, unlisted codes are kept in `Other`
+///
+/// ref: https://dev.mysql.com/doc/mysql-errors/8.0/en/server-error-reference.html
+#[derive(Debug, Serialize, PartialEq, Eq, Clone, Copy)]
+pub enum ErrorCode {
+    NoError,
+    DbCreateExists,
+    DbDropExists,
+    TableExists,
+    BadDb,
+    BadTable,
+    BadField,
+    DupEntry,
+    CantDropFieldOrKey,
+    KeyNotFound,
+    NoSuchTable,
+    LockWaitTimeout,
+    LockDeadlock,
+    QueryInterrupted,
+    Other(u16),
+}
+
+impl ErrorCode {
+    pub fn from_u16(code: u16) -> Self {
+        match code {
+            0 => ErrorCode::NoError,
+            1007 => ErrorCode::DbCreateExists,
+            1008 => ErrorCode::DbDropExists,
+            1049 => ErrorCode::BadDb,
+            1050 => ErrorCode::TableExists,
+            1051 => ErrorCode::BadTable,
+            1054 => ErrorCode::BadField,
+            1062 => ErrorCode::DupEntry,
+            1091 => ErrorCode::CantDropFieldOrKey,
+            1032 => ErrorCode::KeyNotFound,
+            1146 => ErrorCode::NoSuchTable,
+            1205 => ErrorCode::LockWaitTimeout,
+            1213 => ErrorCode::LockDeadlock,
+            1317 => ErrorCode::QueryInterrupted,
+            other => ErrorCode::Other(other),
+        }
+    }
+
+    pub fn as_u16(&self) -> u16 {
+        match self {
+            ErrorCode::NoError => 0,
+            ErrorCode::DbCreateExists => 1007,
+            ErrorCode::DbDropExists => 1008,
+            ErrorCode::BadDb => 1049,
+            ErrorCode::TableExists => 1050,
+            ErrorCode::BadTable => 1051,
+            ErrorCode::BadField => 1054,
+            ErrorCode::DupEntry => 1062,
+            ErrorCode::CantDropFieldOrKey => 1091,
+            ErrorCode::KeyNotFound => 1032,
+            ErrorCode::NoSuchTable => 1146,
+            ErrorCode::LockWaitTimeout => 1205,
+            ErrorCode::LockDeadlock => 1213,
+            ErrorCode::QueryInterrupted => 1317,
+            ErrorCode::Other(code) => *code,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, PartialEq, Eq, Clone)]
 pub enum IncidentEventType {
     None,
@@ -473,28 +1901,15 @@ fn parse_query<'a>(input: &'a [u8], header: Header) -> IResult<&'a [u8], Event>
     let (i, error_code) = le_u16(i)?;
     let (i, status_vars_length) = le_u16(i)?;
     let (i, raw_vars) = take(status_vars_length)(i)?;
-    let (remain, status_vars) = many0(query::parse_status_var)(raw_vars)?;
-    assert_eq!(remain.len(), 0);
-    let (i, schema) = map(take(schema_length), |s: &[u8]| {
-        String::from_utf8(s[0..schema_length as usize].to_vec()).unwrap()
-    })(i)?;
+    let (_, status_vars) = all_consuming(many0(query::parse_status_var))(raw_vars)?;
+    let (i, schema) = map(take(schema_length), |s: &[u8]| extract_string(s))(i)?;
     let (i, _) = take(1usize)(i)?;
-    let (i, query) = map(
-        take(
-            header.event_size
-                - 19
-                - 4
-                - 4
-                - 1
-                - 2
-                - 2
-                - status_vars_length as u32
-                - schema_length as u32
-                - 1
-                - 4,
-        ),
-        |s: &[u8]| extract_string(s),
-    )(i)?;
+    let query_len = rest_len(
+        i,
+        &header,
+        19 + 4 + 4 + 1 + 2 + 2 + status_vars_length as u64 + schema_length as u64 + 1 + 4,
+    )?;
+    let (i, query) = map(take(query_len), |s: &[u8]| extract_string(s))(i)?;
     let (i, checksum) = le_u32(i)?;
     Ok((
         i,
@@ -520,8 +1935,8 @@ fn parse_stop<'a>(input: &'a [u8], header: Header) -> IResult<&'a [u8], Event> {
 
 fn parse_rotate<'a>(input: &'a [u8], header: Header) -> IResult<&'a [u8], Event> {
     let (i, position) = le_u64(input)?;
-    let str_len = header.event_size - 19 - 8 - 4;
-    let (i, next_binlog) = map(take(str_len), |s: &[u8]| string_var(s, str_len as usize))(i)?;
+    let str_len = rest_len(i, &header, 19 + 8 + 4)?;
+    let (i, next_binlog) = map(take(str_len), |s: &[u8]| string_var(s, str_len))(i)?;
     let (i, checksum) = le_u32(i)?;
     Ok((
         i,
@@ -535,12 +1950,16 @@ fn parse_rotate<'a>(input: &'a [u8], header: Header) -> IResult<&'a [u8], Event>
 }
 
 fn parse_intvar<'a>(input: &'a [u8], header: Header) -> IResult<&'a [u8], Event> {
-    let (i, e_type) = map(le_u8, |t: u8| match t {
+    let (i, t) = le_u8(input)?;
+    let e_type = match t {
         0x00 => IntVarEventType::InvalidIntEvent,
         0x01 => IntVarEventType::LastInsertIdEvent,
         0x02 => IntVarEventType::InsertIdEvent,
-        _ => unreachable!(),
-    })(input)?;
+        _ => {
+            log::warn!("unknown intvar type {}", t);
+            IntVarEventType::Unknown(t)
+        }
+    };
     let (i, (value, checksum)) = tuple((le_u64, le_u32))(i)?;
     Ok((
         i,
@@ -561,26 +1980,29 @@ fn extract_many_fields<'a>(
     schema_length: u8,
 ) -> IResult<&'a [u8], (Vec<u8>, Vec<String>, String, String, String)> {
     let (i, field_name_lengths) = map(take(num_fields), |s: &[u8]| s.to_vec())(input)?;
-    let total_len: u64 = field_name_lengths.iter().sum::<u8>() as u64 + num_fields as u64;
+    let total_len: u64 =
+        field_name_lengths.iter().map(|&l| l as u64).sum::<u64>() + num_fields as u64;
     let (i, raw_field_names) = take(total_len)(i)?;
     let (_, field_names) =
         many_m_n(num_fields as usize, num_fields as usize, string_nul)(raw_field_names)?;
-    let (i, table_name) = map(take(table_name_length + 1), |s: &[u8]| extract_string(s))(i)?;
-    let (i, schema_name) = map(take(schema_length + 1), |s: &[u8]| extract_string(s))(i)?;
-    let (i, file_name) = map(
-        take(
-            header.event_size as usize
-                - 19
-                - 25
-                - num_fields as usize
-                - total_len as usize
-                - table_name_length as usize
-                - schema_length as usize
-                - 3
-                - 4,
-        ),
-        |s: &[u8]| extract_string(s),
-    )(i)?;
+    let (i, table_name) = map(take(table_name_length as usize + 1), |s: &[u8]| {
+        extract_string(s)
+    })(i)?;
+    let (i, schema_name) = map(take(schema_length as usize + 1), |s: &[u8]| {
+        extract_string(s)
+    })(i)?;
+    let file_name_len = rest_len(
+        i,
+        header,
+        19 + 25
+            + num_fields as u64
+            + total_len
+            + table_name_length as u64
+            + schema_length as u64
+            + 3
+            + 4,
+    )?;
+    let (i, file_name) = map(take(file_name_len), |s: &[u8]| extract_string(s))(i)?;
     Ok((
         i,
         (
@@ -612,19 +2034,8 @@ fn parse_load<'a>(input: &'a [u8], header: Header) -> IResult<&'a [u8], Event> {
     ) = tuple((
         le_u32, le_u32, le_u32, le_u8, le_u8, le_u32, le_u8, le_u8, le_u8, le_u8, le_u8,
     ))(input)?;
-    let (i, opt_flags) = map(le_u8, |flags: u8| OptFlags {
-        dump_file: (flags >> 0) % 2 == 1,
-        opt_enclosed: (flags >> 1) % 1 == 1,
-        replace: (flags >> 2) % 2 == 1,
-        ignore: (flags >> 3) % 2 == 1,
-    })(i)?;
-    let (i, empty_flags) = map(le_u8, |flags: u8| EmptyFlags {
-        field_term_empty: (flags >> 0) % 2 == 1,
-        enclosed_empty: (flags >> 1) % 2 == 1,
-        line_term_empty: (flags >> 2) % 2 == 1,
-        line_start_empty: (flags >> 3) % 2 == 1,
-        escape_empty: (flags >> 4) % 2 == 1,
-    })(i)?;
+    let (i, opt_flags) = map(le_u8, OptFlags::from_u8)(i)?;
+    let (i, empty_flags) = map(le_u8, EmptyFlags::from_u8)(i)?;
     let (i, (field_name_lengths, field_names, table_name, schema_name, file_name)) =
         extract_many_fields(i, &header, num_fields, table_name_length, schema_length)?;
     let (i, checksum) = le_u32(i)?;
@@ -660,10 +2071,11 @@ fn parse_slave<'a>(input: &'a [u8], header: Header) -> IResult<&'a [u8], Event>
     Ok((i, Event::Slave { header, checksum }))
 }
 
-fn parse_file_data<'a>(input: &'a [u8], header: &Header) -> IResult<&'a [u8], (u32, String, u32)> {
+/// block of loaded file is arbitrary binary, kept as raw bytes
+fn parse_file_data<'a>(input: &'a [u8], header: &Header) -> IResult<&'a [u8], (u32, Vec<u8>, u32)> {
     let (i, file_id) = le_u32(input)?;
-    let (i, block_data) = map(take(header.event_size - 19 - 4 - 4), |s: &[u8]| {
-        extract_string(s)
+    let (i, block_data) = map(take(rest_len(i, header, 19 + 4 + 4)?), |s: &[u8]| {
+        s.to_vec()
     })(i)?;
     let (i, checksum) = le_u32(i)?;
     Ok((i, (file_id, block_data, checksum)))
@@ -730,12 +2142,7 @@ fn parse_new_load<'a>(input: &'a [u8], header: Header) -> IResult<&'a [u8], Even
     let (i, (line_term_length, line_term)) = extract_from_prev(i)?;
     let (i, (line_start_length, line_start)) = extract_from_prev(i)?;
     let (i, (escaped_by_length, escaped_by)) = extract_from_prev(i)?;
-    let (i, opt_flags) = map(le_u8, |flags| OptFlags {
-        dump_file: (flags >> 0) % 2 == 1,
-        opt_enclosed: (flags >> 1) % 2 == 1,
-        replace: (flags >> 2) % 2 == 1,
-        ignore: (flags >> 3) % 2 == 1,
-    })(i)?;
+    let (i, opt_flags) = map(le_u8, OptFlags::from_u8)(i)?;
     let (i, (field_name_lengths, field_names, table_name, schema_name, file_name)) =
         extract_many_fields(i, &header, num_fields, table_name_length, schema_length)?;
     let (i, checksum) = le_u32(i)?;
@@ -862,9 +2269,9 @@ fn parse_format_desc<'a>(input: &'a [u8], header: Header) -> IResult<&'a [u8], E
     let (i, mysql_server_version) = map(take(50usize), |s: &[u8]| extract_string(s))(i)?;
     let (i, create_timestamp) = le_u32(i)?;
     let (i, event_header_length) = le_u8(i)?;
-    let num = header.event_size - 19 - (2 + 50 + 4 + 1) - 1 - 4;
+    let num = rest_len(i, &header, 19 + (2 + 50 + 4 + 1) + 1 + 4)?;
     let (i, supported_types) = map(take(num), |s: &[u8]| s.to_vec())(i)?;
-    let (i, checksum_alg) = le_u8(i)?;
+    let (i, checksum_alg) = map(le_u8, ChecksumAlg::from_u8)(i)?;
     let (i, checksum) = le_u32(i)?;
     Ok((
         i,
@@ -906,6 +2313,8 @@ fn parse_begin_load_query<'a>(input: &'a [u8], header: Header) -> IResult<&'a [u
     ))
 }
 
+// post header of Query event followed by file_id, start_pos, end_pos and dup handling flags,
+// status vars come after the whole 26 bytes post header, not after Query part of it
 fn parse_execute_load_query<'a>(input: &'a [u8], header: Header) -> IResult<&'a [u8], Event> {
     let (
         i,
@@ -920,27 +2329,35 @@ fn parse_execute_load_query<'a>(input: &'a [u8], header: Header) -> IResult<&'a
             end_pos,
         ),
     ) = tuple((
-        le_u32, le_u32, le_u8, le_u16, le_u16, le_u32, le_u32, le_u32,
+        le_u32,
+        le_u32,
+        le_u8,
+        map(le_u16, ErrorCode::from_u16),
+        le_u16,
+        le_u32,
+        le_u32,
+        le_u32,
     ))(input)?;
-    let (i, dup_handling_flags) = map(le_u8, |flags| match flags {
+    let (i, flags) = le_u8(i)?;
+    let dup_handling_flags = match flags {
         0 => DupHandlingFlags::Error,
         1 => DupHandlingFlags::Ignore,
         2 => DupHandlingFlags::Replace,
-        _ => unreachable!(),
-    })(i)?;
+        _ => {
+            log::error!("unknown dup handling flags {}", flags);
+            return Err(nom::Err::Error((i, ErrorKind::Switch)));
+        }
+    };
     let (i, raw_vars) = take(status_vars_length)(i)?;
-    let (remain, status_vars) = many0(query::parse_status_var)(raw_vars)?;
-    assert_eq!(remain.len(), 0);
-    let (i, schema) = map(take(schema_length), |s: &[u8]| {
-        String::from_utf8(s[0..schema_length as usize].to_vec()).unwrap()
-    })(i)?;
+    let (_, status_vars) = all_consuming(many0(query::parse_status_var))(raw_vars)?;
+    let (i, schema) = map(take(schema_length), |s: &[u8]| extract_string(s))(i)?;
     let (i, _) = take(1usize)(i)?;
-    let (i, query) = map(
-        take(
-            header.event_size - 19 - 26 - status_vars_length as u32 - schema_length as u32 - 1 - 4,
-        ),
-        |s: &[u8]| extract_string(s),
-    )(i)?;
+    let query_len = rest_len(
+        i,
+        &header,
+        19 + 26 + status_vars_length as u64 + schema_length as u64 + 1 + 4,
+    )?;
+    let (i, query) = map(take(query_len), |s: &[u8]| extract_string(s))(i)?;
     let (i, checksum) = le_u32(i)?;
     Ok((
         i,
@@ -963,66 +2380,98 @@ fn parse_execute_load_query<'a>(input: &'a [u8], header: Header) -> IResult<&'a
     ))
 }
 
-fn parse_table_map<'a>(input: &'a [u8], header: Header) -> IResult<&'a [u8], Event> {
-    let (i, table_id): (&'a [u8], u64) = map(take(6usize), |id_raw: &[u8]| {
-        let mut filled = id_raw.to_vec();
-        filled.extend(vec![0, 0]);
-        pu64(&filled).unwrap().1
-    })(input)?;
-    // Reserved for future use; currently always 0
-    let (i, flags) = le_u16(i)?;
-    let (i, (schema_length, schema)) = string_fixed(i)?;
-    let (i, term) = le_u8(i)?;
-    assert_eq!(term, 0);
-
-    let (i, (table_name_length, table_name)) = string_fixed(i)?;
-    let (i, term) = le_u8(i)?;
-    assert_eq!(term, 0);
-    let (i, (_, column_count)) = int_lenenc(i)?;
-    let (i, cols_type): (&'a [u8], Vec<ColTypes>) = map(take(column_count), |s: &[u8]| {
-        s.iter().map(|&t| ColTypes::from_u8(t)).collect()
-    })(i)?;
-    let (i, (_, column_meta_count)) = int_lenenc(i)?;
-    let (i, columns_type) = map(take(column_meta_count), |s: &[u8]| {
-        let mut used = 0;
-        let mut ret = vec![];
-        for col in cols_type.iter() {
-            let (_, (u, val)) = col.parse_def(&s[used..]).unwrap();
-            used = used + u;
-            ret.push(val);
+fn parse_table_map<'a>(
+    input: &'a [u8],
+    header: Header,
+    cache: &mut TableMapCache,
+) -> VerboseResult<'a, Event> {
+    let (i, table_id) = context("reading table_id", take_u48_le(input))?;
+    let (i, flags) = context("reading flags", map(le_u16, TableMapFlags::from_u16)(i))?;
+    let (i, (schema_length, schema)) = context("reading schema", string_fixed(i))?;
+    let (i, _) = add_context("reading schema terminator", terminator(i))?;
+
+    let (i, (table_name_length, table_name)) = context("reading table_name", string_fixed(i))?;
+    let (i, _) = add_context("reading table_name terminator", terminator(i))?;
+    let (i, (_, column_count)) = context("reading column_count", int_lenenc(i))?;
+    let (i, raw_types) = context("reading column types", take(column_count)(i))?;
+    let cols_type = match raw_types
+        .iter()
+        .map(|&t| ColTypes::from_u8(t))
+        .collect::<Option<Vec<ColTypes>>>()
+    {
+        Some(cols_type) => cols_type,
+        None => {
+            return context(
+                "reading column types",
+                Err(nom::Err::Error((raw_types, ErrorKind::Switch))),
+            )
         }
-        ret
-    })(i)?;
+    };
+    let (i, (_, column_meta_count)) = context("reading column_meta_count", int_lenenc(i))?;
+    let (i, mut meta) = context("reading column meta", take(column_meta_count)(i))?;
+    let mut columns_type = Vec::with_capacity(cols_type.len());
+    for col in cols_type.iter() {
+        let (remain, (_, val)) = context("reading column meta", col.parse_def(meta))?;
+        meta = remain;
+        columns_type.push(val);
+    }
     let mask_len = (column_count + 7) / 8;
-    let (i, null_bits) = map(take(mask_len), |s: &[u8]| s.to_vec())(i)?;
+    let (i, null_bits) = context(
+        "reading null_bits",
+        map(take(mask_len), |s: &[u8]| s.to_vec())(i),
+    )?;
+    let (i, checksum) = context("reading checksum", le_u32(i))?;
+    let event = Event::TableMap {
+        header,
+        table_id,
+        flags,
+        schema_length,
+        schema,
+        table_name_length,
+        table_name,
+        column_count,
+        columns_type,
+        null_bits,
+        checksum,
+    };
+    cache.insert(&event);
+    Ok((i, event))
+}
+
+fn parse_pre_ga_rows<'a>(input: &'a [u8], header: Header) -> IResult<&'a [u8], Event> {
+    let body_len = rest_len(input, &header, 19 + 4)?;
+    let (i, data) = map(take(body_len), |s: &[u8]| s.to_vec())(input)?;
     let (i, checksum) = le_u32(i)?;
-    if let Ok(mut mapping) = TABLE_MAP.lock() {
-        mapping.insert(table_id, columns_type.clone());
-    }
-    Ok((
-        i,
-        Event::TableMap {
+    let event = match header.event_type {
+        0x14 => Event::PreGaWriteRows {
             header,
-            table_id,
-            flags,
-            schema_length,
-            schema,
-            table_name_length,
-            table_name,
-            column_count,
-            columns_type,
-            null_bits,
+            data,
             checksum,
         },
-    ))
+        0x15 => Event::PreGaUpdateRows {
+            header,
+            data,
+            checksum,
+        },
+        _ => Event::PreGaDeleteRows {
+            header,
+            data,
+            checksum,
+        },
+    };
+    Ok((i, event))
 }
 
 fn parse_incident<'a>(input: &'a [u8], header: Header) -> IResult<&'a [u8], Event> {
-    let (i, d_type) = map(le_u16, |t| match t {
+    let (i, t) = le_u16(input)?;
+    let d_type = match t {
         0x0000 => IncidentEventType::None,
         0x0001 => IncidentEventType::LostEvents,
-        _ => unreachable!(),
-    })(input)?;
+        _ => {
+            log::error!("unknown incident type {}", t);
+            return Err(nom::Err::Error((input, ErrorKind::Switch)));
+        }
+    };
     let (i, message_length) = le_u8(i)?;
     let (i, message) = map(take(message_length), |s: &[u8]| {
         string_var(s, message_length as usize)
@@ -1045,6 +2494,20 @@ fn parse_heartbeat<'a>(input: &'a [u8], header: Header) -> IResult<&'a [u8], Eve
     Ok((i, Event::Heartbeat { header, checksum }))
 }
 
+fn parse_ignorable<'a>(input: &'a [u8], header: Header) -> IResult<&'a [u8], Event> {
+    let len = rest_len(input, &header, 19 + 4)?;
+    let (i, payload) = map(take(len), |s: &[u8]| s.to_vec())(input)?;
+    let (i, checksum) = le_u32(i)?;
+    Ok((
+        i,
+        Event::Ignorable {
+            header,
+            payload,
+            checksum,
+        },
+    ))
+}
+
 fn parse_row_query<'a>(input: &'a [u8], header: Header) -> IResult<&'a [u8], Event> {
     let (i, length) = le_u8(input)?;
     let (i, query_text) = map(take(length), |s: &[u8]| string_var(s, length as usize))(i)?;
@@ -1060,44 +2523,108 @@ fn parse_row_query<'a>(input: &'a [u8], header: Header) -> IResult<&'a [u8], Eve
     ))
 }
 
+#[cfg(feature = "mariadb")]
+fn parse_maria_annotate_rows<'a>(input: &'a [u8], header: Header) -> IResult<&'a [u8], Event> {
+    let len = rest_len(input, &header, 19 + 4)?;
+    let (i, query_text) = map(take(len), |s: &[u8]| extract_string(s))(input)?;
+    let (i, checksum) = le_u32(i)?;
+    Ok((
+        i,
+        Event::MariaAnnotateRows {
+            header,
+            query_text,
+            checksum,
+        },
+    ))
+}
+
+#[cfg(feature = "mariadb")]
+fn parse_maria_gtid<'a>(input: &'a [u8], header: Header) -> IResult<&'a [u8], Event> {
+    let (i, (sequence, domain_id, flags)) = tuple((le_u64, le_u32, le_u8))(input)?;
+    // FL_GROUP_COMMIT_ID
+    let (i, commit_id) = if flags & 0x02 != 0 {
+        map(le_u64, Some)(i)?
+    } else {
+        (i, None)
+    };
+    // padding or XA info of newer versions is skipped
+    let commit_id_len = if commit_id.is_some() { 8 } else { 0 };
+    let used = 19 + 8 + 4 + 1 + commit_id_len + 4;
+    let (i, _) = take(rest_len(input, &header, used)?)(i)?;
+    let (i, checksum) = le_u32(i)?;
+    let server_id = header.server_id;
+    Ok((
+        i,
+        Event::MariaGtid {
+            header,
+            sequence,
+            domain_id,
+            server_id,
+            flags,
+            commit_id,
+            checksum,
+        },
+    ))
+}
+
+/// commit timestamps and transaction length of MySQL 8.0 Gtid event,
+/// absent fields of shorter events are None, fields of later versions are skipped
+fn parse_gtid_commit_info<'a>(
+    input: &'a [u8],
+) -> IResult<&'a [u8], (Option<u64>, Option<u64>, Option<u64>)> {
+    if input.len() < 7 {
+        return Ok((&input[input.len()..], (None, None, None)));
+    }
+    let (i, immediate) = int_fixed(input, 7)?;
+    // highest bit marks that original timestamp differs and follows
+    let (i, original) = if immediate & (1 << 55) != 0 {
+        int_fixed(i, 7)?
+    } else {
+        (i, immediate)
+    };
+    let immediate = immediate & !(1 << 55);
+    let (i, transaction_length) = if i.is_empty() {
+        (i, None)
+    } else {
+        map(int_lenenc, |(_, len)| Some(len))(i)?
+    };
+    Ok((
+        &i[i.len()..],
+        (Some(original), Some(immediate), transaction_length),
+    ))
+}
+
+// rbr_only, source_id, transaction_id, ts_type, last_committed, sequence_number,
+// commit info and checksum
+type GtidFields = (
+    bool,
+    String,
+    i64,
+    u8,
+    i64,
+    i64,
+    (Option<u64>, Option<u64>, Option<u64>),
+    u32,
+);
+
 fn parse_events_gtid<'a>(
     input: &'a [u8],
-) -> IResult<&'a [u8], (bool, String, String, u8, i64, i64, u32)> {
+    header: &Header,
+    ctx: &ParseContext,
+) -> IResult<&'a [u8], GtidFields> {
     let (i, rbr_only) = map(le_u8, |t: u8| t == 0)(input)?;
-    let (i, source_id) = map(take(16usize), |s: &[u8]| {
-        format!(
-            "{}-{}-{}-{}-{}",
-            s[..4].iter().fold(String::new(), |mut acc, i| {
-                acc.push_str(&i.to_string());
-                acc
-            }),
-            s[4..6].iter().fold(String::new(), |mut acc, i| {
-                acc.push_str(&i.to_string());
-                acc
-            }),
-            s[6..8].iter().fold(String::new(), |mut acc, i| {
-                acc.push_str(&i.to_string());
-                acc
-            }),
-            s[8..10].iter().fold(String::new(), |mut acc, i| {
-                acc.push_str(&i.to_string());
-                acc
-            }),
-            s[10..].iter().fold(String::new(), |mut acc, i| {
-                acc.push_str(&i.to_string());
-                acc
-            }),
-        )
-    })(i)?;
-    let (i, transaction_id) = map(take(8usize), |s: &[u8]| {
-        s.iter().fold(String::new(), |mut acc, i| {
-            acc.push_str(&i.to_string());
-            acc
-        })
-    })(i)?;
+    let (i, source_id) = map(take(16usize), gtid::uuid)(i)?;
+    let (i, transaction_id) = le_i64(i)?;
     let (i, ts_type) = le_u8(i)?;
     let (i, last_committed) = le_i64(i)?;
     let (i, sequence_number) = le_i64(i)?;
+    let (i, extra) = take(rest_len(i, header, 19 + 42 + 4)?)(i)?;
+    // commit timestamps are written since 8.0.1
+    let (_, commit_info) = if ctx.before((8, 0, 1)) {
+        (extra, (None, None, None))
+    } else {
+        parse_gtid_commit_info(extra)?
+    };
     let (i, checksum) = le_u32(i)?;
     Ok((
         i,
@@ -1108,14 +2635,19 @@ fn parse_events_gtid<'a>(
             ts_type,
             last_committed,
             sequence_number,
+            commit_info,
             checksum,
         ),
     ))
 }
 
-fn parse_anonymous_gtid<'a>(input: &'a [u8], header: Header) -> IResult<&'a [u8], Event> {
+fn parse_anonymous_gtid<'a>(
+    input: &'a [u8],
+    header: Header,
+    ctx: &ParseContext,
+) -> IResult<&'a [u8], Event> {
     map(
-        parse_events_gtid,
+        |i| parse_events_gtid(i, &header, ctx),
         |(
             rbr_only,
             source_id,
@@ -1123,6 +2655,7 @@ fn parse_anonymous_gtid<'a>(input: &'a [u8], header: Header) -> IResult<&'a [u8]
             ts_type,
             last_committed,
             sequence_number,
+            (original_commit_timestamp, immediate_commit_timestamp, transaction_length),
             checksum,
         )| Event::AnonymousGtid {
             header: header.clone(),
@@ -1132,14 +2665,17 @@ fn parse_anonymous_gtid<'a>(input: &'a [u8], header: Header) -> IResult<&'a [u8]
             ts_type,
             last_committed,
             sequence_number,
+            original_commit_timestamp,
+            immediate_commit_timestamp,
+            transaction_length,
             checksum,
         },
     )(input)
 }
 
-fn parse_gtid<'a>(input: &'a [u8], header: Header) -> IResult<&'a [u8], Event> {
+fn parse_gtid<'a>(input: &'a [u8], header: Header, ctx: &ParseContext) -> IResult<&'a [u8], Event> {
     map(
-        parse_events_gtid,
+        |i| parse_events_gtid(i, &header, ctx),
         |(
             rbr_only,
             source_id,
@@ -1147,6 +2683,7 @@ fn parse_gtid<'a>(input: &'a [u8], header: Header) -> IResult<&'a [u8], Event> {
             ts_type,
             last_committed,
             sequence_number,
+            (original_commit_timestamp, immediate_commit_timestamp, transaction_length),
             checksum,
         )| Event::Gtid {
             header: header.clone(),
@@ -1156,13 +2693,20 @@ fn parse_gtid<'a>(input: &'a [u8], header: Header) -> IResult<&'a [u8], Event> {
             ts_type,
             last_committed,
             sequence_number,
+            original_commit_timestamp,
+            immediate_commit_timestamp,
+            transaction_length,
             checksum,
         },
     )(input)
 }
 
 fn parse_previous_gtids<'a>(input: &'a [u8], header: Header) -> IResult<&'a [u8], Event> {
-    let (i, gtid_sets) = map(take(header.event_size - 19 - 4 - 4), |s: &[u8]| s.to_vec())(input)?;
+    let (_, body) = take(rest_len(input, &header, 19 + 4)?)(input)?;
+    let (_, gtid_set) = all_consuming(gtid::parse_gtid_set)(body)?;
+    let (i, gtid_sets) = map(take(rest_len(input, &header, 19 + 4 + 4)?), |s: &[u8]| {
+        s.to_vec()
+    })(input)?;
     let (i, buf_size) = le_u32(i)?;
     let (i, checksum) = le_u32(i)?;
     Ok((
@@ -1171,6 +2715,39 @@ fn parse_previous_gtids<'a>(input: &'a [u8], header: Header) -> IResult<&'a [u8]
             header,
             gtid_sets,
             buf_size,
+            gtid_set,
+            checksum,
+        },
+    ))
+}
+
+fn parse_cert_info<'a>(input: &'a [u8]) -> IResult<&'a [u8], (String, Vec<u8>)> {
+    let (i, key_len) = le_u16(input)?;
+    let (i, key) = map(take(key_len), |s: &[u8]| string_var(s, key_len as usize))(i)?;
+    let (i, value_len) = le_u32(i)?;
+    let (i, value) = map(take(value_len), |s: &[u8]| s.to_vec())(i)?;
+    Ok((i, (key, value)))
+}
+
+fn parse_view_change<'a>(input: &'a [u8], header: Header) -> IResult<&'a [u8], Event> {
+    let (i, view_id) = map(take(40usize), |s: &[u8]| extract_string(s))(input)?;
+    let (i, seq_number) = le_u64(i)?;
+    // every cert info takes at least one byte, avoid allocating for a corrupted size
+    let (i, cert_info_size) = verify(le_u32, |&size| size as usize <= i.len())(i)?;
+    let (i, cert_info) = many_m_n(
+        cert_info_size as usize,
+        cert_info_size as usize,
+        parse_cert_info,
+    )(i)?;
+    let (i, checksum) = le_u32(i)?;
+    Ok((
+        i,
+        Event::ViewChange {
+            header,
+            view_id,
+            seq_number,
+            cert_info_size,
+            cert_info,
             checksum,
         },
     ))
@@ -1178,27 +2755,20 @@ fn parse_previous_gtids<'a>(input: &'a [u8], header: Header) -> IResult<&'a [u8]
 
 fn parse_part_row_event<'a>(
     input: &'a [u8],
-) -> IResult<&'a [u8], (u64, rows::Flags, u16, Vec<rows::ExtraData>, (usize, u64))> {
-    let (i, table_id): (&'a [u8], u64) = map(take(6usize), |id_raw: &[u8]| {
-        let mut filled = id_raw.to_vec();
-        filled.extend(vec![0, 0]);
-        pu64(&filled).unwrap().1
-    })(input)?;
-    let (i, flags) = map(le_u16, |flag: u16| rows::Flags {
-        end_of_stmt: (flag >> 0) % 2 == 1,
-        foreign_key_checks: (flag >> 1) % 2 == 0,
-        unique_key_checks: (flag >> 2) % 2 == 0,
-        has_columns: (flag >> 3) % 2 == 0,
-    })(i)?;
-    let (i, extra_data_len) = le_u16(i)?;
-    assert!(extra_data_len >= 2);
-    let (i, extra_data) = match extra_data_len {
-        2 => (i, vec![]),
-        _ => many1(rows::parse_extra_data)(i)?,
-    };
+) -> VerboseResult<'a, (u64, rows::Flags, u16, Vec<rows::ExtraData>, (usize, u64))> {
+    let (i, table_id) = context("reading table_id", take_u48_le(input))?;
+    let (i, flags) = context("reading flags", map(le_u16, rows::Flags::from_u16)(i))?;
+    // extra_data_len counts itself
+    let (i, extra_data_len) =
+        context("reading extra_data_len", verify(le_u16, |&len| len >= 2)(i))?;
+    let (i, raw_extra_data) = context("reading extra_data", take(extra_data_len - 2)(i))?;
+    let (_, extra_data) = context(
+        "reading extra_data",
+        all_consuming(many0(rows::parse_extra_data))(raw_extra_data),
+    )?;
 
     // parse body
-    let (i, (encode_len, column_count)) = int_lenenc(i)?;
+    let (i, (encode_len, column_count)) = context("reading column_count", int_lenenc(i))?;
     Ok((
         i,
         (
@@ -1211,44 +2781,256 @@ fn parse_part_row_event<'a>(
     ))
 }
 
+/// find table of rows event, column_count of rows event must match the one of TableMap
+fn lookup_table<'a, 'c>(
+    input: &'a [u8],
+    table_id: u64,
+    column_count: u64,
+    cache: &'c TableMapCache,
+) -> Result<&'c TableInfo, nom::Err<(&'a [u8], ErrorKind)>> {
+    match cache.get(table_id) {
+        Some(table) if table.columns_type.len() as u64 == column_count => Ok(table),
+        Some(table) => {
+            log::error!(
+                "rows event of table_id {} has {} columns, but TableMap of {}.{} has {}",
+                table_id,
+                column_count,
+                table.schema,
+                table.table_name,
+                table.columns_type.len()
+            );
+            Err(nom::Err::Failure((input, ErrorKind::Verify)))
+        }
+        None => {
+            log::error!(
+                "unknown table_id: {}, TableMap event not seen yet",
+                table_id
+            );
+            Err(nom::Err::Failure((input, ErrorKind::MapOpt)))
+        }
+    }
+}
+
+/// byte length of one column bitmap, `images` bitmaps must fit in rest of event
+fn bitmap_len<'a>(
+    input: &'a [u8],
+    column_count: u64,
+    images: u64,
+) -> Result<u64, nom::Err<(&'a [u8], ErrorKind)>> {
+    let len = column_count.saturating_add(7) / 8;
+    if len.saturating_mul(images) > input.len() as u64 {
+        log::error!(
+            "column_count {} needs {} bytes of bitmaps, but only {} bytes left",
+            column_count,
+            len.saturating_mul(images),
+            input.len()
+        );
+        return Err(nom::Err::Error((input, ErrorKind::TooLarge)));
+    }
+    Ok(len)
+}
+
+fn bit_set(bits: &[u8], idx: usize) -> bool {
+    bits.get(idx / 8).is_some_and(|b| (b >> (idx % 8)) & 1 == 1)
+}
+
+/// parse one row image: null bitmap followed by values of non-null columns
+///
+/// only columns set in `image_bits` are stored, e.g. primary key columns of a minimal
+/// before image, null bitmap is counted on these columns too
 fn parse_row<'a>(
     input: &'a [u8],
-    init_idx: usize,
-    col_def: &Vec<ColTypes>,
+    image_bits: &[u8],
+    col_def: &[ColTypes],
 ) -> IResult<&'a [u8], Vec<ColValues>> {
-    let mut index = if input.len() != 0 { init_idx } else { 0 };
-    let mut ret = vec![];
-    for col in col_def {
-        let (_, (offset, col_val)) = col.parse(&input[index..])?;
+    let present = rows::present_columns(image_bits, col_def.len() as u64).count();
+    let (mut i, null_bits) = take((present + 7) / 8)(input)?;
+    let mut ret = Vec::with_capacity(present);
+    for (pos, idx) in rows::present_columns(image_bits, col_def.len() as u64).enumerate() {
+        if bit_set(null_bits, pos) {
+            ret.push(ColValues::Null);
+            continue;
+        }
+        let (remain, (_, col_val)) = col_def[idx].parse(i)?;
         ret.push(col_val);
-        index += offset;
+        i = remain;
+    }
+    Ok((i, ret))
+}
+
+/// parse all row images packed in rows event body, input must be consumed exactly
+///
+/// row images take column bitmaps of `images` in turn, e.g. before and after image bits of
+/// UPDATE event
+fn parse_rows<'a>(
+    input: &'a [u8],
+    images: &[&[u8]],
+    col_def: &[ColTypes],
+) -> IResult<&'a [u8], Vec<Vec<ColValues>>> {
+    #[cfg(feature = "tracing")]
+    let span = tracing::trace_span!("parse_rows", columns = col_def.len(), len = input.len());
+    #[cfg(feature = "tracing")]
+    let _enter = span.enter();
+    let mut rows = vec![];
+    let mut i = input;
+    while !i.is_empty() {
+        let image_bits = images[rows.len() % images.len()];
+        let (remain, row) = match parse_row(i, image_bits, col_def) {
+            Ok(ret) => ret,
+            Err(e) => {
+                #[cfg(feature = "tracing")]
+                tracing::debug!(
+                    row = rows.len(),
+                    offset = input.len() - i.len(),
+                    "failed to decode row"
+                );
+                return Err(e);
+            }
+        };
+        // row without any byte would loop forever
+        if remain.len() == i.len() {
+            return Err(nom::Err::Error((i, ErrorKind::Many0)));
+        }
+        rows.push(row);
+        i = remain;
+    }
+    #[cfg(feature = "tracing")]
+    tracing::trace!(rows = rows.len(), "rows decoded");
+    Ok((i, rows))
+}
+
+/// bit of `value_options` in PartialUpdateRows event
+const PARTIAL_JSON_UPDATES: u64 = 1;
+
+/// parse after image of PartialUpdateRows event
+///
+/// JSON column whose bit is set in `partial_bits` stores a diff vector instead of
+/// a document, the diffs are applied to value of before image
+fn parse_partial_row<'a>(
+    input: &'a [u8],
+    image_bits: &[u8],
+    col_def: &[ColTypes],
+    partial_bits: &[u8],
+    before: &[ColValues],
+    before_bits: &[u8],
+) -> IResult<&'a [u8], Vec<ColValues>> {
+    let present = rows::present_columns(image_bits, col_def.len() as u64).count();
+    let (mut i, null_bits) = take((present + 7) / 8)(input)?;
+    let mut ret = Vec::with_capacity(present);
+    let mut json_idx = 0;
+    for (pos, idx) in rows::present_columns(image_bits, col_def.len() as u64).enumerate() {
+        let col = &col_def[idx];
+        // partial bits are counted on JSON columns only
+        let partial = if let ColTypes::Json(_) = col {
+            json_idx += 1;
+            bit_set(partial_bits, json_idx - 1)
+        } else {
+            false
+        };
+        if bit_set(null_bits, pos) {
+            ret.push(ColValues::Null);
+            continue;
+        }
+        if !partial {
+            let (remain, (_, col_val)) = col.parse(i)?;
+            ret.push(col_val);
+            i = remain;
+            continue;
+        }
+        let (remain, diffs) = json::parse_diffs(i)?;
+        let before_pos = rows::present_columns(before_bits, col_def.len() as u64)
+            .position(|before_idx| before_idx == idx);
+        let mut doc = match before_pos.and_then(|pos| before.get(pos)) {
+            Some(ColValues::Json(doc)) => doc.clone(),
+            _ => {
+                log::error!("partial JSON update of column {} without before value", idx);
+                return Err(nom::Err::Error((i, ErrorKind::Verify)));
+            }
+        };
+        for diff in diffs.iter() {
+            if !doc.apply(diff) {
+                log::error!(
+                    "can't apply JSON diff on path {} of column {}",
+                    diff.path,
+                    idx
+                );
+                return Err(nom::Err::Error((i, ErrorKind::Verify)));
+            }
+        }
+        ret.push(ColValues::Json(doc));
+        i = remain;
     }
-    Ok((&input[index..], ret))
+    Ok((i, ret))
 }
 
-fn parse_write_rows_v2<'a>(input: &'a [u8], header: Header) -> IResult<&'a [u8], Event> {
+/// parse row images of PartialUpdateRows event
+///
+/// every before image is followed by len encoded `value_options`, if PARTIAL_JSON_UPDATES
+/// is set, a bitmap of JSON columns follows, then the after image
+fn parse_partial_update_rows<'a>(
+    input: &'a [u8],
+    before_bits: &[u8],
+    after_bits: &[u8],
+    col_def: &[ColTypes],
+) -> IResult<&'a [u8], Vec<Vec<ColValues>>> {
+    let json_count = rows::present_columns(after_bits, col_def.len() as u64)
+        .filter(|&idx| matches!(col_def[idx], ColTypes::Json(_)))
+        .count();
+    let mut rows = vec![];
+    let mut i = input;
+    while !i.is_empty() {
+        let (remain, before) = parse_row(i, before_bits, col_def)?;
+        let (remain, (_, value_options)) = int_lenenc(remain)?;
+        let (remain, partial_bits) = if value_options & PARTIAL_JSON_UPDATES != 0 {
+            take((json_count + 7) / 8)(remain)?
+        } else {
+            (remain, &[][..])
+        };
+        let (remain, after) = parse_partial_row(
+            remain,
+            after_bits,
+            col_def,
+            partial_bits,
+            &before,
+            before_bits,
+        )?;
+        rows.push(before);
+        rows.push(after);
+        i = remain;
+    }
+    Ok((i, rows))
+}
+
+fn parse_write_rows_v2<'a>(
+    input: &'a [u8],
+    header: Header,
+    cache: &TableMapCache,
+) -> VerboseResult<'a, Event> {
     let (i, (table_id, flags, extra_data_len, extra_data, (encode_len, column_count))) =
         parse_part_row_event(input)?;
-    let bit_len = (column_count + 7) / 8;
-    let (i, inserted_image_bits) = map(take(bit_len), |s: &[u8]| s.to_vec())(i)?;
-    let (i, col_data) = take(
-        header.event_size
-            - 19
-            - 6
-            - 2
-            - extra_data_len as u32
-            - encode_len as u32
-            - ((column_count as u32 + 7) / 8)
-            - 4,
-    )(i)?;
-    let (_, rows) = many1(|s| {
-        parse_row(
-            s,
-            bit_len as usize,
-            TABLE_MAP.lock().unwrap().get(&table_id).unwrap(),
-        )
-    })(col_data)?;
-    let (i, checksum) = le_u32(i)?;
+    let bit_len = context("reading image bits", bitmap_len(i, column_count, 1))?;
+    let (i, inserted_image_bits) = context(
+        "reading image bits",
+        map(take(bit_len), |s: &[u8]| s.to_vec())(i),
+    )?;
+    let col_len = context(
+        "reading rows",
+        rest_len(
+            i,
+            &header,
+            19 + 6 + 2 + extra_data_len as u64 + encode_len as u64 + bit_len + 4,
+        ),
+    )?;
+    let (i, col_data) = context("reading rows", take(col_len)(i))?;
+    let table = context(
+        "looking up TableMap",
+        lookup_table(input, table_id, column_count, cache),
+    )?;
+    let (_, rows) = context(
+        "reading rows",
+        parse_rows(col_data, &[&inserted_image_bits], &table.columns_type),
+    )?;
+    let (i, checksum) = context("reading checksum", le_u32(i))?;
     Ok((
         i,
         Event::WriteRowsV2 {
@@ -1265,30 +3047,37 @@ fn parse_write_rows_v2<'a>(input: &'a [u8], header: Header) -> IResult<&'a [u8],
     ))
 }
 
-fn parse_delete_rows_v2<'a>(input: &'a [u8], header: Header) -> IResult<&'a [u8], Event> {
+fn parse_delete_rows_v2<'a>(
+    input: &'a [u8],
+    header: Header,
+    cache: &TableMapCache,
+) -> VerboseResult<'a, Event> {
     let (i, (table_id, flags, extra_data_len, extra_data, (encode_len, column_count))) =
         parse_part_row_event(input)?;
 
-    let bit_len = (column_count + 7) / 8;
-    let (i, deleted_image_bits) = map(take(bit_len), |s: &[u8]| s.to_vec())(i)?;
-    let (i, col_data) = take(
-        header.event_size
-            - 19
-            - 6
-            - 2
-            - extra_data_len as u32
-            - encode_len as u32
-            - ((column_count as u32 + 7) / 8)
-            - 4,
-    )(i)?;
-    let (_, rows) = many1(|s| {
-        parse_row(
-            s,
-            bit_len as usize,
-            TABLE_MAP.lock().unwrap().get(&table_id).unwrap(),
-        )
-    })(col_data)?;
-    let (i, checksum) = le_u32(i)?;
+    let bit_len = context("reading image bits", bitmap_len(i, column_count, 1))?;
+    let (i, deleted_image_bits) = context(
+        "reading image bits",
+        map(take(bit_len), |s: &[u8]| s.to_vec())(i),
+    )?;
+    let col_len = context(
+        "reading rows",
+        rest_len(
+            i,
+            &header,
+            19 + 6 + 2 + extra_data_len as u64 + encode_len as u64 + bit_len + 4,
+        ),
+    )?;
+    let (i, col_data) = context("reading rows", take(col_len)(i))?;
+    let table = context(
+        "looking up TableMap",
+        lookup_table(input, table_id, column_count, cache),
+    )?;
+    let (_, rows) = context(
+        "reading rows",
+        parse_rows(col_data, &[&deleted_image_bits], &table.columns_type),
+    )?;
+    let (i, checksum) = context("reading checksum", le_u32(i))?;
     Ok((
         i,
         Event::DeleteRowsV2 {
@@ -1305,32 +3094,54 @@ fn parse_delete_rows_v2<'a>(input: &'a [u8], header: Header) -> IResult<&'a [u8]
     ))
 }
 
-fn parse_update_rows_v2<'a>(input: &'a [u8], header: Header) -> IResult<&'a [u8], Event> {
+fn parse_update_rows_v2<'a>(
+    input: &'a [u8],
+    header: Header,
+    cache: &TableMapCache,
+) -> VerboseResult<'a, Event> {
     let (i, (table_id, flags, extra_data_len, extra_data, (encode_len, column_count))) =
         parse_part_row_event(input)?;
 
-    let bit_len = (column_count + 7) / 8;
-    let (i, before_image_bits) = map(take(bit_len), |s: &[u8]| s.to_vec())(i)?;
-    let (i, after_image_bits) = map(take(bit_len), |s: &[u8]| s.to_vec())(i)?;
+    let bit_len = context("reading image bits", bitmap_len(i, column_count, 2))?;
+    let (i, (before_image_bits, after_image_bits)) = context(
+        "reading image bits",
+        tuple((
+            map(take(bit_len), |s: &[u8]| s.to_vec()),
+            map(take(bit_len), |s: &[u8]| s.to_vec()),
+        ))(i),
+    )?;
     // TODO I still don't know is it right or not :(
-    let (i, col_data) = take(
-        header.event_size as u64
-            - 19
-            - 6
-            - 2
-            - extra_data_len as u64
-            - encode_len as u64
-            - bit_len * 2
-            - 4,
-    )(i)?;
-    let (_, rows) = many1(|s| {
-        parse_row(
-            s,
-            bit_len as usize,
-            TABLE_MAP.lock().unwrap().get(&table_id).unwrap(),
-        )
-    })(col_data)?;
-    let (i, checksum) = le_u32(i)?;
+    let col_len = context(
+        "reading rows",
+        rest_len(
+            i,
+            &header,
+            19 + 6 + 2 + extra_data_len as u64 + encode_len as u64 + bit_len * 2 + 4,
+        ),
+    )?;
+    let (i, col_data) = context("reading rows", take(col_len)(i))?;
+    let table = context(
+        "looking up TableMap",
+        lookup_table(input, table_id, column_count, cache),
+    )?;
+    let (_, rows) = context(
+        "reading rows",
+        if header.event_type == EventType::PartialUpdateRows.as_u8() {
+            parse_partial_update_rows(
+                col_data,
+                &before_image_bits,
+                &after_image_bits,
+                &table.columns_type,
+            )
+        } else {
+            parse_rows(
+                col_data,
+                &[&before_image_bits, &after_image_bits],
+                &table.columns_type,
+            )
+        },
+    )?;
+    let (i, checksum) = context("reading checksum", le_u32(i))?;
     Ok((
         i,
         Event::UpdateRowsV2 {
@@ -1,9 +1,14 @@
-use crate::utils::{string_var, extract_string, pu32, string_nul};
+use crate::{
+    charset::{charset_name, collation_name},
+    utils::{extract_string, string_nul, string_var},
+};
+use alloc::{string::String, vec, vec::Vec};
 use nom::{
-    bytes::complete::take,
+    bytes::complete::{tag, take},
     combinator::map,
+    error::ErrorKind,
     multi::many_m_n,
-    number::complete::{le_u16, le_u32, le_u64, le_u8},
+    number::complete::{le_u16, le_u24, le_u32, le_u64, le_u8},
     sequence::tuple,
     IResult,
 };
@@ -28,6 +33,40 @@ pub enum QueryStatusVar {
     Q_MICROSECONDS(u32),
 }
 
+impl QueryStatusVar {
+    /// character set of client, from `Q_CHARSET_CODE`
+    pub fn client_charset(&self) -> Option<&'static str> {
+        match self {
+            QueryStatusVar::Q_CHARSET_CODE(client, _, _) => charset_name(*client),
+            _ => None,
+        }
+    }
+
+    /// collation of connection, from `Q_CHARSET_CODE`
+    pub fn collation_connection(&self) -> Option<&'static str> {
+        match self {
+            QueryStatusVar::Q_CHARSET_CODE(_, conn, _) => collation_name(*conn),
+            _ => None,
+        }
+    }
+
+    /// collation of server, from `Q_CHARSET_CODE`
+    pub fn collation_server(&self) -> Option<&'static str> {
+        match self {
+            QueryStatusVar::Q_CHARSET_CODE(_, _, server) => collation_name(*server),
+            _ => None,
+        }
+    }
+
+    /// collation of default database, from `Q_CHARSET_DATABASE_CODE`
+    pub fn collation_database(&self) -> Option<&'static str> {
+        match self {
+            QueryStatusVar::Q_CHARSET_DATABASE_CODE(id) => collation_name(*id),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, PartialEq, Eq, Clone)]
 pub struct Q_FLAGS2_CODE_VAL {
     pub auto_is_null: bool,
@@ -77,10 +116,10 @@ pub fn parse_status_var<'a>(input: &'a [u8]) -> IResult<&'a [u8], QueryStatusVar
     match key {
         0x00 => {
             let (i, code) = le_u32(i)?;
-            let auto_is_null = (code >> 14) % 2 == 1;
-            let auto_commit = (code >> 19) % 2 == 0;
-            let foreign_key_checks = (code >> 26) % 2 == 0;
-            let unique_checks = (code >> 27) % 2 == 0;
+            let auto_is_null = (code >> 14) & 1 == 1;
+            let auto_commit = (code >> 19) & 1 == 0;
+            let foreign_key_checks = (code >> 26) & 1 == 0;
+            let unique_checks = (code >> 27) & 1 == 0;
             Ok((
                 i,
                 QueryStatusVar::Q_FLAGS2_CODE(Q_FLAGS2_CODE_VAL {
@@ -94,46 +133,45 @@ pub fn parse_status_var<'a>(input: &'a [u8]) -> IResult<&'a [u8], QueryStatusVar
         0x01 => {
             let (i, code) = le_u64(i)?;
             let val = Q_SQL_MODE_CODE_VAL {
-                real_as_float: (code >> 0) % 2 == 1,
-                pipes_as_concat: (code >> 1) % 2 == 1,
-                ansi_quotes: (code >> 2) % 2 == 1,
-                ignore_space: (code >> 3) % 2 == 1,
-                not_used: (code >> 4) % 2 == 1,
-                only_full_group_by: (code >> 5) % 2 == 1,
-                no_unsigned_subtraction: (code >> 6) % 2 == 1,
-                no_dir_in_create: (code >> 7) % 2 == 1,
-                postgresql: (code >> 8) % 2 == 1,
-                oracle: (code >> 9) % 2 == 1,
-                mssql: (code >> 10) % 2 == 1,
-                db2: (code >> 11) % 2 == 1,
-                maxdb: (code >> 12) % 2 == 1,
-                no_key_options: (code >> 13) % 2 == 1,
-                no_table_options: (code >> 14) % 2 == 1,
-                no_field_options: (code >> 15) % 2 == 1,
-                mysql323: (code >> 16) % 2 == 1,
-                mysql40: (code >> 17) % 2 == 1,
-                ansi: (code >> 18) % 2 == 1,
-                no_auto_value_on_zero: (code >> 19) % 2 == 1,
-                no_backslash_escapes: (code >> 20) % 2 == 1,
-                strict_trans_tables: (code >> 21) % 2 == 1,
-                strict_all_tables: (code >> 22) % 2 == 1,
-                no_zero_in_date: (code >> 23) % 2 == 1,
-                no_zero_date: (code >> 24) % 2 == 1,
-                invalid_dates: (code >> 25) % 2 == 1,
-                error_for_division_by_zero: (code >> 26) % 2 == 1,
-                traditional: (code >> 27) % 2 == 1,
-                no_auto_create_user: (code >> 28) % 2 == 1,
-                high_not_precedence: (code >> 29) % 2 == 1,
-                no_engine_substitution: (code >> 30) % 2 == 1,
-                pad_char_to_full_length: (code >> 31) % 2 == 1,
+                real_as_float: code & 1 == 1,
+                pipes_as_concat: (code >> 1) & 1 == 1,
+                ansi_quotes: (code >> 2) & 1 == 1,
+                ignore_space: (code >> 3) & 1 == 1,
+                not_used: (code >> 4) & 1 == 1,
+                only_full_group_by: (code >> 5) & 1 == 1,
+                no_unsigned_subtraction: (code >> 6) & 1 == 1,
+                no_dir_in_create: (code >> 7) & 1 == 1,
+                postgresql: (code >> 8) & 1 == 1,
+                oracle: (code >> 9) & 1 == 1,
+                mssql: (code >> 10) & 1 == 1,
+                db2: (code >> 11) & 1 == 1,
+                maxdb: (code >> 12) & 1 == 1,
+                no_key_options: (code >> 13) & 1 == 1,
+                no_table_options: (code >> 14) & 1 == 1,
+                no_field_options: (code >> 15) & 1 == 1,
+                mysql323: (code >> 16) & 1 == 1,
+                mysql40: (code >> 17) & 1 == 1,
+                ansi: (code >> 18) & 1 == 1,
+                no_auto_value_on_zero: (code >> 19) & 1 == 1,
+                no_backslash_escapes: (code >> 20) & 1 == 1,
+                strict_trans_tables: (code >> 21) & 1 == 1,
+                strict_all_tables: (code >> 22) & 1 == 1,
+                no_zero_in_date: (code >> 23) & 1 == 1,
+                no_zero_date: (code >> 24) & 1 == 1,
+                invalid_dates: (code >> 25) & 1 == 1,
+                error_for_division_by_zero: (code >> 26) & 1 == 1,
+                traditional: (code >> 27) & 1 == 1,
+                no_auto_create_user: (code >> 28) & 1 == 1,
+                high_not_precedence: (code >> 29) & 1 == 1,
+                no_engine_substitution: (code >> 30) & 1 == 1,
+                pad_char_to_full_length: (code >> 31) & 1 == 1,
             };
             Ok((i, QueryStatusVar::Q_SQL_MODE_CODE(val)))
         }
         0x02 => {
             let (i, len) = le_u8(i)?;
             let (i, val) = map(take(len), |s: &[u8]| string_var(s, len as usize))(i)?;
-            let (i, term) = le_u8(i)?;
-            assert_eq!(term, 0x00);
+            let (i, _) = tag([0x00])(i)?;
             Ok((i, QueryStatusVar::Q_CATALOG(val)))
         }
         0x03 => {
@@ -171,7 +209,216 @@ pub fn parse_status_var<'a>(input: &'a [u8]) -> IResult<&'a [u8], QueryStatusVar
             let (i, val) = many_m_n(count as usize, count as usize, string_nul)(i)?;
             Ok((i, QueryStatusVar::Q_UPDATED_DB_NAMES(val)))
         }
-        0x0d => map(pu32, |val| QueryStatusVar::Q_MICROSECONDS(val))(i),
-        __ => unreachable!(),
+        0x0d => map(le_u24, |val| QueryStatusVar::Q_MICROSECONDS(val))(i),
+        _ => {
+            log::error!("unknown status var key {}", key);
+            Err(nom::Err::Error((input, ErrorKind::Switch)))
+        }
+    }
+}
+
+/// encode status var back to binlog layout, reverse of `parse_status_var`
+pub fn encode_status_var(var: &QueryStatusVar) -> Vec<u8> {
+    let mut buf = vec![];
+    match var {
+        QueryStatusVar::Q_FLAGS2_CODE(val) => {
+            let mut code = 0u32;
+            if val.auto_is_null {
+                code |= 1 << 14;
+            }
+            if !val.auto_commit {
+                code |= 1 << 19;
+            }
+            if !val.foreign_key_checks {
+                code |= 1 << 26;
+            }
+            if !val.unique_checks {
+                code |= 1 << 27;
+            }
+            buf.push(0x00);
+            buf.extend_from_slice(&code.to_le_bytes());
+        }
+        QueryStatusVar::Q_SQL_MODE_CODE(val) => {
+            let bits = [
+                val.real_as_float,
+                val.pipes_as_concat,
+                val.ansi_quotes,
+                val.ignore_space,
+                val.not_used,
+                val.only_full_group_by,
+                val.no_unsigned_subtraction,
+                val.no_dir_in_create,
+                val.postgresql,
+                val.oracle,
+                val.mssql,
+                val.db2,
+                val.maxdb,
+                val.no_key_options,
+                val.no_table_options,
+                val.no_field_options,
+                val.mysql323,
+                val.mysql40,
+                val.ansi,
+                val.no_auto_value_on_zero,
+                val.no_backslash_escapes,
+                val.strict_trans_tables,
+                val.strict_all_tables,
+                val.no_zero_in_date,
+                val.no_zero_date,
+                val.invalid_dates,
+                val.error_for_division_by_zero,
+                val.traditional,
+                val.no_auto_create_user,
+                val.high_not_precedence,
+                val.no_engine_substitution,
+                val.pad_char_to_full_length,
+            ];
+            let code = bits.iter().enumerate().fold(
+                0u64,
+                |acc, (idx, &set)| if set { acc | 1 << idx } else { acc },
+            );
+            buf.push(0x01);
+            buf.extend_from_slice(&code.to_le_bytes());
+        }
+        QueryStatusVar::Q_CATALOG(val) => {
+            buf.push(0x02);
+            buf.push(val.len() as u8);
+            buf.extend_from_slice(val.as_bytes());
+            buf.push(0x00);
+        }
+        QueryStatusVar::Q_AUTO_INCREMENT(incr, offset) => {
+            buf.push(0x03);
+            buf.extend_from_slice(&incr.to_le_bytes());
+            buf.extend_from_slice(&offset.to_le_bytes());
+        }
+        QueryStatusVar::Q_CHARSET_CODE(client, conn, server) => {
+            buf.push(0x04);
+            buf.extend_from_slice(&client.to_le_bytes());
+            buf.extend_from_slice(&conn.to_le_bytes());
+            buf.extend_from_slice(&server.to_le_bytes());
+        }
+        QueryStatusVar::Q_TIME_ZONE_CODE(tz) => {
+            buf.push(0x05);
+            buf.push(tz.len() as u8);
+            buf.extend_from_slice(tz.as_bytes());
+        }
+        QueryStatusVar::Q_CATALOG_NZ_CODE(val) => {
+            buf.push(0x06);
+            buf.push(val.len() as u8);
+            buf.extend_from_slice(val.as_bytes());
+        }
+        QueryStatusVar::Q_LC_TIME_NAMES_CODE(v) => {
+            buf.push(0x07);
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+        QueryStatusVar::Q_CHARSET_DATABASE_CODE(v) => {
+            buf.push(0x08);
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+        QueryStatusVar::Q_TABLE_MAP_FOR_UPDATE_CODE(v) => {
+            buf.push(0x09);
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+        QueryStatusVar::Q_MASTER_DATA_WRITTEN_CODE(v) => {
+            buf.push(0x0a);
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+        QueryStatusVar::Q_INVOKERS(user, host) => {
+            buf.push(0x0b);
+            buf.push(user.len() as u8);
+            buf.extend_from_slice(user.as_bytes());
+            buf.push(host.len() as u8);
+            buf.extend_from_slice(host.as_bytes());
+        }
+        QueryStatusVar::Q_UPDATED_DB_NAMES(names) => {
+            buf.push(0x0c);
+            buf.push(names.len() as u8);
+            for name in names {
+                buf.extend_from_slice(name.as_bytes());
+                buf.push(0x00);
+            }
+        }
+        QueryStatusVar::Q_MICROSECONDS(val) => {
+            buf.push(0x0d);
+            buf.extend_from_slice(&val.to_le_bytes()[..3]);
+        }
+    }
+    buf
+}
+
+/// skip leading whitespaces and comments(`/* */`, `-- ` and `#`) of a statement
+pub fn skip_comments(sql: &str) -> &str {
+    let mut sql = sql.trim_start();
+    loop {
+        if sql.starts_with("/*") {
+            sql = match sql.find("*/") {
+                Some(end) => sql[end + 2..].trim_start(),
+                None => "",
+            };
+        } else if sql.starts_with("--") || sql.starts_with('#') {
+            sql = match sql.find('\n') {
+                Some(end) => sql[end + 1..].trim_start(),
+                None => "",
+            };
+        } else {
+            return sql;
+        }
+    }
+}
+
+const DDL_KEYWORDS: [&str; 5] = ["CREATE", "ALTER", "DROP", "TRUNCATE", "RENAME"];
+
+/// leading keywords of statement, comments are skipped
+fn keywords(sql: &str) -> impl Iterator<Item = &str> {
+    skip_comments(sql)
+        .split(|c: char| !c.is_ascii_alphabetic())
+        .filter(|w| !w.is_empty())
+}
+
+/// check if statement starts with a DDL keyword, case insensitive
+pub fn is_ddl(sql: &str) -> bool {
+    let keyword = keywords(sql).next().unwrap_or("");
+    DDL_KEYWORDS.iter().any(|k| k.eq_ignore_ascii_case(keyword))
+}
+
+/// kind of statement carried by Query event
+#[derive(Debug, Serialize, PartialEq, Eq, Clone, Copy)]
+pub enum StatementKind {
+    Begin,
+    Commit,
+    Rollback,
+    Ddl,
+    Dml,
+}
+
+impl StatementKind {
+    /// classify statement by its leading keywords, case insensitive
+    pub fn classify(sql: &str) -> Self {
+        let mut words = keywords(sql);
+        let first = words.next().unwrap_or("");
+        if first.eq_ignore_ascii_case("BEGIN")
+            || (first.eq_ignore_ascii_case("START")
+                && words
+                    .next()
+                    .is_some_and(|w| w.eq_ignore_ascii_case("TRANSACTION")))
+        {
+            StatementKind::Begin
+        } else if first.eq_ignore_ascii_case("COMMIT") {
+            StatementKind::Commit
+        } else if first.eq_ignore_ascii_case("ROLLBACK") {
+            StatementKind::Rollback
+        } else if is_ddl(sql) {
+            StatementKind::Ddl
+        } else {
+            StatementKind::Dml
+        }
     }
 }
+
+/// sql text of Query event with its default schema
+#[derive(Debug, Serialize, PartialEq, Eq, Clone)]
+pub struct Statement {
+    pub schema: String,
+    pub sql: String,
+    pub kind: StatementKind,
+}
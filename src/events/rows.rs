@@ -1,5 +1,11 @@
-use crate::utils::extract_string;
-use nom::{bytes::complete::take, combinator::map, number::complete::le_u8, IResult};
+use crate::{
+    mysql::{ColTypes, ColValues},
+    utils::extract_string,
+};
+use alloc::{string::String, vec::Vec};
+use nom::{
+    bytes::complete::take, combinator::map, error::ErrorKind, number::complete::le_u8, IResult,
+};
 use serde::Serialize;
 
 #[derive(Debug, Serialize, PartialEq, Eq, Clone)]
@@ -7,9 +13,22 @@ pub struct Flags {
     pub end_of_stmt: bool,
     pub foreign_key_checks: bool,
     pub unique_key_checks: bool,
+    /// COMPLETE_ROWS_F, rows carry values of all columns of table
     pub has_columns: bool,
 }
 
+impl Flags {
+    /// decode `flags` of rows event, check flags are set when NO_*_CHECKS bits are clear
+    pub fn from_u16(flag: u16) -> Self {
+        Flags {
+            end_of_stmt: flag & 1 == 1,
+            foreign_key_checks: (flag >> 1) & 1 == 0,
+            unique_key_checks: (flag >> 2) & 1 == 0,
+            has_columns: (flag >> 3) & 1 == 1,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, PartialEq, Eq, Clone)]
 pub struct ExtraData {
     pub d_type: ExtraDataType,
@@ -45,25 +64,75 @@ pub struct Row {
     pub values: Vec<u8>,
 }
 
+/// decoded value along with declared type of its column
+#[derive(Debug, Serialize, PartialEq, Clone)]
+pub struct TypedValue {
+    pub column_type: ColTypes,
+    pub value: ColValues,
+}
+
+/// constraint violation found when checking rows against table schema,
+/// `row` and `column` are 0-based
+#[derive(Debug, Serialize, PartialEq, Eq, Clone)]
+pub enum RowWarning {
+    /// row has `actual` values, but its image has `expected` columns
+    ColumnCount {
+        row: usize,
+        expected: usize,
+        actual: usize,
+    },
+    /// NULL in column which is not nullable
+    UnexpectedNull { row: usize, column: usize },
+    /// value can't be decoded from declared column type
+    TypeMismatch { row: usize, column: usize },
+}
+
+/// rows of rows event checked against table schema
+#[derive(Debug, Serialize, PartialEq, Clone)]
+pub struct ValidatedRows {
+    pub rows: Vec<Vec<TypedValue>>,
+    pub warnings: Vec<RowWarning>,
+}
+
+impl ValidatedRows {
+    pub fn is_valid(&self) -> bool {
+        self.warnings.is_empty()
+    }
+}
+
+/// iterate index of columns present in image bits(inserted_image_bits, before_image_bits etc.)
+pub fn present_columns<'a>(
+    image_bits: &'a [u8],
+    column_count: u64,
+) -> impl Iterator<Item = usize> + 'a {
+    (0..column_count as usize).filter(move |&idx| {
+        image_bits
+            .get(idx / 8)
+            .is_some_and(|b| (b >> (idx % 8)) & 1 == 1)
+    })
+}
+
 pub fn parse_extra_data<'a>(input: &'a [u8]) -> IResult<&'a [u8], ExtraData> {
-    let (i, d_type) = map(le_u8, |t: u8| match t {
+    let (i, t) = le_u8(input)?;
+    let d_type = match t {
         0x00 => ExtraDataType::RW_V_EXTRAINFO_TAG,
         _ => {
             log::error!("unknown extra data type {}", t);
-            unreachable!()
+            return Err(nom::Err::Error((input, ErrorKind::Switch)));
         }
-    })(input)?;
+    };
     let (i, length) = le_u8(i)?;
-    let (i, extra_data_format) = map(le_u8, |fmt: u8| match fmt {
+    let (i, fmt) = le_u8(i)?;
+    let extra_data_format = match fmt {
         0x00 => ExtraDataFormat::NDB,
         0x40 => ExtraDataFormat::OPEN1,
         0x41 => ExtraDataFormat::OPEN2,
         0xff => ExtraDataFormat::MULTI,
         _ => {
             log::error!("unknown extract data format {}", fmt);
-            unreachable!()
+            return Err(nom::Err::Error((input, ErrorKind::Switch)));
         }
-    })(i)?;
+    };
     let (i, payload) = map(take(length), |s: &[u8]| extract_string(s))(i)?;
     Ok((
         i,
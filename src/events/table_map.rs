@@ -0,0 +1,76 @@
+use super::Event;
+use crate::mysql::ColTypes;
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+use serde::Serialize;
+
+/// table info collected from TableMap event
+#[derive(Debug, Serialize, PartialEq, Eq, Clone)]
+pub struct TableInfo {
+    pub schema: String,
+    pub table_name: String,
+    pub columns_type: Vec<ColTypes>,
+}
+
+/// column of table, combines type, metadata and null bit of TableMap event
+#[derive(Debug, Serialize, PartialEq, Eq, Clone)]
+pub struct ColumnDef {
+    pub column_type: ColTypes,
+    // raw metadata bytes, empty if column type has no metadata
+    pub metadata: Vec<u8>,
+    pub nullable: bool,
+}
+
+/// schema of table described by a TableMap event
+#[derive(Debug, Serialize, PartialEq, Eq, Clone)]
+pub struct TableSchema {
+    pub schema: String,
+    pub table: String,
+    pub columns: Vec<ColumnDef>,
+}
+
+/// table_id -> table info mapping, rows events are decoded against it
+#[derive(Debug, Default, Clone)]
+pub struct TableMapCache {
+    tables: BTreeMap<u64, TableInfo>,
+}
+
+impl TableMapCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// record table info of a TableMap event, other events are ignored
+    pub fn insert(&mut self, event: &Event) {
+        if let Event::TableMap {
+            table_id,
+            schema,
+            table_name,
+            columns_type,
+            ..
+        } = event
+        {
+            self.tables.insert(
+                *table_id,
+                TableInfo {
+                    schema: schema.clone(),
+                    table_name: table_name.clone(),
+                    columns_type: columns_type.clone(),
+                },
+            );
+        }
+    }
+
+    pub fn get(&self, table_id: u64) -> Option<&TableInfo> {
+        self.tables.get(&table_id)
+    }
+
+    /// cache holding table of `table_id` only, e.g. to try parsing an event without
+    /// copying or changing this cache
+    pub(crate) fn subset(&self, table_id: u64) -> Self {
+        let mut tables = BTreeMap::new();
+        if let Some(table) = self.tables.get(&table_id) {
+            tables.insert(table_id, table.clone());
+        }
+        Self { tables }
+    }
+}
@@ -0,0 +1,101 @@
+//! composable predicates over events, e.g.
+//! `Filter::event_type(EventType::WriteRowsV2).and(Filter::table("db", "t"))`
+use crate::events::{event_type::EventType, table_map::TableMapCache, Event};
+use alloc::{
+    boxed::Box,
+    string::{String, ToString},
+};
+use core::ops::Not;
+
+/// predicate over events, see `BinlogStream::with_filter`
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Filter {
+    EventType(EventType),
+    /// TableMap and rows events of table
+    Table {
+        schema: String,
+        table: String,
+    },
+    /// TableMap and rows events of tables in schema, and Query events run in schema
+    Schema(String),
+    /// events starting at position in `[start, end)`
+    Position {
+        start: u64,
+        end: u64,
+    },
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+    Not(Box<Filter>),
+}
+
+impl Filter {
+    pub fn event_type(t: EventType) -> Self {
+        Filter::EventType(t)
+    }
+
+    pub fn table(schema: &str, table: &str) -> Self {
+        Filter::Table {
+            schema: schema.to_string(),
+            table: table.to_string(),
+        }
+    }
+
+    pub fn schema(schema: &str) -> Self {
+        Filter::Schema(schema.to_string())
+    }
+
+    pub fn position(start: u64, end: u64) -> Self {
+        Filter::Position { start, end }
+    }
+
+    pub fn and(self, other: Filter) -> Self {
+        Filter::And(Box::new(self), Box::new(other))
+    }
+
+    pub fn or(self, other: Filter) -> Self {
+        Filter::Or(Box::new(self), Box::new(other))
+    }
+
+    /// whether event passes filter, table of rows event is looked up in `tables`
+    pub fn matches(&self, event: &Event, tables: &TableMapCache) -> bool {
+        match self {
+            Filter::EventType(t) => event.header().kind() == *t,
+            Filter::Table { schema, table } => {
+                table_of(event, tables).is_some_and(|(s, t)| s == schema && t == table)
+            }
+            Filter::Schema(schema) => match event {
+                Event::Query { schema: s, .. } => s == schema,
+                event => table_of(event, tables).is_some_and(|(s, _)| s == schema),
+            },
+            Filter::Position { start, end } => {
+                let header = event.header();
+                // log_pos is end position of event
+                let pos = (header.log_pos as u64).saturating_sub(header.event_size as u64);
+                *start <= pos && pos < *end
+            }
+            Filter::And(a, b) => a.matches(event, tables) && b.matches(event, tables),
+            Filter::Or(a, b) => a.matches(event, tables) || b.matches(event, tables),
+            Filter::Not(f) => !f.matches(event, tables),
+        }
+    }
+}
+
+impl Not for Filter {
+    type Output = Filter;
+
+    fn not(self) -> Self::Output {
+        Filter::Not(Box::new(self))
+    }
+}
+
+/// (schema, table) of TableMap event, or of TableMap of rows event
+fn table_of<'a>(event: &'a Event, tables: &'a TableMapCache) -> Option<(&'a str, &'a str)> {
+    match event {
+        Event::TableMap {
+            schema, table_name, ..
+        } => Some((schema, table_name)),
+        event => tables
+            .get(event.table_id()?)
+            .map(|info| (info.schema.as_str(), info.table_name.as_str())),
+    }
+}
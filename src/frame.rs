@@ -0,0 +1,115 @@
+use crate::{
+    error::Error,
+    events::{
+        check_start, parse_header, table_map::TableMapCache, Event, ParseContext, ParseOptions,
+    },
+    stream::StreamEvent,
+};
+
+/// framing state shared by `BinlogReader` and `AsyncBinlogStream`, they only feed it bytes
+///
+/// reads may return any number of bytes, an event is buffered until all `event_size` bytes
+/// arrive
+pub(crate) struct FrameBuffer {
+    // bytes of magic number or event being read
+    buf: Vec<u8>,
+    filled: usize,
+    offset: usize,
+    cache: TableMapCache,
+    context: ParseContext,
+    started: bool,
+}
+
+impl FrameBuffer {
+    pub(crate) fn new() -> Self {
+        Self {
+            buf: Vec::new(),
+            filled: 0,
+            offset: 0,
+            cache: TableMapCache::new(),
+            context: ParseContext::default(),
+            started: false,
+        }
+    }
+
+    pub(crate) fn set_options(&mut self, options: ParseOptions) {
+        self.context.options = options;
+    }
+
+    pub(crate) fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// bytes required before current frame can be handled
+    fn needed(&self) -> Result<usize, Error> {
+        if !self.started {
+            return Ok(4);
+        } else if self.filled < 19 {
+            return Ok(19);
+        }
+        match parse_header(&self.buf[..self.filled]) {
+            Ok((_, header)) if header.event_size as usize > self.context.options.max_event_size => {
+                Err(Error::TooLarge {
+                    offset: self.offset,
+                    size: header.event_size as usize,
+                })
+            }
+            Ok((_, header)) => Ok((header.event_size as usize).max(19)),
+            Err(_) => Ok(19),
+        }
+    }
+
+    /// room to read next bytes into, None if current frame is complete
+    pub(crate) fn spare(&mut self) -> Result<Option<&mut [u8]>, Error> {
+        let needed = self.needed()?;
+        if self.filled >= needed {
+            return Ok(None);
+        }
+        self.buf.resize(needed, 0);
+        Ok(Some(&mut self.buf[self.filled..]))
+    }
+
+    /// record `n` bytes read into `spare`
+    pub(crate) fn fill(&mut self, n: usize) {
+        self.filled += n;
+    }
+
+    /// result of source reaching EOF, only allowed between events
+    pub(crate) fn eof(&self) -> Result<Option<StreamEvent>, Error> {
+        match (self.started, self.filled) {
+            (false, _) => Err(Error::BadMagic),
+            (true, 0) => Ok(None),
+            (true, _) => Err(Error::Truncated {
+                offset: self.offset,
+            }),
+        }
+    }
+
+    /// handle complete frame, None if it was the magic number
+    pub(crate) fn parse(&mut self) -> Result<Option<StreamEvent>, Error> {
+        if !self.started {
+            check_start(&self.buf[..4]).map_err(|_| Error::BadMagic)?;
+            self.started = true;
+            self.offset = 4;
+            self.filled = 0;
+            return Ok(None);
+        }
+        let (_, event) =
+            Event::parse_verbose(&self.buf[..self.filled], &self.context, &mut self.cache)
+                .map_err(|e| Error::from_nom(e, self.offset))?;
+        self.context.update(&event);
+        self.offset += self.filled;
+        self.filled = 0;
+        Ok(Some(StreamEvent::from(event)))
+    }
+}
+
+/// whether stream ends with `item`, it goes on only after events, rotates and heartbeats
+pub(crate) fn is_last(item: &Result<Option<StreamEvent>, Error>) -> bool {
+    !matches!(
+        item,
+        Ok(Some(StreamEvent::Event(_)))
+            | Ok(Some(StreamEvent::RotateTo { .. }))
+            | Ok(Some(StreamEvent::Heartbeat { .. }))
+    )
+}
@@ -0,0 +1,460 @@
+//! decode MySQL binary JSON and apply diffs of partial JSON updates
+//!
+//! ref: https://github.com/mysql/mysql-server/blob/8.0/sql/json_binary.h
+use crate::utils::{base64, int_fixed, int_lenenc};
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+use nom::{
+    bytes::complete::take,
+    error::ErrorKind,
+    number::complete::{le_f64, le_i16, le_i32, le_i64, le_u16, le_u32, le_u64, le_u8},
+    IResult,
+};
+use serde::Serialize;
+
+const SMALL_OBJECT: u8 = 0x00;
+const LARGE_OBJECT: u8 = 0x01;
+const SMALL_ARRAY: u8 = 0x02;
+const LARGE_ARRAY: u8 = 0x03;
+const LITERAL: u8 = 0x04;
+const INT16: u8 = 0x05;
+const UINT16: u8 = 0x06;
+const INT32: u8 = 0x07;
+const UINT32: u8 = 0x08;
+const INT64: u8 = 0x09;
+const UINT64: u8 = 0x0a;
+const DOUBLE: u8 = 0x0b;
+const STRING: u8 = 0x0c;
+const OPAQUE: u8 = 0x0f;
+
+// same as JSON_DOCUMENT_MAX_DEPTH of MySQL
+const MAX_DEPTH: usize = 100;
+
+/// decoded JSON document
+#[derive(Debug, Serialize, PartialEq, Clone)]
+pub enum JsonValue {
+    Null,
+    Bool(bool),
+    Int(i64),
+    UInt(u64),
+    Double(f64),
+    String(String),
+    Array(Vec<JsonValue>),
+    // members keep the order stored in binary
+    Object(Vec<(String, JsonValue)>),
+    // value of other MySQL type, e.g. DECIMAL or DATETIME, kept as raw bytes
+    Opaque { col_type: u8, data: Vec<u8> },
+}
+
+type JsonError<'a> = nom::Err<(&'a [u8], ErrorKind)>;
+
+/// parse binary JSON document, first byte is type of top level value
+///
+/// containers locate their elements by offsets, so the whole input is consumed
+pub fn parse_json(input: &[u8]) -> IResult<&[u8], JsonValue> {
+    let (data, t) = le_u8(input)?;
+    let value = parse_value(t, data, 0)?;
+    Ok((&data[data.len()..], value))
+}
+
+fn parse_value(t: u8, data: &[u8], depth: usize) -> Result<JsonValue, JsonError<'_>> {
+    let value = match t {
+        SMALL_OBJECT | LARGE_OBJECT | SMALL_ARRAY | LARGE_ARRAY => {
+            if depth >= MAX_DEPTH {
+                log::error!("JSON document is nested too deep");
+                return Err(nom::Err::Error((data, ErrorKind::TooLarge)));
+            }
+            let large = t == LARGE_OBJECT || t == LARGE_ARRAY;
+            let is_object = t == SMALL_OBJECT || t == LARGE_OBJECT;
+            parse_container(data, large, is_object, depth + 1)?
+        }
+        LITERAL => match le_u8(data)?.1 {
+            0x00 => JsonValue::Null,
+            0x01 => JsonValue::Bool(true),
+            0x02 => JsonValue::Bool(false),
+            v => {
+                log::error!("unknown JSON literal: {}", v);
+                return Err(nom::Err::Error((data, ErrorKind::Switch)));
+            }
+        },
+        INT16 => JsonValue::Int(le_i16(data)?.1 as i64),
+        UINT16 => JsonValue::UInt(le_u16(data)?.1 as u64),
+        INT32 => JsonValue::Int(le_i32(data)?.1 as i64),
+        UINT32 => JsonValue::UInt(le_u32(data)?.1 as u64),
+        INT64 => JsonValue::Int(le_i64(data)?.1),
+        UINT64 => JsonValue::UInt(le_u64(data)?.1),
+        DOUBLE => JsonValue::Double(le_f64(data)?.1),
+        STRING => {
+            let (i, len) = var_len(data)?;
+            let (_, s) = take(len)(i)?;
+            JsonValue::String(String::from_utf8_lossy(s).into_owned())
+        }
+        OPAQUE => {
+            let (i, col_type) = le_u8(data)?;
+            let (i, len) = var_len(i)?;
+            let (_, s) = take(len)(i)?;
+            JsonValue::Opaque {
+                col_type,
+                data: s.to_vec(),
+            }
+        }
+        t => {
+            log::error!("unknown JSON value type: {}", t);
+            return Err(nom::Err::Error((data, ErrorKind::Switch)));
+        }
+    };
+    Ok(value)
+}
+
+/// length of string and opaque value, 7 bits per byte, highest bit marks a following byte
+fn var_len(input: &[u8]) -> IResult<&[u8], usize> {
+    let mut len = 0usize;
+    for (idx, b) in input.iter().take(5).enumerate() {
+        len |= ((b & 0x7f) as usize) << (7 * idx);
+        if b & 0x80 == 0 {
+            return Ok((&input[idx + 1..], len));
+        }
+    }
+    Err(nom::Err::Error((input, ErrorKind::LengthValue)))
+}
+
+/// object or array, offsets are relative to start of element count
+///
+/// layout: count, size, key entries(object only), value entries, keys, values
+fn parse_container(
+    data: &[u8],
+    large: bool,
+    is_object: bool,
+    depth: usize,
+) -> Result<JsonValue, JsonError<'_>> {
+    let offset_size = if large { 4 } else { 2 };
+    let (i, count) = int_fixed(data, offset_size)?;
+    let (_, size) = int_fixed(i, offset_size)?;
+    let data = match data.get(..size as usize) {
+        Some(data) => data,
+        None => return Err(nom::Err::Error((data, ErrorKind::Eof))),
+    };
+    let (mut i, _) = take(offset_size as usize * 2)(data)?;
+    let mut keys = Vec::new();
+    if is_object {
+        for _ in 0..count {
+            let (remain, offset) = int_fixed(i, offset_size)?;
+            let (remain, len) = le_u16(remain)?;
+            let key = match data.get(offset as usize..offset as usize + len as usize) {
+                Some(key) => key,
+                None => return Err(nom::Err::Error((i, ErrorKind::Eof))),
+            };
+            keys.push(String::from_utf8_lossy(key).into_owned());
+            i = remain;
+        }
+    }
+    let mut values = Vec::with_capacity(keys.len());
+    for _ in 0..count {
+        let (remain, t) = le_u8(i)?;
+        let (remain, field) = take(offset_size)(remain)?;
+        // small scalars are stored in place of offset
+        let inlined = match t {
+            LITERAL | INT16 | UINT16 => true,
+            INT32 | UINT32 => large,
+            _ => false,
+        };
+        let value = if inlined {
+            parse_value(t, field, depth)?
+        } else {
+            let (_, offset) = int_fixed(field, offset_size)?;
+            match data.get(offset as usize..) {
+                Some(v) => parse_value(t, v, depth)?,
+                None => return Err(nom::Err::Error((field, ErrorKind::Eof))),
+            }
+        };
+        values.push(value);
+        i = remain;
+    }
+    Ok(if is_object {
+        JsonValue::Object(keys.into_iter().zip(values).collect())
+    } else {
+        JsonValue::Array(values)
+    })
+}
+
+fn write_str(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    f.write_str("\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            '\x08' => f.write_str("\\b")?,
+            '\x0c' => f.write_str("\\f")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    f.write_str("\"")
+}
+
+/// render as JSON text the way MySQL prints it, e.g. `{"a": [1, 2]}`
+///
+/// opaque values are rendered as `"base64:typeN:..."` strings
+impl fmt::Display for JsonValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonValue::Null => f.write_str("null"),
+            JsonValue::Bool(v) => write!(f, "{}", v),
+            JsonValue::Int(v) => write!(f, "{}", v),
+            JsonValue::UInt(v) => write!(f, "{}", v),
+            JsonValue::Double(v) => write!(f, "{:?}", v),
+            JsonValue::String(s) => write_str(f, s),
+            JsonValue::Array(values) => {
+                f.write_str("[")?;
+                for (idx, v) in values.iter().enumerate() {
+                    if idx != 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", v)?;
+                }
+                f.write_str("]")
+            }
+            JsonValue::Object(members) => {
+                f.write_str("{")?;
+                for (idx, (k, v)) in members.iter().enumerate() {
+                    if idx != 0 {
+                        f.write_str(", ")?;
+                    }
+                    write_str(f, k)?;
+                    write!(f, ": {}", v)?;
+                }
+                f.write_str("}")
+            }
+            JsonValue::Opaque { col_type, data } => {
+                write!(f, "\"base64:type{}:", col_type)?;
+                f.write_str(&base64(data))?;
+                f.write_str("\"")
+            }
+        }
+    }
+}
+
+/// operation of JSON diff
+#[derive(Debug, Serialize, PartialEq, Eq, Clone, Copy)]
+pub enum JsonDiffOp {
+    Replace,
+    Insert,
+    Remove,
+}
+
+/// one diff of partial JSON update, e.g. generated by JSON_SET, JSON_REPLACE or JSON_REMOVE
+#[derive(Debug, Serialize, PartialEq, Clone)]
+pub struct JsonDiff {
+    pub op: JsonDiffOp,
+    pub path: String,
+    // None for Remove
+    pub value: Option<JsonValue>,
+}
+
+/// parse diff vector of partial JSON update, 4 bytes length followed by diffs
+///
+/// source: https://github.com/mysql/mysql-server/blob/8.0/sql/json_diff.cc
+pub fn parse_diffs(input: &[u8]) -> IResult<&[u8], Vec<JsonDiff>> {
+    let (i, len) = le_u32(input)?;
+    let (i, mut data) = take(len)(i)?;
+    let mut diffs = Vec::new();
+    while !data.is_empty() {
+        let (remain, op) = le_u8(data)?;
+        let op = match op {
+            0 => JsonDiffOp::Replace,
+            1 => JsonDiffOp::Insert,
+            2 => JsonDiffOp::Remove,
+            op => {
+                log::error!("unknown JSON diff operation: {}", op);
+                return Err(nom::Err::Error((data, ErrorKind::Switch)));
+            }
+        };
+        let (remain, (_, path_len)) = int_lenenc(remain)?;
+        let (remain, path) = take(path_len)(remain)?;
+        let (remain, value) = if op == JsonDiffOp::Remove {
+            (remain, None)
+        } else {
+            let (remain, (_, value_len)) = int_lenenc(remain)?;
+            let (remain, value) = take(value_len)(remain)?;
+            (remain, Some(parse_json(value)?.1))
+        };
+        diffs.push(JsonDiff {
+            op,
+            path: String::from_utf8_lossy(path).into_owned(),
+            value,
+        });
+        data = remain;
+    }
+    Ok((i, diffs))
+}
+
+/// one leg of JSON path, `.key` or `[n]`
+#[derive(Debug, Serialize, PartialEq, Eq, Clone)]
+pub enum PathLeg {
+    Member(String),
+    Index(usize),
+}
+
+/// parse JSON path of diff, e.g. `$.a."b c"[1]`
+///
+/// return None for wildcards and other paths which can't address a single value
+pub fn parse_path(path: &str) -> Option<Vec<PathLeg>> {
+    let mut chars = path.trim().strip_prefix('$')?.chars().peekable();
+    let mut legs = Vec::new();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '.' if chars.peek() == Some(&'"') => {
+                chars.next();
+                let mut key = String::new();
+                loop {
+                    match chars.next()? {
+                        '"' => break,
+                        '\\' => key.push(chars.next()?),
+                        c => key.push(c),
+                    }
+                }
+                legs.push(PathLeg::Member(key));
+            }
+            '.' => {
+                let mut key = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c == '.' || c == '[' || c.is_whitespace() {
+                        break;
+                    }
+                    key.push(c);
+                    chars.next();
+                }
+                if key.is_empty() || key == "*" {
+                    return None;
+                }
+                legs.push(PathLeg::Member(key));
+            }
+            '[' => {
+                let mut idx = String::new();
+                loop {
+                    match chars.next()? {
+                        ']' => break,
+                        c => idx.push(c),
+                    }
+                }
+                legs.push(PathLeg::Index(idx.trim().parse().ok()?));
+            }
+            _ => return None,
+        }
+    }
+    Some(legs)
+}
+
+impl JsonValue {
+    /// convert to `serde_json::Value`, opaque values become their text form
+    ///
+    /// members of objects are re-sorted by `serde_json::Map` unless `preserve_order` feature
+    /// is enabled, `JsonValue::Object` itself always keeps the stored order
+    #[cfg(feature = "serde_json")]
+    pub fn to_json(&self) -> serde_json::Value {
+        use serde_json::{Map, Number, Value};
+
+        match self {
+            JsonValue::Null => Value::Null,
+            JsonValue::Bool(v) => Value::Bool(*v),
+            JsonValue::Int(v) => Value::Number(Number::from(*v)),
+            JsonValue::UInt(v) => Value::Number(Number::from(*v)),
+            JsonValue::Double(v) => Number::from_f64(*v).map_or(Value::Null, Value::Number),
+            JsonValue::String(v) => Value::String(v.clone()),
+            JsonValue::Array(values) => Value::Array(values.iter().map(|v| v.to_json()).collect()),
+            JsonValue::Object(members) => {
+                let mut map = Map::new();
+                for (k, v) in members {
+                    map.insert(k.clone(), v.to_json());
+                }
+                Value::Object(map)
+            }
+            JsonValue::Opaque { col_type, data } => {
+                Value::String(alloc::format!("base64:type{}:{}", col_type, base64(data)))
+            }
+        }
+    }
+
+    fn get_mut(&mut self, leg: &PathLeg) -> Option<&mut JsonValue> {
+        match (self, leg) {
+            (JsonValue::Object(members), PathLeg::Member(key)) => {
+                members.iter_mut().find(|(k, _)| k == key).map(|(_, v)| v)
+            }
+            (JsonValue::Array(values), PathLeg::Index(idx)) => values.get_mut(*idx),
+            _ => None,
+        }
+    }
+
+    /// apply diff of partial update in place
+    ///
+    /// return false if path doesn't lead to a value the operation can be applied to
+    pub fn apply(&mut self, diff: &JsonDiff) -> bool {
+        let legs = match parse_path(&diff.path) {
+            Some(legs) => legs,
+            None => return false,
+        };
+        let (last, parents) = match legs.split_last() {
+            Some(split) => split,
+            // whole document can only be replaced
+            None => match (diff.op, &diff.value) {
+                (JsonDiffOp::Replace, Some(value)) => {
+                    *self = value.clone();
+                    return true;
+                }
+                _ => return false,
+            },
+        };
+        let mut parent = self;
+        for leg in parents {
+            parent = match parent.get_mut(leg) {
+                Some(v) => v,
+                None => return false,
+            };
+        }
+        match (diff.op, &diff.value) {
+            (JsonDiffOp::Replace, Some(value)) => match parent.get_mut(last) {
+                Some(v) => {
+                    *v = value.clone();
+                    true
+                }
+                None => false,
+            },
+            (JsonDiffOp::Insert, Some(value)) => match (parent, last) {
+                (JsonValue::Object(members), PathLeg::Member(key)) => {
+                    match members.iter_mut().find(|(k, _)| k == key) {
+                        Some((_, v)) => *v = value.clone(),
+                        None => members.push((key.clone(), value.clone())),
+                    }
+                    true
+                }
+                (JsonValue::Array(values), PathLeg::Index(idx)) => {
+                    let idx = (*idx).min(values.len());
+                    values.insert(idx, value.clone());
+                    true
+                }
+                _ => false,
+            },
+            (JsonDiffOp::Remove, _) => match (parent, last) {
+                (JsonValue::Object(members), PathLeg::Member(key)) => {
+                    match members.iter().position(|(k, _)| k == key) {
+                        Some(pos) => {
+                            members.remove(pos);
+                            true
+                        }
+                        None => false,
+                    }
+                }
+                (JsonValue::Array(values), PathLeg::Index(idx)) if *idx < values.len() => {
+                    values.remove(*idx);
+                    true
+                }
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+}
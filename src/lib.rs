@@ -1,15 +1,62 @@
+#![cfg_attr(not(feature = "std"), no_std)]
 #![allow(non_camel_case_types)]
 
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod analyze;
+#[cfg(feature = "async")]
+mod async_stream;
+pub mod charset;
+#[cfg(feature = "cli")]
 mod connection;
+pub mod decimal;
+mod error;
 mod events;
+pub mod filter;
+#[cfg(feature = "std")]
+mod frame;
+pub mod json;
 mod mysql;
-mod utils;
+#[cfg(feature = "std")]
+mod reader;
+mod session;
+pub mod sql;
+mod stream;
+pub mod utils;
 
+#[cfg(feature = "async")]
+pub use async_stream::AsyncBinlogStream;
+#[cfg(feature = "cli")]
 pub use connection::Connection;
+pub use error::Error;
+#[cfg(feature = "mariadb")]
+pub use events::MariaGtidId;
 pub use events::{
-    query::{QueryStatusVar, Q_FLAGS2_CODE_VAL, Q_SQL_MODE_CODE_VAL},
-    rows::{ExtraData, ExtraDataFormat, Flags, Payload, Row},
-    DupHandlingFlags, EmptyFlags, Event, EventFlag, Header, IncidentEventType, IntVarEventType,
-    OptFlags, UserVarType,
+    check_start,
+    checksum::ChecksumAlg,
+    event_slices,
+    event_type::EventType,
+    gtid::{GtidSet, SidIntervals},
+    query::{QueryStatusVar, Statement, StatementKind, Q_FLAGS2_CODE_VAL, Q_SQL_MODE_CODE_VAL},
+    rows::{
+        present_columns, ExtraData, ExtraDataFormat, Flags, Payload, Row, RowWarning, TypedValue,
+        ValidatedRows,
+    },
+    table_map::{ColumnDef, TableInfo, TableMapCache, TableSchema},
+    Dialect, DupHandlingFlags, EmptyFlags, ErrorCode, Event, EventFlag, Header, IncidentEventType,
+    IntVarEventType, OptFlags, ParseContext, ParseOptions, PositionKey, Redacted, TableMapFlags,
+    UserVarType,
+};
+pub use filter::Filter;
+pub use json::{JsonDiff, JsonDiffOp, JsonValue};
+pub use mysql::{read_int, ColTypes, ColValues, IntValue};
+#[cfg(feature = "std")]
+pub use reader::BinlogReader;
+pub use session::{SessionContext, UserVariable};
+pub use stream::{
+    headers_iter, BinlogStream, DecodedEvent, DecodedStream, Headers, RawStream, StreamEvent,
+    StreamKind,
 };
-pub use mysql::{ColTypes, ColValues};
+#[cfg(feature = "std")]
+pub use stream::{parse_binlog_index, parse_binlog_path, read_binlog_path};
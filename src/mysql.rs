@@ -1,8 +1,14 @@
-use crate::utils::pu32;
+use crate::{
+    decimal,
+    json::{parse_json, JsonValue},
+    utils::int_fixed,
+};
+use alloc::{vec, vec::Vec};
 use nom::{
     bytes::complete::take,
     combinator::map,
-    number::complete::{le_u16, le_u8},
+    error::ErrorKind,
+    number::complete::{be_u32, le_i24, le_u16, le_u24, le_u32, le_u64, le_u8},
     sequence::tuple,
     IResult,
 };
@@ -25,12 +31,12 @@ pub enum ColTypes {
     Time,
     DateTime,
     Year,
-    NewDate, // internal used
+    NewDate, // internal used, packed like Date
     VarChar(u16),
     Bit(u8, u8),
-    Timestamp2(u8), // this field is suck!!! don't know how to parse
-    DateTime2(u8),  // this field is suck!!! don't know how to parse
-    Time2(u8),      // this field is suck!!! don't know how to parse
+    Timestamp2(u8), // meta is fsp
+    DateTime2(u8),  // meta is fsp
+    Time2(u8),      // meta is fsp
     NewDecimal(u8, u8),
     Enum,       // internal used
     Set,        // internal used
@@ -41,6 +47,7 @@ pub enum ColTypes {
     VarString(u8, u8),
     String(u8, u8),
     Geometry(u8),
+    Json(u8),
 }
 
 impl ColTypes {
@@ -77,11 +84,50 @@ impl ColTypes {
             ColTypes::VarString(_, _) => (253, 2),
             ColTypes::String(_, _) => (254, 2),
             ColTypes::Geometry(_) => (255, 1),
+            ColTypes::Json(_) => (245, 1),
+        }
+    }
+
+    /// return MySQL name of column type, e.g. "VARCHAR"
+    pub fn name(&self) -> &'static str {
+        match *self {
+            ColTypes::Decimal => "DECIMAL",
+            ColTypes::Tiny => "TINY",
+            ColTypes::Short => "SHORT",
+            ColTypes::Long => "LONG",
+            ColTypes::Float(_) => "FLOAT",
+            ColTypes::Double(_) => "DOUBLE",
+            ColTypes::Null => "NULL",
+            ColTypes::Timestamp => "TIMESTAMP",
+            ColTypes::LongLong => "LONGLONG",
+            ColTypes::Int24 => "INT24",
+            ColTypes::Date => "DATE",
+            ColTypes::Time => "TIME",
+            ColTypes::DateTime => "DATETIME",
+            ColTypes::Year => "YEAR",
+            ColTypes::NewDate => "NEWDATE",
+            ColTypes::VarChar(_) => "VARCHAR",
+            ColTypes::Bit(_, _) => "BIT",
+            ColTypes::Timestamp2(_) => "TIMESTAMP2",
+            ColTypes::DateTime2(_) => "DATETIME2",
+            ColTypes::Time2(_) => "TIME2",
+            ColTypes::NewDecimal(_, _) => "NEWDECIMAL",
+            ColTypes::Enum => "ENUM",
+            ColTypes::Set => "SET",
+            ColTypes::TinyBlob => "TINY_BLOB",
+            ColTypes::MediumBlob => "MEDIUM_BLOB",
+            ColTypes::LongBlob => "LONG_BLOB",
+            ColTypes::Blob(_) => "BLOB",
+            ColTypes::VarString(_, _) => "VAR_STRING",
+            ColTypes::String(_, _) => "STRING",
+            ColTypes::Geometry(_) => "GEOMETRY",
+            ColTypes::Json(_) => "JSON",
         }
     }
 
-    pub fn from_u8(t: u8) -> Self {
-        match t {
+    /// map type byte of TableMap event, return None if type is unknown
+    pub fn from_u8(t: u8) -> Option<Self> {
+        let col = match t {
             0 => ColTypes::Decimal,
             1 => ColTypes::Tiny,
             2 => ColTypes::Short,
@@ -102,6 +148,7 @@ impl ColTypes {
             17 => ColTypes::Timestamp2(0),
             18 => ColTypes::DateTime2(0),
             19 => ColTypes::Time2(0),
+            245 => ColTypes::Json(4),
             246 => ColTypes::NewDecimal(10, 0),
             247 => ColTypes::Enum,
             248 => ColTypes::Set,
@@ -114,8 +161,29 @@ impl ColTypes {
             255 => ColTypes::Geometry(1),
             _ => {
                 log::error!("unknown column type: {}", t);
-                unreachable!()
+                return None;
             }
+        };
+        Some(col)
+    }
+
+    /// metadata bytes of column as stored in TableMap event
+    pub fn meta_bytes(&self) -> Vec<u8> {
+        match *self {
+            ColTypes::Float(v)
+            | ColTypes::Double(v)
+            | ColTypes::Blob(v)
+            | ColTypes::Geometry(v)
+            | ColTypes::Json(v)
+            | ColTypes::Timestamp2(v)
+            | ColTypes::DateTime2(v)
+            | ColTypes::Time2(v) => vec![v],
+            ColTypes::VarChar(v) => v.to_le_bytes().to_vec(),
+            ColTypes::NewDecimal(a, b)
+            | ColTypes::VarString(a, b)
+            | ColTypes::String(a, b)
+            | ColTypes::Bit(a, b) => vec![a, b],
+            _ => vec![],
         }
     }
 
@@ -138,9 +206,10 @@ impl ColTypes {
                 map(tuple((le_u8, le_u8)), |(b1, b2)| (2, ColTypes::Bit(b1, b2)))(input)
             }
             ColTypes::Geometry(_) => map(le_u8, |v| (1, ColTypes::Geometry(v)))(input),
+            ColTypes::Json(_) => map(le_u8, |v| (1, ColTypes::Json(v)))(input),
             ColTypes::Timestamp2(_) => map(le_u8, |v| (1, ColTypes::Timestamp2(v)))(input),
             ColTypes::DateTime2(_) => map(le_u8, |v| (1, ColTypes::DateTime2(v)))(input),
-            ColTypes::Time2(_) => map(le_u8, |v| (1, ColTypes::Timestamp2(v)))(input),
+            ColTypes::Time2(_) => map(le_u8, |v| (1, ColTypes::Time2(v)))(input),
             _ => Ok((input, (0, self.clone()))),
         }
     }
@@ -150,11 +219,11 @@ impl ColTypes {
             ColTypes::Decimal => {
                 map(take(4usize), |s: &[u8]| (4, ColValues::Decimal(s.to_vec())))(input)
             }
-            ColTypes::Tiny => map(take(1usize), |s: &[u8]| (1, ColValues::Tiny(s.to_vec())))(input),
-            ColTypes::Short => {
-                map(take(2usize), |s: &[u8]| (2, ColValues::Short(s.to_vec())))(input)
-            }
-            ColTypes::Long => map(take(4usize), |s: &[u8]| (4, ColValues::Long(s.to_vec())))(input),
+            // signedness is not recorded in TableMap, integers are decoded as signed,
+            // use `read_int` with `signed = false` for unsigned column
+            ColTypes::Tiny => map(|i| read_int(i, 1, true), |v| (1, ColValues::Tiny(v)))(input),
+            ColTypes::Short => map(|i| read_int(i, 2, true), |v| (2, ColValues::Short(v)))(input),
+            ColTypes::Long => map(|i| read_int(i, 4, true), |v| (4, ColValues::Long(v)))(input),
             ColTypes::Float(_) => map(take(4usize), |s: &[u8]| {
                 let mut f: [u8; 4] = Default::default();
                 f.copy_from_slice(s);
@@ -166,26 +235,61 @@ impl ColTypes {
                 (8, ColValues::Double(f64::from_le_bytes(d)))
             })(input),
             ColTypes::Null => map(take(0usize), |_| (0, ColValues::Null))(input),
-            ColTypes::LongLong => map(take(8usize), |s: &[u8]| {
-                (8, ColValues::LongLong(s.to_vec()))
-            })(input),
-            ColTypes::Int24 => {
-                map(take(4usize), |s: &[u8]| (4, ColValues::Int24(s.to_vec())))(input)
+            ColTypes::LongLong => {
+                map(|i| read_int(i, 8, true), |v| (8, ColValues::LongLong(v)))(input)
             }
-            ColTypes::Timestamp => map(parse_packed, |(len, v): (usize, Vec<u8>)| {
-                (len, ColValues::Timestamp(v))
+            ColTypes::Int24 => map(|i| read_int(i, 3, true), |v| (3, ColValues::Int24(v)))(input),
+            // unix timestamp in seconds
+            ColTypes::Timestamp => map(le_u32, |seconds| {
+                (4, ColValues::Timestamp { seconds, micros: 0 })
             })(input),
-            ColTypes::Date => map(parse_packed, |(len, v): (usize, Vec<u8>)| {
-                (len, ColValues::Date(v))
+            // 3 bytes packed as year << 9 | month << 5 | day, NEWDATE is stored the same way
+            ColTypes::Date | ColTypes::NewDate => map(le_u24, |v| {
+                (
+                    3,
+                    ColValues::Date {
+                        year: (v >> 9) as u16,
+                        month: ((v >> 5) & 0x0f) as u8,
+                        day: (v & 0x1f) as u8,
+                    },
+                )
             })(input),
-            ColTypes::Time => map(parse_packed, |(len, v): (usize, Vec<u8>)| {
-                (len, ColValues::Time(v))
+            // 3 bytes signed integer HHMMSS
+            ColTypes::Time => map(le_i24, |v| {
+                let abs = v.abs() as u32;
+                (
+                    3,
+                    ColValues::Time {
+                        negative: v < 0,
+                        hour: abs / 10000,
+                        minute: (abs / 100 % 100) as u8,
+                        second: (abs % 100) as u8,
+                        micros: 0,
+                    },
+                )
             })(input),
-            ColTypes::DateTime => map(parse_packed, |(len, v): (usize, Vec<u8>)| {
-                (len, ColValues::DateTime(v))
+            // 8 bytes integer YYYYMMDDHHMMSS
+            ColTypes::DateTime => map(le_u64, |v| {
+                let date = v / 1_000_000;
+                let time = v % 1_000_000;
+                (
+                    8,
+                    ColValues::DateTime {
+                        year: (date / 10000) as u16,
+                        month: (date / 100 % 100) as u8,
+                        day: (date % 100) as u8,
+                        hour: (time / 10000) as u8,
+                        minute: (time / 100 % 100) as u8,
+                        second: (time % 100) as u8,
+                        micros: 0,
+                    },
+                )
+            })(input),
+            // 1 byte stores year - 1900, 0 means 0000
+            ColTypes::Year => map(le_u8, |v| {
+                let year = if v == 0 { 0 } else { 1900 + v as u16 };
+                (1, ColValues::Year(year))
             })(input),
-            ColTypes::Year => map(take(2usize), |s: &[u8]| (2, ColValues::Year(s.to_vec())))(input),
-            ColTypes::NewDate => map(take(0usize), |_| (0, ColValues::NewDate))(input),
             // ref: https://dev.mysql.com/doc/refman/5.7/en/char.html
             ColTypes::VarChar(max_len) => {
                 if max_len > 255 {
@@ -201,49 +305,42 @@ impl ColTypes {
                 }
             }
             ColTypes::Bit(b1, b2) => {
-                let len = ((b1 + 7) / 8 + (b2 + 7) / 8) as usize;
+                let len = (b1 as usize + 7) / 8 + (b2 as usize + 7) / 8;
                 map(take(len), move |s: &[u8]| (len, ColValues::Bit(s.to_vec())))(input)
             }
-            ColTypes::Timestamp2(_) => map(take(4usize), |v: &[u8]| {
-                (4, ColValues::Timestamp2(v.to_vec()))
-            })(input),
-            ColTypes::DateTime2(_) => map(take(4usize), |v: &[u8]| {
-                (4, ColValues::DateTime2(v.to_vec()))
-            })(input),
-            ColTypes::Time2(_) => {
-                map(take(4usize), |v: &[u8]| (4, ColValues::Time2(v.to_vec())))(input)
+            // 4 bytes big endian seconds since epoch in UTC, followed by fraction
+            ColTypes::Timestamp2(fsp) => {
+                let (i, seconds) = be_u32(input)?;
+                let (i, (len, micros)) = fraction(i, fsp)?;
+                Ok((i, (4 + len, ColValues::Timestamp { seconds, micros })))
             }
+            ColTypes::DateTime2(fsp) => datetime2(input, fsp),
+            ColTypes::Time2(fsp) => time2(input, fsp),
             ColTypes::NewDecimal(precision, scale) => {
-                // copy from https://github.com/mysql/mysql-server/blob/a394a7e17744a70509be5d3f1fd73f8779a31424/libbinlogevents/src/binary_log_funcs.cpp#L204-L214
-                let dig2bytes: [u8; 10] = [0, 1, 1, 2, 2, 3, 3, 4, 4, 4];
-                let intg = precision - scale;
-                let intg0 = intg / 9;
-                let frac0 = scale / 9;
-                let intg0x = intg - intg0 * 9;
-                let frac0x = scale - frac0 * 9;
-                let len =
-                    intg0 * 4 + dig2bytes[intg0x as usize] + frac0 * 4 + dig2bytes[frac0x as usize];
-                map(take(len), move |s: &[u8]| {
-                    (len as usize, ColValues::NewDecimal(s.to_vec()))
-                })(input)
+                if precision < scale {
+                    return Err(nom::Err::Error((input, ErrorKind::Verify)));
+                }
+                let len = decimal::binary_size(precision, scale);
+                let (i, data) = take(len)(input)?;
+                match decimal::decode(data, precision, scale) {
+                    Some(text) => Ok((i, (len, ColValues::NewDecimal(text)))),
+                    None => Err(nom::Err::Error((input, ErrorKind::Verify))),
+                }
             }
-            ColTypes::Enum => map(take(0usize), |_| (0, ColValues::Enum))(input),
-            ColTypes::Set => map(take(0usize), |_| (0, ColValues::Set))(input),
+            // never appear in binlog, real type is packed in meta of STRING
+            ColTypes::Enum => map(take(0usize), |_| (0, ColValues::Enum(0)))(input),
+            ColTypes::Set => map(take(0usize), |_| (0, ColValues::Set(vec![])))(input),
             ColTypes::TinyBlob => map(take(0usize), |_| (0, ColValues::TinyBlob))(input),
             ColTypes::MediumBlob => map(take(0usize), |_| (0, ColValues::MediumBlob))(input),
             ColTypes::LongBlob => map(take(0usize), |_| (0, ColValues::LongBlob))(input),
             ColTypes::Blob(len_bytes) => {
-                let mut raw_len = input[..len_bytes as usize].to_vec();
-                for _ in 0..(4 - len_bytes) {
-                    raw_len.push(0);
-                }
-                let (_, len) = pu32(&raw_len).unwrap();
+                let (i, len) = int_fixed(input, len_bytes)?;
                 map(take(len), move |s: &[u8]| {
                     (
                         len_bytes as usize + len as usize,
                         ColValues::Blob(s.to_vec()),
                     )
-                })(&input[len_bytes as usize..])
+                })(i)
             }
             ColTypes::VarString(_, _) => {
                 // TODO should check string max_len ?
@@ -252,59 +349,440 @@ impl ColTypes {
                     (len as usize, ColValues::VarString(s.to_vec()))
                 })(i)
             }
-            ColTypes::String(_, _) => {
-                // TODO should check string max_len ?
-                let (i, len) = le_u8(input)?;
-                map(take(len), move |s: &[u8]| {
-                    (len as usize, ColValues::VarChar(s.to_vec()))
-                })(i)
+            ColTypes::String(meta0, meta1) => {
+                let (real_type, max_len) = unpack_string_meta(meta0, meta1);
+                match real_type {
+                    // ENUM stores index of member, 1 or 2 bytes
+                    247 => map(take(max_len), |s: &[u8]| {
+                        (s.len(), ColValues::Enum(le_fold(s) as u16))
+                    })(input),
+                    // SET stores bitmask of members, 1 to 8 bytes
+                    248 => map(take(max_len), |s: &[u8]| {
+                        let mask = le_fold(s);
+                        let members = (0..s.len() as u32 * 8)
+                            .filter(|idx| (mask >> idx) & 1 == 1)
+                            .collect();
+                        (s.len(), ColValues::Set(members))
+                    })(input),
+                    _ => {
+                        let (i, len) = if max_len > 255 {
+                            map(le_u16, |v| v as usize)(input)?
+                        } else {
+                            map(le_u8, |v| v as usize)(input)?
+                        };
+                        map(take(len), move |s: &[u8]| {
+                            (len, ColValues::String(s.to_vec()))
+                        })(i)
+                    }
+                }
+            }
+            // stored as blob, payload is 4 bytes SRID followed by WKB
+            ColTypes::Geometry(len_bytes) => {
+                let (i, len) = int_fixed(input, len_bytes)?;
+                let (i, payload) = take(len)(i)?;
+                let (wkb, srid) = le_u32(payload)?;
+                Ok((
+                    i,
+                    (
+                        len_bytes as usize + len as usize,
+                        ColValues::Geometry {
+                            srid,
+                            wkb: wkb.to_vec(),
+                        },
+                    ),
+                ))
+            }
+            // stored as blob of binary JSON
+            ColTypes::Json(len_bytes) => {
+                let (i, len) = int_fixed(input, len_bytes)?;
+                let (i, doc) = take(len)(i)?;
+                let value = if doc.is_empty() {
+                    JsonValue::Null
+                } else {
+                    parse_json(doc)?.1
+                };
+                Ok((
+                    i,
+                    (len_bytes as usize + len as usize, ColValues::Json(value)),
+                ))
             }
-            // TODO fix do not use len in def ?
-            ColTypes::Geometry(len) => map(take(len), |s: &[u8]| {
-                (len as usize, ColValues::Geometry(s.to_vec()))
-            })(input),
         }
     }
 }
 
-fn parse_packed(input: &[u8]) -> IResult<&[u8], (usize, Vec<u8>)> {
-    let mut data = vec![input[0]];
-    let (i, len) = le_u8(input)?;
-    let (i, raw) = take(len)(i)?;
-    data.extend(raw);
-    Ok((i, (len as usize + 1, data)))
-}
-
 #[derive(Debug, Serialize, PartialEq, Clone)]
 pub enum ColValues {
     Decimal(Vec<u8>),
-    Tiny(Vec<u8>),
-    Short(Vec<u8>),
-    Long(Vec<u8>),
+    Tiny(IntValue),
+    Short(IntValue),
+    Long(IntValue),
     Float(f32),
     Double(f64),
     Null,
-    Timestamp(Vec<u8>),
-    LongLong(Vec<u8>),
-    Int24(Vec<u8>),
-    Date(Vec<u8>),
-    Time(Vec<u8>),
-    DateTime(Vec<u8>),
-    Year(Vec<u8>),
-    NewDate, // internal used
+    Timestamp {
+        seconds: u32,
+        micros: u32,
+    },
+    LongLong(IntValue),
+    Int24(IntValue),
+    Date {
+        year: u16,
+        month: u8,
+        day: u8,
+    },
+    Time {
+        negative: bool,
+        hour: u32,
+        minute: u8,
+        second: u8,
+        micros: u32,
+    },
+    DateTime {
+        year: u16,
+        month: u8,
+        day: u8,
+        hour: u8,
+        minute: u8,
+        second: u8,
+        micros: u32,
+    },
+    Year(u16),
+    NewDate, // internal used, NEWDATE columns are decoded as Date
     VarChar(Vec<u8>),
     Bit(Vec<u8>),
-    Timestamp2(Vec<u8>),
-    DateTime2(Vec<u8>),
-    Time2(Vec<u8>),
-    NewDecimal(Vec<u8>),
-    Enum,       // internal used
-    Set,        // internal used
+    // text form, e.g. `-12.34`
+    NewDecimal(alloc::string::String),
+    // index of member
+    Enum(u16),
+    // 0-based indices of members
+    Set(Vec<u32>),
     TinyBlob,   // internal used
     MediumBlob, // internal used
     LongBlob,   // internal used
     Blob(Vec<u8>),
     VarString(Vec<u8>),
     String(Vec<u8>),
-    Geometry(Vec<u8>),
+    Geometry {
+        srid: u32,
+        wkb: Vec<u8>,
+    },
+    Json(JsonValue),
+}
+
+impl ColValues {
+    /// TIMESTAMP and TIMESTAMP2 value as UTC instant
+    #[cfg(feature = "chrono")]
+    pub fn as_utc(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        use chrono::TimeZone;
+
+        match self {
+            ColValues::Timestamp { seconds, micros } => chrono::Utc
+                .timestamp_opt(*seconds as i64, micros * 1000)
+                .single(),
+            _ => None,
+        }
+    }
+
+    /// whether value can be decoded from column of type `col`, NULL matches any type
+    pub fn matches_type(&self, col: &ColTypes) -> bool {
+        match (self, col) {
+            (ColValues::Null, _) => true,
+            // ENUM and SET are stored as STRING with real type in meta
+            (ColValues::Enum(_), ColTypes::String(meta0, meta1)) => {
+                unpack_string_meta(*meta0, *meta1).0 == 247
+            }
+            (ColValues::Set(_), ColTypes::String(meta0, meta1)) => {
+                unpack_string_meta(*meta0, *meta1).0 == 248
+            }
+            (ColValues::String(_), ColTypes::String(meta0, meta1)) => {
+                !matches!(unpack_string_meta(*meta0, *meta1).0, 247 | 248)
+            }
+            (ColValues::Timestamp { .. }, ColTypes::Timestamp2(_)) => true,
+            (value, col) => matches!(
+                (value, col),
+                (ColValues::Decimal(_), ColTypes::Decimal)
+                    | (ColValues::Tiny(_), ColTypes::Tiny)
+                    | (ColValues::Short(_), ColTypes::Short)
+                    | (ColValues::Long(_), ColTypes::Long)
+                    | (ColValues::Float(_), ColTypes::Float(_))
+                    | (ColValues::Double(_), ColTypes::Double(_))
+                    | (ColValues::Timestamp { .. }, ColTypes::Timestamp)
+                    | (ColValues::LongLong(_), ColTypes::LongLong)
+                    | (ColValues::Int24(_), ColTypes::Int24)
+                    | (ColValues::Date { .. }, ColTypes::Date)
+                    | (ColValues::Date { .. }, ColTypes::NewDate)
+                    | (ColValues::Time { .. }, ColTypes::Time)
+                    | (ColValues::DateTime { .. }, ColTypes::DateTime)
+                    | (ColValues::Year(_), ColTypes::Year)
+                    | (ColValues::NewDate, ColTypes::NewDate)
+                    | (ColValues::VarChar(_), ColTypes::VarChar(_))
+                    | (ColValues::Bit(_), ColTypes::Bit(_, _))
+                    | (ColValues::DateTime { .. }, ColTypes::DateTime2(_))
+                    | (ColValues::Time { .. }, ColTypes::Time2(_))
+                    | (ColValues::NewDecimal(_), ColTypes::NewDecimal(_, _))
+                    | (ColValues::Enum(_), ColTypes::Enum)
+                    | (ColValues::Set(_), ColTypes::Set)
+                    | (ColValues::TinyBlob, ColTypes::TinyBlob)
+                    | (ColValues::MediumBlob, ColTypes::MediumBlob)
+                    | (ColValues::LongBlob, ColTypes::LongBlob)
+                    | (ColValues::Blob(_), ColTypes::Blob(_))
+                    | (ColValues::VarString(_), ColTypes::VarString(_, _))
+                    | (ColValues::Geometry { .. }, ColTypes::Geometry(_))
+                    | (ColValues::Json(_), ColTypes::Json(_))
+            ),
+        }
+    }
+
+    /// convert to `serde_json::Value`
+    ///
+    /// integers and floats become numbers, text becomes strings, binary data(BLOB, BIT and
+    /// other values kept as raw bytes) becomes base64 strings, temporal values become ISO-8601
+    /// strings. NEWDECIMAL becomes string of its text form, e.g. `"3.00"`, so no precision
+    /// is lost. NULL and internal types become `null`
+    ///
+    /// known gap: legacy DECIMAL is not decoded yet, it's still rendered as base64 of its
+    /// raw bytes
+    ///
+    /// needs `serde_json` feature, enabled by `std`
+    #[cfg(feature = "serde_json")]
+    pub fn to_json(&self) -> serde_json::Value {
+        use crate::utils::base64;
+        use alloc::format;
+        use serde_json::{Map, Number, Value};
+
+        let micros = |micros: u32| {
+            if micros == 0 {
+                alloc::string::String::new()
+            } else {
+                format!(".{:06}", micros)
+            }
+        };
+        match self {
+            ColValues::Tiny(v)
+            | ColValues::Short(v)
+            | ColValues::Long(v)
+            | ColValues::LongLong(v)
+            | ColValues::Int24(v) => match v {
+                IntValue::Signed(v) => Value::Number(Number::from(*v)),
+                IntValue::Unsigned(v) => Value::Number(Number::from(*v)),
+            },
+            ColValues::Float(v) => Number::from_f64(*v as f64).map_or(Value::Null, Value::Number),
+            ColValues::Double(v) => Number::from_f64(*v).map_or(Value::Null, Value::Number),
+            ColValues::Year(v) => Value::Number(Number::from(*v as u64)),
+            ColValues::Enum(v) => Value::Number(Number::from(*v as u64)),
+            ColValues::Set(v) => Value::Array(
+                v.iter()
+                    .map(|idx| Value::Number(Number::from(*idx as u64)))
+                    .collect(),
+            ),
+            ColValues::Timestamp {
+                seconds,
+                micros: us,
+            } => {
+                let (year, month, day) = civil_from_days(*seconds as i64 / 86400);
+                let secs = seconds % 86400;
+                Value::String(format!(
+                    "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}{}Z",
+                    year,
+                    month,
+                    day,
+                    secs / 3600,
+                    secs / 60 % 60,
+                    secs % 60,
+                    micros(*us)
+                ))
+            }
+            ColValues::Date { year, month, day } => {
+                Value::String(format!("{:04}-{:02}-{:02}", year, month, day))
+            }
+            ColValues::Time {
+                negative,
+                hour,
+                minute,
+                second,
+                micros: us,
+            } => Value::String(format!(
+                "{}{:02}:{:02}:{:02}{}",
+                if *negative { "-" } else { "" },
+                hour,
+                minute,
+                second,
+                micros(*us)
+            )),
+            ColValues::DateTime {
+                year,
+                month,
+                day,
+                hour,
+                minute,
+                second,
+                micros: us,
+            } => Value::String(format!(
+                "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}{}",
+                year,
+                month,
+                day,
+                hour,
+                minute,
+                second,
+                micros(*us)
+            )),
+            ColValues::VarChar(v) | ColValues::VarString(v) | ColValues::String(v) => {
+                Value::String(alloc::string::String::from_utf8_lossy(v).into_owned())
+            }
+            ColValues::Blob(v) | ColValues::Bit(v) | ColValues::Decimal(v) => {
+                Value::String(base64(v))
+            }
+            ColValues::NewDecimal(v) => Value::String(v.clone()),
+            ColValues::Geometry { srid, wkb } => {
+                let mut map = Map::new();
+                map.insert("srid".into(), Value::Number(Number::from(*srid as u64)));
+                map.insert("wkb".into(), Value::String(base64(wkb)));
+                Value::Object(map)
+            }
+            ColValues::Json(v) => v.to_json(),
+            ColValues::Null
+            | ColValues::NewDate
+            | ColValues::TinyBlob
+            | ColValues::MediumBlob
+            | ColValues::LongBlob => Value::Null,
+        }
+    }
+}
+
+/// convert days since 1970-01-01 to (year, month, day)
+///
+/// ref: http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+#[cfg(feature = "serde_json")]
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// value of TINY/SHORT/INT24/LONG/LONGLONG column
+#[derive(Debug, Serialize, PartialEq, Eq, Clone, Copy)]
+pub enum IntValue {
+    Signed(i64),
+    Unsigned(u64),
+}
+
+/// fractional seconds of TIMESTAMP2, (fsp + 1) / 2 bytes big endian,
+/// return (used_bytes, microseconds)
+fn fraction(input: &[u8], fsp: u8) -> IResult<&[u8], (usize, u32)> {
+    if fsp > 6 {
+        log::error!("invalid fractional seconds precision: {}", fsp);
+        return Err(nom::Err::Error((input, ErrorKind::Verify)));
+    }
+    let len = (fsp as usize + 1) / 2;
+    let (i, s) = take(len)(input)?;
+    let v = s.iter().fold(0u32, |acc, &b| (acc << 8) | b as u32);
+    // stored in 1/100, 1/10000 or 1/1000000 second
+    let micros = match len {
+        1 => v * 10_000,
+        2 => v * 100,
+        _ => v,
+    };
+    Ok((i, (len, micros)))
+}
+
+/// 5 bytes big endian, 1 bit sign(always set), 17 bits year * 13 + month, 5 bits day,
+/// 5 bits hour, 6 bits minute and 6 bits second, followed by fraction
+fn datetime2(input: &[u8], fsp: u8) -> IResult<&[u8], (usize, ColValues)> {
+    let (i, s) = take(5usize)(input)?;
+    let packed = s.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64) - 0x80_0000_0000;
+    let (i, (len, micros)) = fraction(i, fsp)?;
+    let ymd = packed >> 17;
+    let hms = packed & 0x1_ffff;
+    let ym = ymd >> 5;
+    Ok((
+        i,
+        (
+            5 + len,
+            ColValues::DateTime {
+                year: (ym / 13) as u16,
+                month: (ym % 13) as u8,
+                day: (ymd & 0x1f) as u8,
+                hour: (hms >> 12) as u8,
+                minute: ((hms >> 6) & 0x3f) as u8,
+                second: (hms & 0x3f) as u8,
+                micros,
+            },
+        ),
+    ))
+}
+
+/// 3 bytes big endian offset by 0x800000, 1 bit sign, 1 bit unused, 10 bits hour, 6 bits
+/// minute and 6 bits second, followed by fraction
+fn time2(input: &[u8], fsp: u8) -> IResult<&[u8], (usize, ColValues)> {
+    let (i, s) = take(3usize)(input)?;
+    let mut int_part = s.iter().fold(0i64, |acc, &b| (acc << 8) | b as i64) - 0x80_0000;
+    let (i, (len, micros)) = fraction(i, fsp)?;
+    // fraction of 1 and 2 bytes of negative time is stored as complement of next second
+    let mut frac = micros as i64;
+    if int_part < 0 && frac != 0 {
+        match len {
+            1 => {
+                int_part += 1;
+                frac -= 0x100 * 10_000;
+            }
+            2 => {
+                int_part += 1;
+                frac -= 0x1_0000 * 100;
+            }
+            _ => {}
+        }
+    }
+    let packed = (int_part << 24) + frac;
+    let abs = packed.unsigned_abs();
+    let hms = abs >> 24;
+    Ok((
+        i,
+        (
+            3 + len,
+            ColValues::Time {
+                negative: packed < 0,
+                hour: ((hms >> 12) & 0x3ff) as u32,
+                minute: ((hms >> 6) & 0x3f) as u8,
+                second: (hms & 0x3f) as u8,
+                micros: (abs & 0xff_ffff) as u32,
+            },
+        ),
+    ))
+}
+
+/// read `width` bytes little endian int, sign extended if `signed` is set
+pub fn read_int(input: &[u8], width: u8, signed: bool) -> IResult<&[u8], IntValue> {
+    let (i, v) = int_fixed(input, width)?;
+    if !signed {
+        return Ok((i, IntValue::Unsigned(v)));
+    }
+    let shift = 64 - width as u32 * 8;
+    Ok((i, IntValue::Signed(((v << shift) as i64) >> shift)))
+}
+
+/// unpack real type and max length from meta of STRING column
+///
+/// real type is stored in first byte, for CHAR longer than 255 bytes,
+/// high bits of length are xor-ed into bit 4 and 5 of real type
+///
+/// ref: https://github.com/mysql/mysql-server/blob/8.0/sql/log_event.cc `log_event_print_value`
+fn unpack_string_meta(meta0: u8, meta1: u8) -> (u8, u16) {
+    if meta0 & 0x30 != 0x30 {
+        let max_len = meta1 as u16 | ((((meta0 & 0x30) ^ 0x30) as u16) << 4);
+        (meta0 | 0x30, max_len)
+    } else {
+        (meta0, meta1 as u16)
+    }
+}
+
+fn le_fold(input: &[u8]) -> u64 {
+    input.iter().rev().fold(0, |acc, &b| (acc << 8) | b as u64)
 }
@@ -0,0 +1,70 @@
+use crate::{
+    error::Error,
+    events::ParseOptions,
+    frame::{is_last, FrameBuffer},
+    stream::StreamEvent,
+};
+use std::io::{ErrorKind, Read};
+
+/// blocking version of `AsyncBinlogStream`, read events from a `Read` source, e.g. a pipe
+/// or socket
+///
+/// reads may return any number of bytes, an event is buffered until all `event_size` bytes
+/// arrive. stream ends after a Stop event or when reader reaches EOF between events,
+/// an error ends stream too
+pub struct BinlogReader<R> {
+    reader: R,
+    frame: FrameBuffer,
+    done: bool,
+}
+
+impl<R: Read> BinlogReader<R> {
+    /// reader should start with binlog magic number
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            frame: FrameBuffer::new(),
+            done: false,
+        }
+    }
+
+    /// parse events with `options`, event larger than `max_event_size` is rejected
+    /// before its body is buffered
+    pub fn with_options(mut self, options: ParseOptions) -> Self {
+        self.frame.set_options(options);
+        self
+    }
+
+    /// offset of next event in source
+    pub fn offset(&self) -> usize {
+        self.frame.offset()
+    }
+
+    fn read_event(&mut self) -> Result<Option<StreamEvent>, Error> {
+        loop {
+            if let Some(buf) = self.frame.spare()? {
+                match self.reader.read(buf) {
+                    Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                    Err(e) => return Err(Error::Io(e.kind())),
+                    Ok(0) => return self.frame.eof(),
+                    Ok(n) => self.frame.fill(n),
+                }
+            } else if let Some(event) = self.frame.parse()? {
+                return Ok(Some(event));
+            }
+        }
+    }
+}
+
+impl<R: Read> Iterator for BinlogReader<R> {
+    type Item = Result<StreamEvent, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let item = self.read_event();
+        self.done = is_last(&item);
+        item.transpose()
+    }
+}
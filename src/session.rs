@@ -0,0 +1,98 @@
+use crate::events::{Event, IntVarEventType, UserVarType};
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+use serde::Serialize;
+
+/// value of user variable set by UserVar event
+#[derive(Debug, Serialize, PartialEq, Clone)]
+pub struct UserVariable {
+    pub d_type: Option<UserVarType>,
+    pub charset: Option<u32>,
+    // None if variable is NULL
+    pub value: Option<Vec<u8>>,
+}
+
+/// session context of a statement, collected from Rand, IntVar and UserVar events
+///
+/// these events are written right before the Query event they belong to, feed every event
+/// with `apply`, after a statement is applied context stays readable until next event
+/// is applied, then it's reset
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SessionContext {
+    rand_seeds: Option<(u64, u64)>,
+    last_insert_id: Option<u64>,
+    insert_id: Option<u64>,
+    user_vars: BTreeMap<String, UserVariable>,
+    // statement is applied, reset before next event
+    done: bool,
+}
+
+impl SessionContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// record context event, or mark statement boundary on Query and ExecuteLoadQuery events
+    pub fn apply(&mut self, event: &Event) {
+        if self.done {
+            *self = Self::default();
+        }
+        match event {
+            Event::Rand { seed1, seed2, .. } => self.rand_seeds = Some((*seed1, *seed2)),
+            Event::IntVar { e_type, value, .. } => match e_type {
+                IntVarEventType::LastInsertIdEvent => self.last_insert_id = Some(*value),
+                IntVarEventType::InsertIdEvent => self.insert_id = Some(*value),
+                _ => {}
+            },
+            Event::UserVar {
+                name,
+                is_null,
+                d_type,
+                charset,
+                value,
+                ..
+            } => {
+                self.user_vars.insert(
+                    name.clone(),
+                    UserVariable {
+                        d_type: d_type.clone(),
+                        charset: *charset,
+                        value: if *is_null { None } else { value.clone() },
+                    },
+                );
+            }
+            Event::Query { .. } | Event::ExecuteLoadQueryEvent { .. } => self.done = true,
+            _ => {}
+        }
+    }
+
+    /// seed1 and seed2 of RAND()
+    pub fn rand_seeds(&self) -> Option<(u64, u64)> {
+        self.rand_seeds
+    }
+
+    /// value of LAST_INSERT_ID()
+    pub fn last_insert_id(&self) -> Option<u64> {
+        self.last_insert_id
+    }
+
+    /// first auto increment value used by statement
+    pub fn insert_id(&self) -> Option<u64> {
+        self.insert_id
+    }
+
+    pub fn user_var(&self, name: &str) -> Option<&UserVariable> {
+        self.user_vars.get(name)
+    }
+
+    pub fn user_vars(&self) -> impl Iterator<Item = (&String, &UserVariable)> {
+        self.user_vars.iter()
+    }
+
+    /// whether no context event is recorded
+    pub fn is_empty(&self) -> bool {
+        self.rand_seeds.is_none()
+            && self.last_insert_id.is_none()
+            && self.insert_id.is_none()
+            && self.user_vars.is_empty()
+    }
+}
@@ -0,0 +1,219 @@
+//! rebuild approximate sql statements from rows events
+//!
+//! column names are not recorded in TableMap event, columns are referred as `@1`, `@2` ...
+//! in UPDATE and DELETE statements, the same way mysqlbinlog does
+use crate::{
+    events::{rows::present_columns, table_map::TableSchema, Event},
+    mysql::{ColValues, IntValue},
+};
+use alloc::{format, string::String, vec::Vec};
+use core::fmt::{self, Write};
+
+/// quote identifier with backticks
+fn ident(name: &str) -> String {
+    format!("`{}`", name.replace('`', "``"))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(bytes.len() * 2 + 3);
+    s.push_str("x'");
+    for b in bytes {
+        let _ = write!(s, "{:02X}", b);
+    }
+    s.push('\'');
+    s
+}
+
+/// quote string literal, bytes which are not valid utf8 are rendered as hex literal
+fn quote(bytes: &[u8]) -> String {
+    let text = match core::str::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(_) => return hex(bytes),
+    };
+    let mut s = String::with_capacity(text.len() + 2);
+    s.push('\'');
+    for c in text.chars() {
+        match c {
+            '\'' => s.push_str("\\'"),
+            '\\' => s.push_str("\\\\"),
+            '\0' => s.push_str("\\0"),
+            '\n' => s.push_str("\\n"),
+            '\r' => s.push_str("\\r"),
+            '\x1a' => s.push_str("\\Z"),
+            c => s.push(c),
+        }
+    }
+    s.push('\'');
+    s
+}
+
+fn fraction(micros: u32) -> String {
+    if micros == 0 {
+        String::new()
+    } else {
+        format!(".{:06}", micros)
+    }
+}
+
+/// render column value as sql literal, blobs are rendered as hex literal
+///
+/// None for values kept as undecoded bytes(legacy DECIMAL) and internal
+/// types, they have no literal of the same value
+pub fn literal(value: &ColValues) -> Option<String> {
+    literal_or_type(value).ok()
+}
+
+/// `literal` of value, or marker of its type if it has none, e.g. `<DECIMAL>`,
+/// marker is not valid sql so statement fails instead of writing wrong value
+fn render(value: &ColValues) -> String {
+    literal_or_type(value).unwrap_or_else(|name| format!("<{}>", name))
+}
+
+/// sql literal of value, or name of its type if it has none
+fn literal_or_type(value: &ColValues) -> Result<String, &'static str> {
+    let literal = match value {
+        ColValues::Tiny(v)
+        | ColValues::Short(v)
+        | ColValues::Long(v)
+        | ColValues::LongLong(v)
+        | ColValues::Int24(v) => match v {
+            IntValue::Signed(v) => format!("{}", v),
+            IntValue::Unsigned(v) => format!("{}", v),
+        },
+        ColValues::Float(v) => format!("{}", v),
+        ColValues::Double(v) => format!("{}", v),
+        ColValues::Year(v) => format!("{}", v),
+        // ENUM and SET accept index and bitmask of members
+        ColValues::Enum(v) => format!("{}", v),
+        ColValues::Set(v) => format!("{}", v.iter().fold(0u64, |acc, idx| acc | 1 << idx)),
+        ColValues::Timestamp { seconds, micros } => {
+            format!("FROM_UNIXTIME({}{})", seconds, fraction(*micros))
+        }
+        ColValues::Date { year, month, day } => {
+            format!("'{:04}-{:02}-{:02}'", year, month, day)
+        }
+        ColValues::Time {
+            negative,
+            hour,
+            minute,
+            second,
+            micros,
+        } => format!(
+            "'{}{:02}:{:02}:{:02}{}'",
+            if *negative { "-" } else { "" },
+            hour,
+            minute,
+            second,
+            fraction(*micros)
+        ),
+        ColValues::DateTime {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+            micros,
+        } => format!(
+            "'{:04}-{:02}-{:02} {:02}:{:02}:{:02}{}'",
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+            fraction(*micros)
+        ),
+        ColValues::VarChar(v) | ColValues::VarString(v) | ColValues::String(v) => quote(v),
+        ColValues::NewDecimal(v) => v.clone(),
+        ColValues::Blob(v) | ColValues::Bit(v) => hex(v),
+        ColValues::Geometry { srid, wkb } => format!("ST_GeomFromWKB({}, {})", hex(wkb), srid),
+        ColValues::Json(v) => quote(format!("{}", v).as_bytes()),
+        ColValues::Null => String::from("NULL"),
+        ColValues::Decimal(_) => return Err("DECIMAL"),
+        ColValues::NewDate => return Err("NEWDATE"),
+        ColValues::TinyBlob => return Err("TINY_BLOB"),
+        ColValues::MediumBlob => return Err("MEDIUM_BLOB"),
+        ColValues::LongBlob => return Err("LONG_BLOB"),
+    };
+    Ok(literal)
+}
+
+/// sql literal of value, strings are quoted and escaped, e.g. `'it\'s'`, blobs are
+/// rendered as hex literal, e.g. `x'0102'`, value without literal is rendered as marker
+/// of its type, e.g. `<DECIMAL>`
+impl fmt::Display for ColValues {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&render(self))
+    }
+}
+
+/// `image_bits` tells which column every value belongs to, minimal images carry some columns only
+fn assignments(
+    row: &[ColValues],
+    image_bits: &[u8],
+    column_count: u64,
+    sep: &str,
+    is_where: bool,
+) -> String {
+    row.iter()
+        .zip(present_columns(image_bits, column_count))
+        .map(|(v, idx)| match v {
+            ColValues::Null if is_where => format!("@{} IS NULL", idx + 1),
+            v => format!("@{}={}", idx + 1, render(v)),
+        })
+        .collect::<Vec<_>>()
+        .join(sep)
+}
+
+/// rebuild statements of rows event, one statement per row
+///
+/// return empty vec if event is not a rows event, values without literal are rendered
+/// as marker, see `Display` of `ColValues`
+pub fn to_sql(event: &Event, table: &TableSchema) -> Vec<String> {
+    let name = format!("{}.{}", ident(&table.schema), ident(&table.table));
+    match event {
+        Event::WriteRowsV2 { rows, .. } => rows
+            .iter()
+            .map(|row| {
+                let values: Vec<_> = row.iter().map(render).collect();
+                format!("INSERT INTO {} VALUES ({})", name, values.join(", "))
+            })
+            .collect(),
+        Event::DeleteRowsV2 {
+            rows,
+            column_count,
+            deleted_image_bits,
+            ..
+        } => rows
+            .iter()
+            .map(|row| {
+                format!(
+                    "DELETE FROM {} WHERE {}",
+                    name,
+                    assignments(row, deleted_image_bits, *column_count, " AND ", true)
+                )
+            })
+            .collect(),
+        // before and after images are stored in turn
+        Event::UpdateRowsV2 {
+            rows,
+            column_count,
+            before_image_bits,
+            after_image_bits,
+            ..
+        } => rows
+            .chunks(2)
+            .filter(|pair| pair.len() == 2)
+            .map(|pair| {
+                format!(
+                    "UPDATE {} SET {} WHERE {}",
+                    name,
+                    assignments(&pair[1], after_image_bits, *column_count, ", ", false),
+                    assignments(&pair[0], before_image_bits, *column_count, " AND ", true)
+                )
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
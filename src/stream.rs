@@ -0,0 +1,586 @@
+use crate::{
+    error::Error,
+    events::{
+        check_start,
+        event_type::EventType,
+        parse_header,
+        table_map::{TableInfo, TableMapCache},
+        Event, Header, ParseContext, ParseOptions,
+    },
+    filter::Filter,
+    utils::{string_fixed, take_u48_le},
+};
+use alloc::{
+    collections::BTreeSet,
+    string::{String, ToString},
+};
+use nom::{bytes::complete::tag, error::ErrorKind, number::complete::le_u16, IResult};
+use serde::Serialize;
+
+/// item yielded by BinlogStream
+#[derive(Debug, Serialize, PartialEq, Clone)]
+pub enum StreamEvent {
+    Event(Event),
+    /// Stop event, server was shut down cleanly and no more events follow
+    Stop {
+        header: Header,
+    },
+    /// Rotate event, following events are in binlog `next_binlog` starting at `position`
+    RotateTo {
+        header: Header,
+        next_binlog: String,
+        position: u64,
+    },
+    /// Heartbeat event, source is at `log_pos` of current binlog
+    Heartbeat {
+        header: Header,
+        log_pos: u64,
+    },
+    /// bytes `[start, end)` of input dropped to resync after a malformed event,
+    /// only yielded in resync mode
+    Skipped {
+        start: usize,
+        end: usize,
+    },
+}
+
+/// kind of log file a stream is read from
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum StreamKind {
+    /// binlog written by source
+    Binlog,
+    /// relay log written by replica
+    RelayLog,
+}
+
+impl StreamKind {
+    /// relay log has events with `relay_log` flag set, and FormatDesc event of source
+    /// follows the one of replica
+    pub fn detect<'a>(events: impl IntoIterator<Item = &'a Event>) -> Self {
+        let mut format_desc = 0;
+        for event in events {
+            if event.header().flags.relay_log() {
+                return StreamKind::RelayLog;
+            }
+            if let Event::FormatDesc { .. } = event {
+                format_desc += 1;
+                if format_desc > 1 {
+                    return StreamKind::RelayLog;
+                }
+            }
+        }
+        StreamKind::Binlog
+    }
+}
+
+impl From<Event> for StreamEvent {
+    fn from(event: Event) -> Self {
+        match event {
+            Event::Stop { header, .. } => StreamEvent::Stop { header },
+            Event::Rotate {
+                header,
+                next_binlog,
+                position,
+                ..
+            } => StreamEvent::RotateTo {
+                header,
+                next_binlog,
+                position,
+            },
+            Event::Heartbeat { header, .. } => StreamEvent::Heartbeat {
+                log_pos: header.log_pos as u64,
+                header,
+            },
+            event => StreamEvent::Event(event),
+        }
+    }
+}
+
+/// iterate events of a binlog file, table maps are tracked by stream itself
+///
+/// stream ends after a Stop event, or when input is exhausted,
+/// an event cut off by the end of input is reported as `Error::Truncated`
+pub struct BinlogStream<'a> {
+    input: &'a [u8],
+    offset: usize,
+    cache: TableMapCache,
+    context: ParseContext,
+    kind: StreamKind,
+    format_desc: usize,
+    // (schema, table) allowed by table filter
+    tables: Option<BTreeSet<(String, String)>>,
+    // table_ids mapped to tables rejected by table filter
+    skipped: BTreeSet<u64>,
+    filter: Option<Filter>,
+    resync: bool,
+    started: bool,
+    done: bool,
+}
+
+/// (table_id, schema, table) of TableMap event body, rest of body is not decoded
+fn peek_table_map(input: &[u8]) -> IResult<&[u8], (u64, String, String)> {
+    let (i, table_id) = take_u48_le(input)?;
+    let (i, _) = le_u16(i)?;
+    let (i, (_, schema)) = string_fixed(i)?;
+    let (i, _) = tag([0x00])(i)?;
+    let (i, (_, table)) = string_fixed(i)?;
+    Ok((i, (table_id, schema, table)))
+}
+
+fn is_rows_type(t: EventType) -> bool {
+    matches!(
+        t,
+        EventType::PreGaWriteRows
+            | EventType::PreGaUpdateRows
+            | EventType::PreGaDeleteRows
+            | EventType::WriteRowsV1
+            | EventType::UpdateRowsV1
+            | EventType::DeleteRowsV1
+            | EventType::WriteRowsV2
+            | EventType::UpdateRowsV2
+            | EventType::DeleteRowsV2
+            | EventType::PartialUpdateRows
+    )
+}
+
+impl<'a> BinlogStream<'a> {
+    /// input should be a whole binlog file, magic number included
+    pub fn new(input: &'a [u8]) -> Self {
+        Self {
+            input,
+            // events start right after magic number
+            offset: 4,
+            cache: TableMapCache::new(),
+            context: ParseContext::default(),
+            kind: StreamKind::Binlog,
+            format_desc: 0,
+            tables: None,
+            skipped: BTreeSet::new(),
+            filter: None,
+            resync: false,
+            started: false,
+            done: false,
+        }
+    }
+
+    /// offset of next event in input
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// repair mode for corrupted binlog, instead of ending with an error, stream scans
+    /// forward from a malformed event for the next valid one and yields the dropped
+    /// bytes as `StreamEvent::Skipped`
+    ///
+    /// a valid event has known type, fits in input, has `log_pos` 0 or pointing to its
+    /// end in input and can be parsed, so input should be a whole binlog file
+    pub fn with_resync(mut self) -> Self {
+        self.resync = true;
+        self
+    }
+
+    /// parse events with `options` instead of defaults
+    pub fn with_options(mut self, options: ParseOptions) -> Self {
+        self.context.options = options;
+        self
+    }
+
+    /// only yield TableMap and rows events of listed `(schema, table)`
+    ///
+    /// events of other tables are skipped without decoding their bodies,
+    /// events not bound to a table are not filtered
+    pub fn with_table_filter(mut self, tables: &[(&str, &str)]) -> Self {
+        self.tables = Some(
+            tables
+                .iter()
+                .map(|(schema, table)| (schema.to_string(), table.to_string()))
+                .collect(),
+        );
+        self
+    }
+
+    /// only yield events matching `filter`, unlike table filter events are decoded
+    /// before being checked
+    ///
+    /// stream still ends after a Stop event even if it is filtered out
+    pub fn with_filter(mut self, filter: Filter) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    /// whether event should be dropped by table filter, TableMap events update
+    /// the set of skipped table_ids
+    fn filtered(&mut self, header: &Header, body: &[u8]) -> bool {
+        let tables = match &self.tables {
+            Some(tables) => tables,
+            None => return false,
+        };
+        let kind = header.kind();
+        if kind == EventType::TableMap {
+            // malformed TableMap is left to parser
+            if let Ok((_, (table_id, schema, table))) = peek_table_map(body) {
+                if tables.contains(&(schema, table)) {
+                    self.skipped.remove(&table_id);
+                    return false;
+                }
+                self.skipped.insert(table_id);
+                return true;
+            }
+        } else if is_rows_type(kind) {
+            if let Ok((_, table_id)) = take_u48_le(body) {
+                return self.skipped.contains(&table_id);
+            }
+        }
+        false
+    }
+
+    /// pair rows events with TableMap of their tables
+    pub fn decoded(self) -> DecodedStream<'a> {
+        DecodedStream { stream: self }
+    }
+
+    /// kind of stream detected from events read so far
+    pub fn kind(&self) -> StreamKind {
+        self.kind
+    }
+
+    /// pair events with raw bytes they are parsed from, e.g. to forward them unchanged
+    pub fn raw(self) -> RawStream<'a> {
+        RawStream { stream: self }
+    }
+
+    /// whether a valid event starts at `offset`, see `with_resync`
+    fn plausible(&self, offset: usize) -> bool {
+        let remain = &self.input[offset..];
+        let header = match parse_header(remain) {
+            Ok((_, header)) => header,
+            Err(_) => return false,
+        };
+        let event_size = header.event_size as usize;
+        if matches!(header.kind(), EventType::Unknown | EventType::Other(_))
+            || event_size < 19
+            || event_size > remain.len()
+            || (header.log_pos != 0 && header.log_pos as usize != offset + event_size)
+        {
+            return false;
+        }
+        // rows event looks up its table only, TableMap event is recorded into scratch cache
+        let body = remain.get((self.context.header_len as usize).max(19)..);
+        let mut scratch = match body.map(take_u48_le) {
+            Some(Ok((_, table_id))) if is_rows_type(header.kind()) => self.cache.subset(table_id),
+            _ => TableMapCache::new(),
+        };
+        Event::parse_with_context(remain, &self.context, &mut scratch).is_ok()
+    }
+
+    fn next_event(&mut self) -> Result<Option<(StreamEvent, &'a [u8])>, Error> {
+        match self.read_event() {
+            Err(e) if self.resync && !matches!(e, Error::BadMagic) => {
+                let start = self.offset;
+                let end = match (start + 1..self.input.len()).find(|&pos| self.plausible(pos)) {
+                    Some(end) => end,
+                    None => return Err(e),
+                };
+                log::warn!(
+                    "skip {} bytes at offset {} to resync: {}",
+                    end - start,
+                    start,
+                    e
+                );
+                self.offset = end;
+                Ok(Some((
+                    StreamEvent::Skipped { start, end },
+                    &self.input[start..end],
+                )))
+            }
+            item => item,
+        }
+    }
+
+    fn read_event(&mut self) -> Result<Option<(StreamEvent, &'a [u8])>, Error> {
+        if !self.started {
+            self.started = true;
+            check_start(self.input).map_err(|_| Error::BadMagic)?;
+        }
+        let input = self.input;
+        loop {
+            let remain = loop {
+                let remain = &input[self.offset..];
+                if remain.is_empty() {
+                    return Ok(None);
+                }
+                let truncated = Error::Truncated {
+                    offset: self.offset,
+                };
+                let (body, header) = parse_header(remain).map_err(|_| truncated.clone())?;
+                let event_size = header.event_size as usize;
+                if remain.len() < event_size {
+                    return Err(truncated);
+                }
+                // event_size smaller than header is left to parser
+                if event_size >= 19 && self.filtered(&header, body) {
+                    self.offset += event_size;
+                    continue;
+                }
+                break remain;
+            };
+            let (i, event) = Event::parse_verbose(remain, &self.context, &mut self.cache)
+                .map_err(|e| Error::from_nom(e, self.offset))?;
+            let raw = &remain[..remain.len() - i.len()];
+            self.offset += raw.len();
+            self.context.update(&event);
+            if let Event::FormatDesc { .. } = event {
+                self.format_desc += 1;
+            }
+            if event.header().flags.relay_log() || self.format_desc > 1 {
+                self.kind = StreamKind::RelayLog;
+            }
+            if let Event::Stop { .. } = event {
+                self.done = true;
+            }
+            if let Some(filter) = &self.filter {
+                if !filter.matches(&event, &self.cache) {
+                    if self.done {
+                        return Ok(None);
+                    }
+                    continue;
+                }
+            }
+            return Ok(Some((StreamEvent::from(event), raw)));
+        }
+    }
+
+    fn next_raw(&mut self) -> Option<Result<(StreamEvent, &'a [u8]), Error>> {
+        if self.done {
+            return None;
+        }
+        let item = self.next_event();
+        if !matches!(item, Ok(Some(_))) {
+            self.done = true;
+        }
+        item.transpose()
+    }
+}
+
+/// read whole binlog file, gzipped file is decompressed when `gzip` feature is enabled
+///
+/// gzip is detected by magic bytes `1f 8b` instead of file extension, without `gzip` feature
+/// such file is reported as `Error::BadMagic` by stream
+#[cfg(feature = "std")]
+pub fn read_binlog_path<P: AsRef<std::path::Path>>(path: P) -> Result<alloc::vec::Vec<u8>, Error> {
+    let data = std::fs::read(path).map_err(|e| Error::Io(e.kind()))?;
+    #[cfg(feature = "gzip")]
+    {
+        if data.starts_with(&[0x1f, 0x8b]) {
+            use std::io::Read;
+
+            let mut decoded = alloc::vec![];
+            flate2::read::GzDecoder::new(data.as_slice())
+                .read_to_end(&mut decoded)
+                .map_err(|e| Error::Io(e.kind()))?;
+            return Ok(decoded);
+        }
+    }
+    Ok(data)
+}
+
+/// parse all events of binlog file at `path` with BinlogStream, see `read_binlog_path`
+#[cfg(feature = "std")]
+pub fn parse_binlog_path<P: AsRef<std::path::Path>>(
+    path: P,
+) -> Result<alloc::vec::Vec<StreamEvent>, Error> {
+    let data = read_binlog_path(path)?;
+    BinlogStream::new(&data).collect()
+}
+
+/// binlog files listed in index file(e.g. `mysql-bin.index`) in order, paths are returned
+/// as written by server, e.g. `./mysql-bin.000001`, blank lines are skipped
+#[cfg(feature = "std")]
+pub fn parse_binlog_index<R: std::io::BufRead>(
+    reader: R,
+) -> Result<alloc::vec::Vec<alloc::string::String>, Error> {
+    let mut files = alloc::vec![];
+    for line in reader.lines() {
+        let line = line.map_err(|e| Error::Io(e.kind()))?;
+        let line = line.trim();
+        if !line.is_empty() {
+            files.push(line.into());
+        }
+    }
+    Ok(files)
+}
+
+impl<'a> Iterator for BinlogStream<'a> {
+    type Item = Result<StreamEvent, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_raw().map(|item| item.map(|(event, _)| event))
+    }
+}
+
+/// BinlogStream yielding events along with their raw bytes
+pub struct RawStream<'a> {
+    stream: BinlogStream<'a>,
+}
+
+impl<'a> RawStream<'a> {
+    /// offset of next event in input
+    pub fn offset(&self) -> usize {
+        self.stream.offset()
+    }
+}
+
+impl<'a> Iterator for RawStream<'a> {
+    type Item = Result<(StreamEvent, &'a [u8]), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.stream.next_raw()
+    }
+}
+
+/// iterate headers of a binlog file, input should include magic number
+///
+/// bodies are skipped by `event_size` without being decoded, much faster than
+/// BinlogStream when only type, size or position of events are needed
+pub fn headers_iter(input: &[u8]) -> Headers<'_> {
+    Headers {
+        input,
+        offset: 4,
+        started: false,
+        done: false,
+    }
+}
+
+/// iterator returned by `headers_iter`
+pub struct Headers<'a> {
+    input: &'a [u8],
+    offset: usize,
+    started: bool,
+    done: bool,
+}
+
+impl<'a> Headers<'a> {
+    /// offset of next event in input
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    fn next_header(&mut self) -> Result<Option<Header>, Error> {
+        if !self.started {
+            self.started = true;
+            check_start(self.input).map_err(|_| Error::BadMagic)?;
+        }
+        let remain = &self.input[self.offset..];
+        if remain.is_empty() {
+            return Ok(None);
+        }
+        let truncated = Error::Truncated {
+            offset: self.offset,
+        };
+        let (_, header) = parse_header(remain).map_err(|_| truncated.clone())?;
+        let event_size = header.event_size as usize;
+        if event_size < 19 {
+            return Err(Error::Parse {
+                offset: self.offset,
+                kind: ErrorKind::LengthValue,
+                context: None,
+            });
+        }
+        if remain.len() < event_size {
+            return Err(truncated);
+        }
+        self.offset += event_size;
+        Ok(Some(header))
+    }
+}
+
+impl<'a> Iterator for Headers<'a> {
+    type Item = Result<Header, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let item = self.next_header();
+        if !matches!(item, Ok(Some(_))) {
+            self.done = true;
+        }
+        item.transpose()
+    }
+}
+
+/// item yielded by DecodedStream
+#[derive(Debug, PartialEq, Clone)]
+pub enum DecodedEvent {
+    /// rows event along with table info of its TableMap
+    Rows {
+        event: Event,
+        table: TableInfo,
+    },
+    Event(Event),
+    Stop {
+        header: Header,
+    },
+    RotateTo {
+        header: Header,
+        next_binlog: String,
+        position: u64,
+    },
+    Heartbeat {
+        header: Header,
+        log_pos: u64,
+    },
+    Skipped {
+        start: usize,
+        end: usize,
+    },
+}
+
+/// BinlogStream yielding rows events paired with their TableMap
+pub struct DecodedStream<'a> {
+    stream: BinlogStream<'a>,
+}
+
+impl<'a> DecodedStream<'a> {
+    /// offset of next event in input
+    pub fn offset(&self) -> usize {
+        self.stream.offset()
+    }
+}
+
+impl<'a> Iterator for DecodedStream<'a> {
+    type Item = Result<DecodedEvent, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let event = match self.stream.next()? {
+            Ok(StreamEvent::Event(event)) => event,
+            Ok(StreamEvent::Stop { header }) => return Some(Ok(DecodedEvent::Stop { header })),
+            Ok(StreamEvent::RotateTo {
+                header,
+                next_binlog,
+                position,
+            }) => {
+                return Some(Ok(DecodedEvent::RotateTo {
+                    header,
+                    next_binlog,
+                    position,
+                }))
+            }
+            Ok(StreamEvent::Heartbeat { header, log_pos }) => {
+                return Some(Ok(DecodedEvent::Heartbeat { header, log_pos }))
+            }
+            Ok(StreamEvent::Skipped { start, end }) => {
+                return Some(Ok(DecodedEvent::Skipped { start, end }))
+            }
+            Err(e) => return Some(Err(e)),
+        };
+        let table = match event.table_id() {
+            Some(table_id) if event.is_rows_event() => self.stream.cache.get(table_id).cloned(),
+            _ => None,
+        };
+        Some(Ok(match table {
+            Some(table) => DecodedEvent::Rows { event, table },
+            None => DecodedEvent::Event(event),
+        }))
+    }
+}
@@ -1,9 +1,11 @@
 #![allow(dead_code)]
 
+use alloc::string::{String, ToString};
 use nom::{
     bytes::complete::{take, take_till},
     combinator::map,
-    number::complete::{le_u16, le_u32, le_u64, le_u8},
+    error::{ErrorKind, ParseError},
+    number::complete::{le_u16, le_u24, le_u32, le_u64, le_u8},
     IResult,
 };
 
@@ -12,48 +14,54 @@ use nom::{
 /// ref: https://dev.mysql.com/doc/internals/en/integer.html#fixed-length-integer
 pub fn int_fixed<'a>(input: &'a [u8], len: u8) -> IResult<&'a [u8], u64> {
     match len {
-        1 => map(le_u8, |v| v as u64)(input),
-        2 => map(le_u16, |v| v as u64)(input),
-        3 | 6 => map(take(3usize), |s: &[u8]| {
-            let mut filled = s.to_vec();
-            if len == 3 {
-                filled.extend(vec![0, 0, 0, 0, 0]);
-            } else {
-                filled.extend(vec![0, 0]);
-            }
-            pu64(&filled).unwrap().1
+        1..=8 => map(take(len), |s: &[u8]| {
+            s.iter().rev().fold(0, |acc, &b| (acc << 8) | b as u64)
         })(input),
-        4 => map(le_u32, |v| v as u64)(input),
-        8 => le_u64(input),
-        _ => unreachable!(),
+        _ => Err(nom::Err::Error((input, ErrorKind::LengthValue))),
+    }
+}
+
+/// consume `[00]` terminator of string, fails with expected char `'\0'` on any other byte
+pub fn terminator<'a, E: ParseError<&'a [u8]>>(input: &'a [u8]) -> IResult<&'a [u8], (), E> {
+    match input.first() {
+        Some(0x00) => Ok((&input[1..], ())),
+        Some(b) => {
+            log::error!("expect string terminator 0x00, got {:#04x}", b);
+            Err(nom::Err::Error(E::from_char(input, '\0')))
+        }
+        None => Err(nom::Err::Error(E::from_error_kind(input, ErrorKind::Eof))),
     }
 }
 
+/// read 6 bytes little endian int, eg. table_id
+pub fn take_u48_le(input: &[u8]) -> IResult<&[u8], u64> {
+    let (i, low) = le_u32(input)?;
+    let (i, high) = le_u16(i)?;
+    Ok((i, (high as u64) << 32 | low as u64))
+}
+
 /// parse len encoded int, return (used_bytes, value).
 ///
 /// ref: https://dev.mysql.com/doc/internals/en/integer.html#packet-Protocol::LengthEncodedInteger
 pub fn int_lenenc<'a>(input: &'a [u8]) -> IResult<&'a [u8], (usize, u64)> {
-    match input[0] {
-        0..=0xfa => map(le_u8, |num: u8| (1, num as u64))(input),
-        0xfb | 0xfc => {
-            let (i, _) = take(1usize)(input)?;
-            map(le_u16, |num: u16| (3, num as u64))(i)
-        }
-        0xfd => {
-            let (i, _) = take(1usize)(input)?;
-            let (i, v) = map(take(3usize), |s: &[u8]| {
-                let mut raw = s.to_vec();
-                raw.push(0);
-                raw
-            })(i)?;
-            let (_, num) = pu32(&v).unwrap();
-            Ok((i, (4, num as u64)))
-        }
-        0xfe => {
-            let (i, _) = take(1usize)(input)?;
-            map(le_u64, |v: u64| (9, v))(i)
-        }
-        0xff => unreachable!(),
+    let (i, marker) = le_u8(input)?;
+    match marker {
+        0..=0xfa => Ok((i, (1, marker as u64))),
+        0xfc => map(le_u16, |num: u16| (3, num as u64))(i),
+        0xfd => map(le_u24, |num: u32| (4, num as u64))(i),
+        0xfe => map(le_u64, |num: u64| (9, num))(i),
+        // 0xfb(NULL) and 0xff(ERR) are not valid integer
+        _ => Err(nom::Err::Error((input, ErrorKind::Tag))),
+    }
+}
+
+/// return bytes used to encode num as len encoded int
+pub fn int_lenenc_len(num: u64) -> usize {
+    match num {
+        0..=0xfa => 1,
+        0xfb..=0xffff => 3,
+        0x1_0000..=0xff_ffff => 4,
+        _ => 9,
     }
 }
 
@@ -108,6 +116,74 @@ pub fn string_fixed(input: &[u8]) -> IResult<&[u8], (u8, String)> {
     })(i)
 }
 
+/// render terminator byte of Load event(`field_term`, `line_term` etc.) as written in
+/// LOAD DATA statement, e.g. `\t` for tab
+///
+/// ref: https://dev.mysql.com/doc/refman/8.0/en/load-data.html#load-data-field-line-handling
+pub fn escape_terminator(byte: u8) -> String {
+    match byte {
+        0x00 => String::from("\\0"),
+        0x08 => String::from("\\b"),
+        b'\n' => String::from("\\n"),
+        b'\r' => String::from("\\r"),
+        b'\t' => String::from("\\t"),
+        0x1a => String::from("\\Z"),
+        b'\'' => String::from("\\'"),
+        b'\\' => String::from("\\\\"),
+        b => String::from(b as char),
+    }
+}
+
+// lookup table of reflected polynomial 0xedb88320, one entry per byte value
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut crc = n as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[n] = crc;
+        n += 1;
+    }
+    table
+}
+
+/// compute crc32(IEEE) checksum, same as zlib's crc32 used by MySQL
+pub fn crc32(input: &[u8]) -> u32 {
+    !input.iter().fold(0xffff_ffffu32, |crc, &byte| {
+        CRC32_TABLE[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+/// standard base64 with padding
+pub fn base64(data: &[u8]) -> String {
+    const CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut s = String::with_capacity(data.len() * 4 / 3 + 4);
+    for chunk in data.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |acc, (idx, &b)| acc | (b as u32) << (16 - 8 * idx));
+        for idx in 0..4 {
+            if idx <= chunk.len() {
+                s.push(CHARS[(n >> (18 - 6 * idx) & 0x3f) as usize] as char);
+            } else {
+                s.push('=');
+            }
+        }
+    }
+    s
+}
+
 pub fn pu32(input: &[u8]) -> IResult<&[u8], u32> {
     le_u32(input)
 }
@@ -1,6 +1,37 @@
 use boxercrab::ColValues::*;
 use boxercrab::Event;
 use boxercrab::Event::*;
+use boxercrab::IntValue::*;
+
+/// build raw event bytes with a zeroed header and checksum
+fn raw_event(event_type: u8, body: &[u8]) -> Vec<u8> {
+    let mut buf = vec![0, 0, 0, 0, event_type, 1, 0, 0, 0];
+    buf.extend_from_slice(&((19 + body.len() + 4) as u32).to_le_bytes());
+    buf.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+    buf.extend_from_slice(body);
+    buf.extend_from_slice(&[0, 0, 0, 0]);
+    buf
+}
+
+/// TableMap event of table `d`.`table` with one nullable LONG column
+fn raw_table_map(table_id: u8, table: &str) -> Vec<u8> {
+    let mut body = vec![table_id, 0, 0, 0, 0, 0, 0x01, 0x00];
+    body.extend_from_slice(&[0x01, b'd', 0x00]);
+    body.push(table.len() as u8);
+    body.extend_from_slice(table.as_bytes());
+    body.push(0x00);
+    // one LONG column, no meta, nullable
+    body.extend_from_slice(&[0x01, 0x03, 0x00, 0x01]);
+    raw_event(0x13, &body)
+}
+
+/// WriteRowsV2 event of one row `(val)` for table of `raw_table_map`
+fn raw_write_rows(table_id: u8, val: u8) -> Vec<u8> {
+    let body = [
+        table_id, 0, 0, 0, 0, 0, 0x01, 0x00, 0x02, 0x00, 0x01, 0x01, 0x00, val, 0, 0, 0,
+    ];
+    raw_event(0x1e, &body)
+}
 
 #[test]
 fn test_stop() {
@@ -46,6 +77,24 @@ fn test_intvar() {
     }
 }
 
+#[test]
+fn test_intvar_unknown_type() {
+    use boxercrab::IntVarEventType;
+
+    let mut body = vec![0x7f];
+    body.extend_from_slice(&42u64.to_le_bytes());
+    let input = raw_event(0x05, &body);
+    let (remain, event) = Event::parse(&input).unwrap();
+    assert_eq!(remain.len(), 0);
+    match event {
+        IntVar { e_type, value, .. } => {
+            assert_eq!(e_type, IntVarEventType::Unknown(0x7f));
+            assert_eq!(value, 42);
+        }
+        _ => panic!("should be intvar"),
+    }
+}
+
 #[test]
 fn test_rand() {
     let input = include_bytes!("events/13_rand/log.bin");
@@ -116,6 +165,38 @@ fn test_user_var() {
         }
         _ => panic!("should be user var"),
     }
+    assert_eq!(output[11].user_var_decimal(), Some("1.00".to_string()));
+    assert_eq!(output[10].user_var_decimal(), None);
+}
+
+#[test]
+fn test_decimal_decode() {
+    use boxercrab::decimal::decode;
+
+    // @x := 12.34 is DECIMAL(4, 2)
+    assert_eq!(decode(&[0x8c, 0x22], 4, 2), Some("12.34".to_string()));
+    assert_eq!(decode(&[0x73, 0xdd], 4, 2), Some("-12.34".to_string()));
+    assert_eq!(decode(&[0x80, 0x05], 4, 2), Some("0.05".to_string()));
+    // full group of 9 digits in integer part, partial group in fraction part
+    let mut data = vec![0x81];
+    data.extend_from_slice(&234_567_890u32.to_be_bytes());
+    data.extend_from_slice(&1234u16.to_be_bytes());
+    assert_eq!(decode(&data, 14, 4), Some("1234567890.1234".to_string()));
+    assert_eq!(
+        decode(&[0x80, 0, 0, 0, 0x2a], 10, 0),
+        Some("42".to_string())
+    );
+    assert_eq!(decode(&[0x8c], 4, 2), None);
+    assert_eq!(decode(&[0x8c, 0x22], 2, 4), None);
+
+    // name `x`, not null, DECIMAL, utf8_general_ci, value
+    let mut var = 1u32.to_le_bytes().to_vec();
+    var.extend_from_slice(&[b'x', 0x00, 0x04]);
+    var.extend_from_slice(&33u32.to_le_bytes());
+    var.extend_from_slice(&4u32.to_le_bytes());
+    var.extend_from_slice(&[4, 2, 0x8c, 0x22]);
+    let event = Event::parse(&raw_event(0x0e, &var)).unwrap().1;
+    assert_eq!(event.user_var_decimal(), Some("12.34".to_string()));
 }
 
 #[test]
@@ -136,6 +217,19 @@ fn test_format_desc() {
         }
         _ => panic!("should be format desc"),
     }
+    assert_eq!(
+        output.get(0).unwrap().checksum_alg(),
+        Some(boxercrab::ChecksumAlg::Crc32)
+    );
+    assert_eq!(output.get(1).unwrap().checksum_alg(), None);
+
+    let format_desc = output.get(0).unwrap();
+    assert_eq!(format_desc.post_header_len(0x02), Some(13));
+    assert_eq!(format_desc.post_header_len(0x13), Some(8));
+    assert_eq!(format_desc.post_header_len(0x1e), Some(10));
+    assert_eq!(format_desc.post_header_len(0x00), None);
+    assert_eq!(format_desc.post_header_len(0xff), None);
+    assert_eq!(Event::xid(1).post_header_len(0x02), None);
 }
 
 #[test]
@@ -154,6 +248,7 @@ fn test_xid() {
 #[test]
 fn test_table_map() {
     use boxercrab::ColTypes::*;
+    use boxercrab::TableMapFlags;
 
     // TODO need to test more column types
     let input = include_bytes!("events/19_table_map/log.bin");
@@ -170,12 +265,79 @@ fn test_table_map() {
         } => {
             assert_eq!(*table_id, 110);
             assert_eq!(table_name, "boxercrab");
-            assert_eq!(*flags, 1);
+            assert_eq!(*flags, TableMapFlags::from_u16(1));
+            assert!(flags.bit_len_exact);
+            assert!(!flags.referred_fk_db && !flags.generated_invisible_pk);
             assert_eq!(*columns_type, vec![Long, VarChar(160)]);
             assert_eq!(*null_bits, vec![0]);
         }
         _ => panic!("should be table_map"),
     }
+    assert_eq!(
+        output[8].schema_and_table(),
+        Some(("default", Some("boxercrab")))
+    );
+    assert_eq!(output[9].schema_and_table(), None);
+
+    match Event::table_map(1, "db", "t", vec![Long]) {
+        TableMap { flags, .. } => assert_eq!(
+            flags,
+            TableMapFlags {
+                bit_len_exact: false,
+                referred_fk_db: false,
+                generated_invisible_pk: false,
+            }
+        ),
+        _ => panic!("should be table_map"),
+    }
+}
+
+#[test]
+fn test_table_schema() {
+    use boxercrab::{ColTypes, ColumnDef};
+
+    let input = include_bytes!("events/19_table_map/log.bin");
+    let (_, output) = Event::from_bytes(input).unwrap();
+    let table = output.get(8).unwrap().table_schema().unwrap();
+    assert_eq!(table.schema, "default");
+    assert_eq!(table.table, "boxercrab");
+    assert_eq!(
+        table.columns,
+        vec![
+            ColumnDef {
+                column_type: ColTypes::Long,
+                metadata: vec![],
+                nullable: false,
+            },
+            ColumnDef {
+                column_type: ColTypes::VarChar(160),
+                metadata: vec![160, 0],
+                nullable: false,
+            },
+        ]
+    );
+
+    let mut event = Event::table_map(
+        1,
+        "db",
+        "t",
+        vec![
+            ColTypes::Long,
+            ColTypes::VarChar(300),
+            ColTypes::NewDecimal(10, 2),
+            ColTypes::DateTime2(3),
+        ],
+    );
+    if let TableMap { null_bits, .. } = &mut event {
+        *null_bits = vec![0b1010];
+    }
+    let table = event.table_schema().unwrap();
+    assert_eq!(table.columns.len(), 4);
+    let metadata: Vec<_> = table.columns.iter().map(|c| c.metadata.clone()).collect();
+    assert_eq!(metadata, vec![vec![], vec![44, 1], vec![10, 2], vec![3]]);
+    let nullable: Vec<_> = table.columns.iter().map(|c| c.nullable).collect();
+    assert_eq!(nullable, vec![false, true, false, true]);
+    assert_eq!(Event::xid(1).table_schema(), None);
 }
 
 #[test]
@@ -204,7 +366,7 @@ fn test_begin_load_query_and_exec_load_query() {
             ..
         } => {
             assert_eq!(*file_id, 1);
-            assert_eq!(block_data, "1,\"abc\"\n");
+            assert_eq!(block_data, b"1,\"abc\"\n");
         }
         _ => panic!("should be begin load query"),
     };
@@ -229,6 +391,46 @@ fn test_begin_load_query_and_exec_load_query() {
     }
 }
 
+#[test]
+fn test_exec_load_query_consumed() {
+    use boxercrab::ErrorCode;
+
+    let input = include_bytes!("events/17_18_load/log.bin");
+    // skip magic number, then walk through raw events by event_size
+    let mut i = &input[4..];
+    let mut raw = None;
+    while !i.is_empty() {
+        let size = u32::from_le_bytes([i[9], i[10], i[11], i[12]]) as usize;
+        if i[4] == 0x12 {
+            raw = Some(&i[..size]);
+        }
+        i = &i[size..];
+    }
+    let (remain, event) = Event::parse(raw.unwrap()).unwrap();
+    assert_eq!(remain.len(), 0);
+    match event {
+        ExecuteLoadQueryEvent {
+            error_code,
+            status_vars_length,
+            status_vars,
+            file_id,
+            schema,
+            ..
+        } => {
+            assert_eq!(error_code, ErrorCode::NoError);
+            assert!(status_vars_length > 0);
+            assert!(!status_vars.is_empty());
+            // fields around status vars are read from right offsets
+            assert_eq!(file_id, 1);
+            assert_eq!(schema, "default");
+        }
+        _ => panic!("should be exec load query"),
+    }
+    assert_eq!(ErrorCode::from_u16(1062), ErrorCode::DupEntry);
+    assert_eq!(ErrorCode::from_u16(9999), ErrorCode::Other(9999));
+    assert_eq!(ErrorCode::LockDeadlock.as_u16(), 1213);
+}
+
 #[test]
 fn test_write_rows_v2() {
     let input = include_bytes!("events/30_write_rows_v2/log.bin");
@@ -245,10 +447,7 @@ fn test_write_rows_v2() {
             assert_eq!(*column_count, 2);
             assert_eq!(
                 *rows,
-                vec![vec![
-                    Long(vec![1, 0, 0, 0]),
-                    VarChar(vec![97, 98, 99, 100, 101])
-                ]]
+                vec![vec![Long(Signed(1)), VarChar(vec![97, 98, 99, 100, 101])]]
             )
         }
         _ => panic!("should write_rows_v2"),
@@ -264,7 +463,7 @@ fn test_update_rows_v2() {
     let xd = vec![120, 100];
     let values = vec![
         vec![
-            Long(vec![1, 0, 0, 0]),
+            Long(Signed(1)),
             VarChar(abc.clone()),
             VarChar(abc.clone()),
             Blob(abc.clone()),
@@ -272,10 +471,10 @@ fn test_update_rows_v2() {
             Blob(abc.clone()),
             Float(1.0),
             Double(2.0),
-            NewDecimal(vec![128, 0, 3, 0, 0]),
+            NewDecimal("3.0000".into()),
         ],
         vec![
-            Long(vec![1, 0, 0, 0]),
+            Long(Signed(1)),
             VarChar(xd.clone()),
             VarChar(xd.clone()),
             Blob(xd.clone()),
@@ -283,7 +482,7 @@ fn test_update_rows_v2() {
             Blob(xd.clone()),
             Float(4.0),
             Double(4.0),
-            NewDecimal(vec![128, 0, 4, 0, 0]),
+            NewDecimal("4.0000".into()),
         ],
     ];
     match update_row {
@@ -308,10 +507,7 @@ fn test_delete_rows_v2() {
             assert_eq!(*column_count, 2);
             assert_eq!(
                 *rows,
-                vec![vec![
-                    Long(vec![1, 0, 0, 0]),
-                    VarChar(vec![97, 98, 99, 100, 101])
-                ]]
+                vec![vec![Long(Signed(1)), VarChar(vec![97, 98, 99, 100, 101])]]
             )
         }
         _ => panic!("should be delete rows v2"),
@@ -334,8 +530,8 @@ fn test_gtid() {
             ..
         } => {
             assert_eq!(*rbr_only, false);
-            assert_eq!(source_id, "12884158204-210242-17234-183144-2661721902");
-            assert_eq!(transaction_id, "10000000");
+            assert_eq!(source_id, "80549ecc-d2f2-11ea-b790-0242ac130002");
+            assert_eq!(*transaction_id, 1);
             assert_eq!(*ts_type, 2);
             assert_eq!(*last_committed, 0);
             assert_eq!(*sequence_number, 1);
@@ -360,8 +556,8 @@ fn test_anonymous_gtid() {
             ..
         } => {
             assert_eq!(*rbr_only, false);
-            assert_eq!(source_id, "0000-00-00-00-000000");
-            assert_eq!(transaction_id, "00000000");
+            assert_eq!(source_id, "00000000-0000-0000-0000-000000000000");
+            assert_eq!(*transaction_id, 0);
             assert_eq!(*ts_type, 2);
             assert_eq!(*last_committed, 0);
             assert_eq!(*sequence_number, 1);
@@ -382,3 +578,2680 @@ fn test_previous_gtid() {
         _ => panic!("should be previous gtid"),
     }
 }
+
+#[test]
+fn test_event_slices() {
+    use boxercrab::event_slices;
+
+    let input = include_bytes!("events/16_xid/log.bin");
+    let mut i = &input[4..];
+    while i.len() != 0 {
+        let (remain, (header, body, _)) = event_slices(i).unwrap();
+        assert_eq!(body.len() as u32, header.event_size - 19 - 4);
+        i = remain;
+    }
+}
+
+#[test]
+fn test_xid_to_bytes() {
+    use boxercrab::event_slices;
+
+    let input = include_bytes!("events/16_xid/log.bin");
+    let (_, output) = Event::from_bytes(input).unwrap();
+    let mut i = &input[4..];
+    for _ in 0..10 {
+        i = event_slices(i).unwrap().0;
+    }
+    let (_, (header, _, _)) = event_slices(i).unwrap();
+    let raw = &i[..header.event_size as usize];
+
+    let xid = output.get(10).unwrap();
+    let bytes = xid.to_bytes().unwrap();
+    assert_eq!(bytes, raw);
+    let (remain, parsed) = Event::parse(&bytes).unwrap();
+    assert_eq!(remain.len(), 0);
+    assert_eq!(&parsed, xid);
+}
+
+#[test]
+fn test_col_type_name() {
+    let input = include_bytes!("events/19_table_map/log.bin");
+    let (_, output) = Event::from_bytes(input).unwrap();
+    match output.get(8).unwrap() {
+        TableMap { columns_type, .. } => {
+            let names: Vec<&str> = columns_type.iter().map(|c| c.name()).collect();
+            assert_eq!(names, vec!["LONG", "VARCHAR"]);
+        }
+        _ => panic!("should be table_map"),
+    }
+    assert_eq!(boxercrab::ColTypes::NewDecimal(10, 2).name(), "NEWDECIMAL");
+}
+
+#[test]
+fn test_build_events() {
+    let event = Event::query("boxercrab", "CREATE TABLE t (id INT)");
+    let bytes = event.to_bytes().unwrap();
+    let (remain, parsed) = Event::parse(&bytes).unwrap();
+    assert_eq!(remain.len(), 0);
+    match (event, parsed) {
+        (
+            Query { header: h1, .. },
+            Query {
+                header,
+                schema,
+                query,
+                ..
+            },
+        ) => {
+            assert_eq!(h1.event_size as usize, bytes.len());
+            assert_eq!(header, h1);
+            assert_eq!(schema, "boxercrab");
+            assert_eq!(query, "CREATE TABLE t (id INT)");
+        }
+        _ => panic!("should be query"),
+    }
+
+    match Event::xid(42) {
+        XID { header, xid, .. } => {
+            assert_eq!(header.event_size, 19 + 8 + 4);
+            assert_eq!(xid, 42);
+        }
+        _ => panic!("should be xid"),
+    }
+
+    match Event::table_map(1, "db", "t", vec![boxercrab::ColTypes::Long]) {
+        TableMap {
+            header,
+            column_count,
+            null_bits,
+            ..
+        } => {
+            assert_eq!(header.event_size, 19 + 6 + 2 + 4 + 3 + 1 + 1 + 1 + 1 + 4);
+            assert_eq!(column_count, 1);
+            assert_eq!(null_bits, vec![0xff]);
+        }
+        _ => panic!("should be table_map"),
+    }
+}
+
+#[test]
+fn test_view_change() {
+    let mut body = vec![];
+    let mut view_id = b"15972354546781259:1".to_vec();
+    view_id.resize(40, 0);
+    body.extend(view_id);
+    body.extend_from_slice(&3u64.to_le_bytes());
+    body.extend_from_slice(&1u32.to_le_bytes());
+    body.extend_from_slice(&19u16.to_le_bytes());
+    body.extend_from_slice(b"group_gtid_executed");
+    body.extend_from_slice(&2u32.to_le_bytes());
+    body.extend_from_slice(&[1, 2]);
+    let input = raw_event(0x25, &body);
+    let (remain, output) = Event::parse(&input).unwrap();
+    assert_eq!(remain.len(), 0);
+    match output {
+        ViewChange {
+            view_id,
+            seq_number,
+            cert_info,
+            ..
+        } => {
+            assert_eq!(view_id, "15972354546781259:1");
+            assert_eq!(seq_number, 3);
+            assert_eq!(
+                cert_info,
+                vec![("group_gtid_executed".to_string(), vec![1, 2])]
+            );
+        }
+        _ => panic!("should be view change"),
+    }
+}
+
+#[test]
+fn test_table_map_cache() {
+    use boxercrab::{event_slices, TableMapCache};
+
+    let input = include_bytes!("events/30_write_rows_v2/log.bin");
+    let mut cache = TableMapCache::new();
+    let mut i = &input[4..];
+    let mut raw_rows = None;
+    while i.len() != 0 {
+        let (_, (header, _, _)) = event_slices(i).unwrap();
+        if header.event_type == 0x1e {
+            raw_rows = Some(i);
+        }
+        let (remain, _) = Event::parse_with_cache(i, &mut cache).unwrap();
+        i = remain;
+    }
+    let table = cache.get(111).unwrap();
+    assert_eq!(table.table_name, "boxercrab");
+    assert_eq!(table.columns_type.len(), 2);
+
+    let raw_rows = raw_rows.unwrap();
+    match Event::parse_with_cache(raw_rows, &mut cache).unwrap().1 {
+        WriteRowsV2 { rows, .. } => assert_eq!(rows.len(), 1),
+        _ => panic!("should be write_rows_v2"),
+    }
+    assert!(Event::parse_with_cache(raw_rows, &mut TableMapCache::new()).is_err());
+}
+
+#[test]
+fn test_multi_rows() {
+    use boxercrab::{ColTypes, TableMapCache};
+
+    let mut cache = TableMapCache::new();
+    cache.insert(&Event::table_map(
+        1,
+        "db",
+        "t",
+        vec![ColTypes::Long, ColTypes::VarChar(20)],
+    ));
+    let mut body = vec![1, 0, 0, 0, 0, 0, 1, 0, 2, 0, 2, 0b11];
+    // (1, 'abc')
+    body.extend_from_slice(&[0, 1, 0, 0, 0, 3, 97, 98, 99]);
+    // (2, NULL)
+    body.extend_from_slice(&[0b10, 2, 0, 0, 0]);
+    let input = raw_event(0x1e, &body);
+    let (remain, output) = Event::parse_with_cache(&input, &mut cache).unwrap();
+    assert_eq!(remain.len(), 0);
+    match output {
+        WriteRowsV2 { rows, .. } => assert_eq!(
+            rows,
+            vec![
+                vec![Long(Signed(1)), VarChar(vec![97, 98, 99])],
+                vec![Long(Signed(2)), Null],
+            ]
+        ),
+        _ => panic!("should be write_rows_v2"),
+    }
+
+    // trailing byte which can not form a row
+    body.push(0);
+    let input = raw_event(0x1e, &body);
+    assert!(Event::parse_with_cache(&input, &mut cache).is_err());
+}
+
+#[test]
+fn test_geometry_value() {
+    use boxercrab::{ColTypes, TableMapCache};
+
+    let mut cache = TableMapCache::new();
+    cache.insert(&Event::table_map(
+        2,
+        "db",
+        "geo",
+        vec![ColTypes::Geometry(4)],
+    ));
+    // POINT(1 2) with SRID 4326
+    let mut wkb = vec![1, 1, 0, 0, 0];
+    wkb.extend_from_slice(&1f64.to_le_bytes());
+    wkb.extend_from_slice(&2f64.to_le_bytes());
+    let mut body = vec![2, 0, 0, 0, 0, 0, 1, 0, 2, 0, 1, 0b1, 0];
+    body.extend_from_slice(&(4 + wkb.len() as u32).to_le_bytes());
+    body.extend_from_slice(&4326u32.to_le_bytes());
+    body.extend_from_slice(&wkb);
+    let input = raw_event(0x1e, &body);
+    let (_, output) = Event::parse_with_cache(&input, &mut cache).unwrap();
+    match output {
+        WriteRowsV2 { rows, .. } => {
+            assert_eq!(rows, vec![vec![Geometry { srid: 4326, wkb }]]);
+        }
+        _ => panic!("should be write_rows_v2"),
+    }
+}
+
+#[test]
+fn test_legacy_temporal_values() {
+    use boxercrab::ColTypes;
+
+    let raw = 20200731134507u64.to_le_bytes();
+    let (remain, (used, val)) = ColTypes::DateTime.parse(&raw).unwrap();
+    assert_eq!(remain.len(), 0);
+    assert_eq!(used, 8);
+    assert_eq!(
+        val,
+        DateTime {
+            year: 2020,
+            month: 7,
+            day: 31,
+            hour: 13,
+            minute: 45,
+            second: 7,
+            micros: 0
+        }
+    );
+
+    let packed = (2021u32 << 9 | 12 << 5 | 25).to_le_bytes();
+    let (_, (used, val)) = ColTypes::Date.parse(&packed[..3]).unwrap();
+    assert_eq!(used, 3);
+    assert_eq!(
+        val,
+        Date {
+            year: 2021,
+            month: 12,
+            day: 25
+        }
+    );
+
+    // month takes bits 5-8, day bits 0-4, year the rest
+    for &(year, month, day) in [(1999u16, 1u8, 31u8), (2020, 10, 1), (9999, 12, 31)].iter() {
+        let packed = ((year as u32) << 9 | (month as u32) << 5 | day as u32).to_le_bytes();
+        for t in [ColTypes::Date, ColTypes::NewDate].iter() {
+            let (remain, (used, val)) = t.parse(&packed[..3]).unwrap();
+            assert!(remain.is_empty());
+            assert_eq!(used, 3);
+            assert_eq!(val, Date { year, month, day });
+            assert!(val.matches_type(t));
+        }
+    }
+}
+
+#[test]
+fn test_year_value() {
+    use boxercrab::ColTypes;
+
+    let (_, (used, val)) = ColTypes::Year.parse(&[121]).unwrap();
+    assert_eq!(used, 1);
+    assert_eq!(val, Year(2021));
+    let (_, (_, val)) = ColTypes::Year.parse(&[0]).unwrap();
+    assert_eq!(val, Year(0));
+}
+
+#[test]
+fn test_int24_value() {
+    use boxercrab::ColTypes;
+
+    let (remain, (used, val)) = ColTypes::Int24.parse(&[0xff, 0xff, 0xff, 0x01]).unwrap();
+    assert_eq!(remain, &[0x01]);
+    assert_eq!(used, 3);
+    assert_eq!(val, Int24(Signed(-1)));
+    let (_, (_, val)) = ColTypes::Int24.parse(&[0x40, 0xe2, 0x01]).unwrap();
+    assert_eq!(val, Int24(Signed(123456)));
+}
+
+#[test]
+fn test_present_columns() {
+    use boxercrab::present_columns;
+
+    let input = include_bytes!("events/30_write_rows_v2/log.bin");
+    let (_, output) = Event::from_bytes(input).unwrap();
+    match output.get(10).unwrap() {
+        WriteRowsV2 {
+            column_count,
+            inserted_image_bits,
+            ..
+        } => {
+            let present: Vec<usize> = present_columns(inserted_image_bits, *column_count).collect();
+            assert_eq!(present, vec![0, 1]);
+            assert_eq!(present.len() as u64, *column_count);
+        }
+        _ => panic!("should write_rows_v2"),
+    }
+    let present: Vec<usize> = present_columns(&[0b101, 0b1], 9).collect();
+    assert_eq!(present, vec![0, 2, 8]);
+}
+
+#[test]
+fn test_strict_parse() {
+    use boxercrab::ParseOptions;
+
+    let strict = ParseOptions {
+        strict: true,
+        ..Default::default()
+    };
+    let input = Event::xid(1).to_bytes().unwrap();
+    let (i, _) = Event::parse_with_options(&input, strict).unwrap();
+    assert_eq!(i.len(), 0);
+
+    // event_size claims one more byte than xid parser consumes
+    let mut wrong = input.clone();
+    wrong.push(0x00);
+    wrong[9..13].copy_from_slice(&(input.len() as u32 + 1).to_le_bytes());
+    assert!(Event::parse(&wrong).is_ok());
+    assert!(Event::parse_with_options(&wrong, strict).is_err());
+}
+
+#[test]
+fn test_pre_ga_rows() {
+    let input = raw_event(0x14, &[0x01, 0x02, 0x03]);
+    let (i, event) = Event::parse(&input).unwrap();
+    assert_eq!(i.len(), 0);
+    match event {
+        PreGaWriteRows { header, data, .. } => {
+            assert_eq!(header.event_type, 0x14);
+            assert_eq!(data, vec![0x01, 0x02, 0x03]);
+        }
+        _ => panic!("should be pre ga write rows"),
+    }
+}
+
+#[test]
+fn test_checksum_alg() {
+    use boxercrab::ChecksumAlg;
+
+    let input = include_bytes!("events/16_xid/log.bin");
+    let (_, output) = Event::from_bytes(input).unwrap();
+    let alg = output.get(0).unwrap().checksum_alg().unwrap();
+    assert_eq!(alg, ChecksumAlg::Crc32);
+    assert_eq!(ChecksumAlg::from_u8(0), ChecksumAlg::Off);
+    assert_eq!(ChecksumAlg::from_u8(255), ChecksumAlg::Unknown(255));
+    assert_eq!(ChecksumAlg::Unknown(255).as_u8(), 255);
+
+    // skip magic number, then walk through raw events by event_size
+    let mut i = &input[4..];
+    while !i.is_empty() {
+        let size = u32::from_le_bytes([i[9], i[10], i[11], i[12]]) as usize;
+        assert!(alg.verify(&i[..size]));
+        i = &i[size..];
+    }
+
+    let mut xid = Event::xid(1).to_bytes().unwrap();
+    assert!(ChecksumAlg::Crc32.verify(&xid));
+    *xid.last_mut().unwrap() ^= 0xff;
+    assert!(!ChecksumAlg::Crc32.verify(&xid));
+    assert!(ChecksumAlg::Off.verify(&xid));
+    assert!(!ChecksumAlg::Unknown(2).verify(&xid));
+}
+
+// only uses apis available without `std` feature
+#[test]
+fn test_alloc_parse() {
+    use boxercrab::{check_start, TableMapCache};
+
+    let input = include_bytes!("events/16_xid/log.bin");
+    let mut cache = TableMapCache::new();
+    let (mut i, _) = check_start(input).unwrap();
+    let mut events = vec![];
+    while !i.is_empty() {
+        let (remain, event) = Event::parse_with_cache(i, &mut cache).unwrap();
+        events.push(event);
+        i = remain;
+    }
+    let (_, expected) = Event::from_bytes(input).unwrap();
+    assert_eq!(events, expected);
+}
+
+#[test]
+fn test_string_value() {
+    use boxercrab::ColTypes;
+
+    // CHAR(3) latin1, real type 254, max length 3
+    let (_, (_, val)) = ColTypes::String(0xfe, 0x03)
+        .parse(&[0x02, b'a', b'b'])
+        .unwrap();
+    assert_eq!(val, String(b"ab".to_vec()));
+
+    // CHAR(100) utf8mb4, max length 400 = 0x190, high bits xor-ed into real type
+    let (i, (_, val)) = ColTypes::String(0xee, 0x90)
+        .parse(&[0x03, 0x00, b'a', b'b', b'c'])
+        .unwrap();
+    assert_eq!(i.len(), 0);
+    assert_eq!(val, String(b"abc".to_vec()));
+
+    // ENUM and SET masquerading as STRING
+    let (_, (_, val)) = ColTypes::String(0xf7, 0x01).parse(&[0x02]).unwrap();
+    assert_eq!(val, Enum(2));
+    let (_, (_, val)) = ColTypes::String(0xf8, 0x02).parse(&[0x01, 0x02]).unwrap();
+    assert_eq!(val, Set(vec![0, 9]));
+}
+
+#[test]
+fn test_event_classification() {
+    let create = Event::query(
+        "boxercrab",
+        "/* ApplicationName=cli */ create TABLE t (id int)",
+    );
+    assert!(create.is_ddl());
+    assert!(!create.is_rows_event());
+    assert!(Event::query("boxercrab", "-- comment\n  DROP table t").is_ddl());
+    assert!(!Event::query("boxercrab", "BEGIN").is_ddl());
+    assert!(!Event::query("boxercrab", "INSERT INTO created VALUES (1)").is_ddl());
+    assert!(!Event::query("boxercrab", "/* CREATE */").is_ddl());
+
+    let input = include_bytes!("events/30_write_rows_v2/log.bin");
+    let (_, output) = Event::from_bytes(input).unwrap();
+    let rows = output.get(10).unwrap();
+    assert!(rows.is_rows_event());
+    assert!(!rows.is_ddl());
+    assert!(!Event::xid(1).is_rows_event());
+}
+
+#[test]
+fn test_invalid_utf8_schema() {
+    let mut input = Event::query("ab", "SELECT 1").to_bytes().unwrap();
+    // schema follows header and 13 bytes post header
+    input[19 + 13] = 0xff;
+    let (_, event) = Event::parse(&input).unwrap();
+    match event {
+        Query { schema, query, .. } => {
+            assert_eq!(schema, "\u{fffd}b");
+            assert_eq!(query, "SELECT 1");
+        }
+        _ => panic!("should be query"),
+    }
+}
+
+#[test]
+fn test_event_type() {
+    use boxercrab::EventType;
+
+    assert_eq!(EventType::from_u8(0x02), EventType::Query);
+    assert_eq!(EventType::from_u8(0x0f), EventType::FormatDescription);
+    assert_eq!(EventType::from_u8(0x13), EventType::TableMap);
+    assert_eq!(EventType::from_u8(0x1e), EventType::WriteRowsV2);
+    assert_eq!(EventType::from_u8(0x21), EventType::Gtid);
+    assert_eq!(EventType::from_u8(0xa0), EventType::MariaAnnotateRows);
+    assert_eq!(EventType::from_u8(0xfe), EventType::Other(0xfe));
+    for t in 0..=u8::MAX {
+        assert_eq!(EventType::from_u8(t).as_u8(), t);
+    }
+    assert_eq!(EventType::from_name("query"), Some(EventType::Query));
+    assert_eq!(EventType::from_name("TableMap"), Some(EventType::TableMap));
+    assert_eq!(
+        EventType::from_name("WRITEROWSV2"),
+        Some(EventType::WriteRowsV2)
+    );
+    assert_eq!(EventType::from_name("Other"), None);
+
+    let input = include_bytes!("events/16_xid/log.bin");
+    let (_, output) = Event::from_bytes(input).unwrap();
+    assert_eq!(output[0].header().kind(), EventType::FormatDescription);
+    assert_eq!(output[10].header().kind(), EventType::Xid);
+}
+
+#[test]
+fn test_stream_stop() {
+    use boxercrab::{BinlogStream, Error, StreamEvent};
+
+    let input = include_bytes!("events/03_stop/log.bin");
+    let items: Vec<_> = BinlogStream::new(input).collect();
+    assert_eq!(items.len(), 3);
+    match items.last().unwrap() {
+        Ok(StreamEvent::Stop { header }) => assert_eq!(header.event_type, 0x03),
+        _ => panic!("stream should end with stop"),
+    }
+
+    // no stop event, last event is cut off
+    let input = include_bytes!("events/16_xid/log.bin");
+    let truncated = &input[..input.len() - 5];
+    let items: Vec<_> = BinlogStream::new(truncated).collect();
+    assert!(items[..items.len() - 1]
+        .iter()
+        .all(|item| matches!(item, Ok(StreamEvent::Event(_)))));
+    match items.last().unwrap() {
+        Err(Error::Truncated { .. }) => {}
+        _ => panic!("stream should be truncated"),
+    }
+
+    assert_eq!(
+        BinlogStream::new(&[0x00]).next(),
+        Some(Err(Error::BadMagic))
+    );
+}
+
+#[test]
+fn test_int_lenenc() {
+    use boxercrab::utils::{int_lenenc, int_lenenc_len};
+
+    let cases: Vec<(Vec<u8>, usize, u64)> = vec![
+        (vec![0xfa], 1, 0xfa),
+        (vec![0xfc, 0xfb, 0x00], 3, 0xfb),
+        (vec![0xfd, 0x00, 0x00, 0x01], 4, 0x01_0000),
+        (vec![0xfe, 0, 0, 0, 0, 0x01, 0, 0, 0], 9, 0x01_0000_0000),
+    ];
+    for (input, len, num) in cases {
+        let (i, (used, val)) = int_lenenc(&input).unwrap();
+        assert_eq!(i.len(), 0);
+        assert_eq!((used, val), (len, num));
+        assert_eq!(int_lenenc_len(num), len);
+    }
+    assert!(int_lenenc(&[]).is_err());
+    assert!(int_lenenc(&[0xfb]).is_err());
+    assert!(int_lenenc(&[0xff]).is_err());
+    assert!(int_lenenc(&[0xfd, 0x00]).is_err());
+}
+
+#[test]
+fn test_malformed_no_panic() {
+    use boxercrab::TableMapCache;
+
+    // unknown event type, used to hit unreachable!()
+    let input = raw_event(0xfe, &[]);
+    assert!(Event::parse_with_cache(&input, &mut TableMapCache::new()).is_err());
+    // rows event v1 is not supported
+    let input = raw_event(0x17, &[0x00; 10]);
+    assert!(Event::parse_with_cache(&input, &mut TableMapCache::new()).is_err());
+
+    // table map with unknown column type 0x30
+    let mut body = vec![0x01, 0, 0, 0, 0, 0, 0, 0];
+    body.extend_from_slice(&[0x01, b'd', 0x00, 0x01, b't', 0x00]);
+    body.extend_from_slice(&[0x01, 0x30, 0x00, 0x00]);
+    let input = raw_event(0x13, &body);
+    assert!(Event::parse_with_cache(&input, &mut TableMapCache::new()).is_err());
+
+    // rows event with extra_data_len smaller than itself
+    let mut cache = TableMapCache::new();
+    cache.insert(&Event::table_map(
+        1,
+        "d",
+        "t",
+        vec![boxercrab::ColTypes::Long],
+    ));
+    let body = [0x01, 0, 0, 0, 0, 0, 0x00, 0x00, 0x00, 0x00, 0x01, 0x01];
+    let input = raw_event(0x1e, &body);
+    assert!(Event::parse_with_cache(&input, &mut cache).is_err());
+
+    // every truncation of a valid stream is handled
+    let input = include_bytes!("events/30_write_rows_v2/log.bin");
+    for len in 4..input.len() {
+        let mut cache = TableMapCache::new();
+        let mut i = &input[4..len];
+        while let Ok((remain, _)) = Event::parse_with_cache(i, &mut cache) {
+            i = remain;
+        }
+    }
+}
+
+#[test]
+fn test_query_timestamp_micros() {
+    use boxercrab::QueryStatusVar;
+
+    let event = Event::query("boxercrab", "BEGIN");
+    assert_eq!(event.query_timestamp_micros(), None);
+    assert_eq!(Event::xid(1).query_timestamp_micros(), None);
+
+    let mut input = event.to_bytes().unwrap();
+    // append Q_MICROSECONDS(123456) to status vars
+    input[0..4].copy_from_slice(&1_600_000_000u32.to_le_bytes());
+    input[19 + 11..19 + 13].copy_from_slice(&4u16.to_le_bytes());
+    for (idx, b) in [0x0d, 0x40, 0xe2, 0x01].iter().enumerate() {
+        input.insert(19 + 13 + idx, *b);
+    }
+    let size = input.len() as u32;
+    input[9..13].copy_from_slice(&size.to_le_bytes());
+    let (i, event) = Event::parse(&input).unwrap();
+    assert_eq!(i.len(), 0);
+    match &event {
+        Query { status_vars, .. } => {
+            assert_eq!(status_vars, &vec![QueryStatusVar::Q_MICROSECONDS(123456)])
+        }
+        _ => panic!("should be query"),
+    }
+    assert_eq!(
+        event.query_timestamp_micros(),
+        Some(1_600_000_000 * 1_000_000 + 123456)
+    );
+    // encoded back with 3 bytes
+    assert_eq!(
+        &event.to_bytes().unwrap()[..size as usize - 4],
+        &input[..size as usize - 4]
+    );
+}
+
+#[test]
+fn test_take_u48_le() {
+    use boxercrab::utils::take_u48_le;
+
+    let (i, v) = take_u48_le(&[0x6e, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff]).unwrap();
+    assert_eq!(i, &[0xff]);
+    assert_eq!(v, 110);
+    let (_, v) = take_u48_le(&[0x01, 0x02, 0x03, 0x04, 0x05, 0x06]).unwrap();
+    assert_eq!(v, 0x0605_0403_0201);
+    assert!(take_u48_le(&[0x01; 5]).is_err());
+}
+
+#[test]
+fn test_table_index() {
+    use boxercrab::analyze::TableIndex;
+
+    let mut input = vec![0xfe, b'b', b'i', b'n'];
+    let mut offsets = vec![];
+    for event in vec![
+        raw_table_map(1, "t1"),
+        raw_write_rows(1, 1),
+        raw_table_map(2, "t2"),
+        raw_write_rows(2, 2),
+        raw_table_map(1, "t1"),
+        raw_write_rows(1, 3),
+        Event::xid(1).to_bytes().unwrap(),
+    ] {
+        offsets.push(input.len() as u64);
+        input.extend(event);
+    }
+    let index = TableIndex::build(&input).unwrap();
+    assert_eq!(index.positions("d.t1").unwrap(), &[offsets[1], offsets[5]]);
+    assert_eq!(index.positions("d.t2").unwrap(), &[offsets[3]]);
+    assert!(index.positions("d.t3").is_none());
+    assert_eq!(index.into_inner().len(), 2);
+}
+
+#[test]
+fn test_parse_checked() {
+    use boxercrab::Error;
+
+    let input = Event::xid(1).to_bytes().unwrap();
+    let (i, event, consumed) = Event::parse_checked(&input).unwrap();
+    assert_eq!(i.len(), 0);
+    assert_eq!(consumed, 31);
+    assert_eq!(event, Event::parse(&input).unwrap().1);
+
+    // event_size claims 4 more bytes than xid parser consumes
+    let mut under = input.clone();
+    under.extend_from_slice(&[0x00; 4]);
+    under[9..13].copy_from_slice(&35u32.to_le_bytes());
+    assert_eq!(
+        Event::parse_checked(&under),
+        Err(Error::SizeMismatch {
+            expected: 35,
+            actual: 31
+        })
+    );
+    assert!(Event::parse_checked(&input[..20]).is_err());
+}
+
+#[test]
+fn test_read_int() {
+    use boxercrab::{read_int, IntValue};
+
+    let cases: Vec<(&[u8], bool, IntValue)> = vec![
+        (&[0xff], true, Signed(-1)),
+        (&[0xff], false, Unsigned(0xff)),
+        (&[0x7f], true, Signed(127)),
+        (&[0x00, 0x80], true, Signed(-32768)),
+        (&[0x00, 0x80], false, Unsigned(32768)),
+        (&[0x40, 0xe2, 0x01], true, Signed(123456)),
+        (&[0xff, 0xff, 0xff], true, Signed(-1)),
+        (&[0xff, 0xff, 0xff], false, Unsigned(0xff_ffff)),
+        (&[0x00, 0x00, 0x00, 0x80], true, Signed(i32::MIN as i64)),
+        (&[0x00, 0x00, 0x00, 0x80], false, Unsigned(0x8000_0000)),
+        (&[0xff; 8], true, Signed(-1)),
+        (&[0xff; 8], false, Unsigned(u64::MAX)),
+    ];
+    for (input, signed, expected) in cases {
+        let (remain, v) = read_int(input, input.len() as u8, signed).unwrap();
+        assert_eq!(remain.len(), 0);
+        assert_eq!(v, expected, "{:?} signed: {}", input, signed);
+    }
+    assert!(read_int(&[0x01, 0x02], 3, true).is_err());
+    assert!(read_int(&[0x01], 0, false).is_err());
+}
+
+#[test]
+fn test_as_statement() {
+    use boxercrab::{Statement, StatementKind};
+
+    let input = Event::query("boxercrab", "BEGIN").to_bytes().unwrap();
+    let (_, begin) = Event::parse(&input).unwrap();
+    assert_eq!(
+        begin.as_statement(),
+        Some(Statement {
+            schema: "boxercrab".to_string(),
+            sql: "BEGIN".to_string(),
+            kind: StatementKind::Begin,
+        })
+    );
+
+    let sql = "/* app */ CREATE TABLE t (id int)";
+    let create = Event::query("boxercrab", sql).as_statement().unwrap();
+    assert_eq!(create.schema, "boxercrab");
+    assert_eq!(create.sql, sql);
+    assert_eq!(create.kind, StatementKind::Ddl);
+
+    assert_eq!(
+        StatementKind::classify("start  transaction"),
+        StatementKind::Begin
+    );
+    assert_eq!(StatementKind::classify("COMMIT"), StatementKind::Commit);
+    assert_eq!(StatementKind::classify("ROLLBACK"), StatementKind::Rollback);
+    assert_eq!(
+        StatementKind::classify("INSERT INTO t VALUES (1)"),
+        StatementKind::Dml
+    );
+    assert_eq!(Event::xid(1).as_statement(), None);
+}
+
+#[test]
+fn test_binary_block_data() {
+    let block = [0xff, 0xfe, 0x00, 0x80, b'\n'];
+    let mut body = 7u32.to_le_bytes().to_vec();
+    body.extend_from_slice(&block);
+    for &event_type in &[0x08, 0x09, 0x11] {
+        let input = raw_event(event_type, &body);
+        let (remain, event) = Event::parse(&input).unwrap();
+        assert_eq!(remain.len(), 0);
+        match event {
+            CreateFile {
+                file_id,
+                block_data,
+                ..
+            }
+            | AppendBlock {
+                file_id,
+                block_data,
+                ..
+            }
+            | BeginLoadQuery {
+                file_id,
+                block_data,
+                ..
+            } => {
+                assert_eq!(file_id, 7);
+                assert_eq!(block_data, block);
+            }
+            _ => panic!("should be file block event"),
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_async_stream() {
+    use boxercrab::{AsyncBinlogStream, BinlogStream, Error};
+    use futures::StreamExt;
+    use std::{
+        pin::Pin,
+        task::{Context, Poll},
+    };
+
+    /// yield one byte per read to exercise partial frames
+    struct OneByte<'a>(&'a [u8]);
+
+    impl<'a> tokio::io::AsyncRead for OneByte<'a> {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<std::io::Result<usize>> {
+            if self.0.is_empty() || buf.is_empty() {
+                return Poll::Ready(Ok(0));
+            }
+            buf[0] = self.0[0];
+            self.0 = &self.0[1..];
+            Poll::Ready(Ok(1))
+        }
+    }
+
+    for input in &[
+        &include_bytes!("events/03_stop/log.bin")[..],
+        &include_bytes!("events/30_write_rows_v2/log.bin")[..],
+    ] {
+        let expected: Vec<_> = BinlogStream::new(input).collect();
+        let items: Vec<_> = AsyncBinlogStream::new(*input).collect().await;
+        assert_eq!(items, expected);
+        let items: Vec<_> = AsyncBinlogStream::new(OneByte(input)).collect().await;
+        assert_eq!(items, expected);
+    }
+
+    let input = include_bytes!("events/16_xid/log.bin");
+    let truncated = &input[..input.len() - 5];
+    let items: Vec<_> = AsyncBinlogStream::new(truncated).collect().await;
+    assert_eq!(items, BinlogStream::new(truncated).collect::<Vec<_>>());
+    assert!(matches!(items.last(), Some(Err(Error::Truncated { .. }))));
+
+    let items: Vec<_> = AsyncBinlogStream::new(&[0x00][..]).collect().await;
+    assert_eq!(items, vec![Err(Error::BadMagic)]);
+}
+
+#[test]
+fn test_collect_stats() {
+    use boxercrab::{analyze::collect_stats, EventType};
+
+    let input = include_bytes!("events/02_query/log.bin");
+    let stats = collect_stats(input).unwrap();
+    let query = stats.get(EventType::Query).unwrap();
+    assert_eq!(query.count, 2);
+    assert_eq!(query.bytes, 138 + 333);
+    assert_eq!(query.min_size, 138);
+    assert_eq!(query.max_size, 333);
+    assert_eq!(query.avg_size(), 235);
+    assert!(stats.get(EventType::WriteRowsV2).is_none());
+
+    let total = stats.total();
+    assert_eq!(total.bytes as usize, input.len() - 4);
+    assert_eq!(total.count, stats.iter().map(|(_, s)| s.count).sum::<u64>());
+    let table = stats.to_string();
+    assert!(table.lines().any(|l| l.starts_with("Query")));
+    assert!(table.lines().last().unwrap().starts_with("total"));
+}
+
+#[cfg(feature = "tracing")]
+#[test]
+fn test_tracing_spans() {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+    use tracing::{span, Metadata, Subscriber};
+
+    #[derive(Default)]
+    struct Counter {
+        spans: AtomicUsize,
+        events: AtomicUsize,
+    }
+
+    struct CountSubscriber(Arc<Counter>);
+
+    impl Subscriber for CountSubscriber {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+        fn new_span(&self, _: &span::Attributes<'_>) -> span::Id {
+            span::Id::from_u64(self.0.spans.fetch_add(1, Ordering::SeqCst) as u64 + 1)
+        }
+        fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+        fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+        fn event(&self, _: &tracing::Event<'_>) {
+            self.0.events.fetch_add(1, Ordering::SeqCst);
+        }
+        fn enter(&self, _: &span::Id) {}
+        fn exit(&self, _: &span::Id) {}
+    }
+
+    let counter = Arc::new(Counter::default());
+    let input = include_bytes!("events/30_write_rows_v2/log.bin");
+    let output = tracing::subscriber::with_default(CountSubscriber(counter.clone()), || {
+        Event::from_bytes(input).unwrap().1
+    });
+    // one span per event plus one for rows of write rows event
+    assert!(counter.spans.load(Ordering::SeqCst) > output.len());
+}
+
+#[test]
+fn test_session_context() {
+    use boxercrab::SessionContext;
+
+    let mut body = vec![0x02];
+    body.extend_from_slice(&5u64.to_le_bytes());
+    let input = raw_event(0x05, &body);
+    let (_, intvar) = Event::parse(&input).unwrap();
+    let mut body = 1u64.to_le_bytes().to_vec();
+    body.extend_from_slice(&2u64.to_le_bytes());
+    let input = raw_event(0x0d, &body);
+    let (_, rand) = Event::parse(&input).unwrap();
+
+    let mut ctx = SessionContext::new();
+    ctx.apply(&intvar);
+    ctx.apply(&rand);
+    ctx.apply(&Event::query("db", "INSERT INTO t VALUES (NULL, RAND())"));
+    assert_eq!(ctx.insert_id(), Some(5));
+    assert_eq!(ctx.last_insert_id(), None);
+    assert_eq!(ctx.rand_seeds(), Some((1, 2)));
+    // context belongs to previous statement only
+    ctx.apply(&Event::query("db", "INSERT INTO t VALUES (NULL)"));
+    assert!(ctx.is_empty());
+
+    let input = include_bytes!("events/14_user_var/log.bin");
+    let (_, output) = Event::from_bytes(input).unwrap();
+    let mut ctx = SessionContext::new();
+    for event in output.iter().take(10) {
+        ctx.apply(event);
+    }
+    let var = ctx.user_var("val_s").unwrap();
+    assert_eq!(var.value, Some(b"test blog".to_vec()));
+}
+
+#[test]
+fn test_stream_kind() {
+    use boxercrab::{BinlogStream, StreamKind};
+
+    let input = include_bytes!("events/16_xid/log.bin");
+    let mut stream = BinlogStream::new(input);
+    stream.by_ref().for_each(|item| assert!(item.is_ok()));
+    assert_eq!(stream.kind(), StreamKind::Binlog);
+    let (_, output) = Event::from_bytes(input).unwrap();
+    assert_eq!(StreamKind::detect(&output), StreamKind::Binlog);
+
+    // set relay_log flag of FormatDesc event, flags start at offset 17 of header
+    let mut relay = input.to_vec();
+    relay[4 + 17] |= 1 << 6;
+    let mut stream = BinlogStream::new(&relay);
+    assert!(stream.next().unwrap().is_ok());
+    assert_eq!(stream.kind(), StreamKind::RelayLog);
+    let (_, output) = Event::from_bytes(&relay).unwrap();
+    assert!(output[0].header().flags.relay_log());
+    assert_eq!(StreamKind::detect(&output), StreamKind::RelayLog);
+
+    // FormatDesc of source follows the one of replica
+    let (_, output) = Event::from_bytes(input).unwrap();
+    let events = vec![output[0].clone(), output[0].clone()];
+    assert_eq!(StreamKind::detect(&events), StreamKind::RelayLog);
+}
+
+#[test]
+fn test_to_sql() {
+    use boxercrab::{sql::to_sql, ColTypes, TableMapCache};
+
+    let table_map = Event::table_map(1, "db", "t", vec![ColTypes::Long, ColTypes::VarChar(20)]);
+    let table = table_map.table_schema().unwrap();
+    let mut cache = TableMapCache::new();
+    cache.insert(&table_map);
+    let mut body = vec![1, 0, 0, 0, 0, 0, 1, 0, 2, 0, 2, 0b11];
+    // (1, 'it''s')
+    body.extend_from_slice(&[0, 1, 0, 0, 0, 4, b'i', b't', b'\'', b's']);
+    // (-2, NULL)
+    body.extend_from_slice(&[0b10, 0xfe, 0xff, 0xff, 0xff]);
+    let input = raw_event(0x1e, &body);
+    let (_, event) = Event::parse_with_cache(&input, &mut cache).unwrap();
+    assert_eq!(
+        to_sql(&event, &table),
+        vec![
+            "INSERT INTO `db`.`t` VALUES (1, 'it\\'s')",
+            "INSERT INTO `db`.`t` VALUES (-2, NULL)",
+        ]
+    );
+
+    let input = raw_event(0x20, &body);
+    let (_, event) = Event::parse_with_cache(&input, &mut cache).unwrap();
+    assert_eq!(
+        to_sql(&event, &table),
+        vec![
+            "DELETE FROM `db`.`t` WHERE @1=1 AND @2='it\\'s'",
+            "DELETE FROM `db`.`t` WHERE @1=-2 AND @2 IS NULL",
+        ]
+    );
+
+    let input = include_bytes!("events/31_update_rows_v2/log.bin");
+    let (_, output) = Event::from_bytes(input).unwrap();
+    let table = output.get(4).unwrap().table_schema().unwrap();
+    let sql = to_sql(output.get(5).unwrap(), &table);
+    assert_eq!(sql.len(), 1);
+    assert!(sql[0].starts_with("UPDATE `default`.`boxercrab` SET @1=1, @2='xd'"));
+    assert!(sql[0].contains(" WHERE @1=1 AND @2='abc'"));
+    assert!(to_sql(&Event::xid(1), &table).is_empty());
+}
+
+#[test]
+fn test_undersized_event_size() {
+    use boxercrab::event_slices;
+
+    let mut body = [0u8; 64];
+    // extra_data_len and column_count of rows events
+    body[8] = 2;
+    body[10] = 1;
+    // query, rotate, create file, format desc, execute load query, table map,
+    // pre ga rows, write/update/delete rows v2 and previous gtids
+    for &event_type in &[0x02, 0x04, 0x08, 0x0f, 0x12, 0x14, 0x1e, 0x1f, 0x20, 0x23] {
+        let mut input = raw_event(event_type, &body);
+        input[9..13].copy_from_slice(&20u32.to_le_bytes());
+        match Event::parse(&input) {
+            Err(nom::Err::Error((_, kind))) => {
+                assert_eq!(kind, nom::error::ErrorKind::LengthValue, "{:x}", event_type)
+            }
+            other => panic!("{:x} should fail: {:?}", event_type, other),
+        }
+    }
+    let mut input = raw_event(0x10, &[0; 8]);
+    input[9..13].copy_from_slice(&10u32.to_le_bytes());
+    assert!(event_slices(&input).is_err());
+}
+
+#[cfg(feature = "mariadb")]
+#[test]
+fn test_maria_annotate_rows() {
+    let sql = "INSERT INTO t VALUES (1, 'abc')";
+    let input = raw_event(0xa0, sql.as_bytes());
+    let (remain, event) = Event::parse(&input).unwrap();
+    assert_eq!(remain.len(), 0);
+    match event {
+        MariaAnnotateRows { query_text, .. } => assert_eq!(query_text, sql),
+        _ => panic!("should be annotate rows"),
+    }
+}
+
+#[cfg(feature = "mariadb")]
+#[test]
+fn test_maria_gtid() {
+    // seq_no 100, domain_id 0, flags FL_STANDALONE, 6 bytes padding
+    let mut body = 100u64.to_le_bytes().to_vec();
+    body.extend_from_slice(&[0, 0, 0, 0, 0x01]);
+    body.extend_from_slice(&[0; 6]);
+    let input = raw_event(0xa2, &body);
+    let (remain, event) = Event::parse(&input).unwrap();
+    assert_eq!(remain.len(), 0);
+    match &event {
+        MariaGtid {
+            sequence,
+            domain_id,
+            server_id,
+            flags,
+            commit_id,
+            ..
+        } => {
+            assert_eq!(*sequence, 100);
+            assert_eq!(*domain_id, 0);
+            assert_eq!(*server_id, 1);
+            assert_eq!(*flags, 0x01);
+            assert_eq!(*commit_id, None);
+        }
+        _ => panic!("should be mariadb gtid"),
+    }
+    assert_eq!(event.maria_gtid().unwrap().to_string(), "0-1-100");
+
+    // FL_GROUP_COMMIT_ID with commit id
+    let mut body = 7u64.to_le_bytes().to_vec();
+    body.extend_from_slice(&[2, 0, 0, 0, 0x02]);
+    body.extend_from_slice(&42u64.to_le_bytes());
+    let input = raw_event(0xa2, &body);
+    let (remain, event) = Event::parse(&input).unwrap();
+    assert_eq!(remain.len(), 0);
+    match event {
+        MariaGtid { commit_id, .. } => assert_eq!(commit_id, Some(42)),
+        _ => panic!("should be mariadb gtid"),
+    }
+}
+
+#[test]
+fn test_rows_column_count_mismatch() {
+    use boxercrab::{ColTypes, TableMapCache};
+
+    let mut cache = TableMapCache::new();
+    cache.insert(&Event::table_map(
+        1,
+        "db",
+        "t",
+        vec![ColTypes::Long, ColTypes::Long],
+    ));
+    // (1, 2) with column_count 2
+    let mut body = vec![1, 0, 0, 0, 0, 0, 1, 0, 2, 0, 2, 0b11];
+    body.extend_from_slice(&[0, 1, 0, 0, 0, 2, 0, 0, 0]);
+    let input = raw_event(0x1e, &body);
+    assert!(Event::parse_with_cache(&input, &mut cache).is_ok());
+
+    // rows event claims 3 columns
+    body[10] = 3;
+    body[11] = 0b111;
+    let input = raw_event(0x1e, &body);
+    match Event::parse_with_cache(&input, &mut cache) {
+        Err(nom::Err::Failure((_, kind))) => assert_eq!(kind, nom::error::ErrorKind::Verify),
+        other => panic!("column count mismatch should fail: {:?}", other),
+    }
+}
+
+#[test]
+fn test_decoded_stream() {
+    use boxercrab::{BinlogStream, DecodedEvent};
+
+    let input = include_bytes!("events/30_write_rows_v2/log.bin");
+    let items: Vec<_> = BinlogStream::new(input)
+        .decoded()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    let rows: Vec<_> = items
+        .iter()
+        .filter_map(|item| match item {
+            DecodedEvent::Rows { event, table } => Some((event, table)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(rows.len(), 1);
+    let (event, table) = rows[0];
+    assert_eq!(table.table_name, "boxercrab");
+    assert_eq!(table.columns_type.len(), 2);
+    match event {
+        WriteRowsV2 { table_id, .. } => assert_eq!(*table_id, 111),
+        _ => panic!("should be write_rows_v2"),
+    }
+    assert!(items
+        .iter()
+        .any(|item| matches!(item, DecodedEvent::Event(TableMap { .. }))));
+}
+
+#[test]
+fn test_partial_json_update() {
+    use boxercrab::{ColTypes, JsonValue, TableMapCache};
+
+    let mut cache = TableMapCache::new();
+    cache.insert(&Event::table_map(
+        1,
+        "db",
+        "t",
+        vec![ColTypes::Long, ColTypes::Json(4)],
+    ));
+    // {"a": 1}
+    let doc = [0x00, 1, 0, 12, 0, 11, 0, 1, 0, 0x05, 1, 0, b'a'];
+    let mut body = vec![1, 0, 0, 0, 0, 0, 1, 0, 2, 0, 2, 0b11, 0b11];
+    body.extend_from_slice(&[0, 1, 0, 0, 0, doc.len() as u8, 0, 0, 0]);
+    body.extend_from_slice(&doc);
+    // value_options PARTIAL_JSON, partial bit of the only JSON column
+    body.extend_from_slice(&[1, 0b1]);
+    // JSON_SET(j, '$.a', 2, '$.b', 'x'): replace $.a, insert $.b
+    body.extend_from_slice(&[0, 1, 0, 0, 0, 18, 0, 0, 0]);
+    body.extend_from_slice(&[0, 3, b'$', b'.', b'a', 3, 0x05, 2, 0]);
+    body.extend_from_slice(&[1, 3, b'$', b'.', b'b', 3, 0x0c, 1, b'x']);
+    let input = raw_event(0x27, &body);
+    let (_, event) = Event::parse_with_cache(&input, &mut cache).unwrap();
+    match event {
+        UpdateRowsV2 { rows, .. } => {
+            assert_eq!(rows.len(), 2);
+            assert_eq!(
+                rows[0][1],
+                Json(JsonValue::Object(vec![("a".into(), JsonValue::Int(1))]))
+            );
+            match &rows[1][1] {
+                Json(doc) => assert_eq!(doc.to_string(), r#"{"a": 2, "b": "x"}"#),
+                other => panic!("should be json: {:?}", other),
+            }
+        }
+        _ => panic!("should be update rows"),
+    }
+}
+
+#[test]
+fn test_parse_exact() {
+    use boxercrab::Error;
+
+    let input = Event::xid(7).to_bytes().unwrap();
+    match Event::parse_exact(&input).unwrap() {
+        XID { xid, .. } => assert_eq!(xid, 7),
+        other => panic!("should be xid: {:?}", other),
+    }
+
+    let mut extra = input.clone();
+    extra.extend_from_slice(&[0x01, 0x02]);
+    assert_eq!(
+        Event::parse_exact(&extra),
+        Err(Error::TrailingBytes { len: 2 })
+    );
+    assert_eq!(
+        Event::parse_exact(&input[..input.len() - 1]),
+        Err(Error::Truncated { offset: 0 })
+    );
+}
+
+#[test]
+fn test_crc32() {
+    use boxercrab::{event_slices, utils::crc32};
+
+    assert_eq!(crc32(b""), 0);
+    assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+
+    // checksum of event covers header and body
+    let input = include_bytes!("events/16_xid/log.bin");
+    let mut i = &input[4..];
+    while !i.is_empty() {
+        let (remain, (header, _, checksum)) = event_slices(i).unwrap();
+        if header.event_type == 0x10 {
+            let len = header.event_size as usize - 4;
+            assert_eq!(crc32(&i[..len]), checksum);
+        }
+        i = remain;
+    }
+}
+
+#[test]
+fn test_datetime2() {
+    use boxercrab::ColTypes;
+
+    // 2020-07-31 12:34:56
+    let input = [0x99, 0xa6, 0xfe, 0xc8, 0xb8];
+    let (i, (len, value)) = ColTypes::DateTime2(0).parse(&input).unwrap();
+    assert!(i.is_empty());
+    assert_eq!(len, 5);
+    let expected = DateTime {
+        year: 2020,
+        month: 7,
+        day: 31,
+        hour: 12,
+        minute: 34,
+        second: 56,
+        micros: 0,
+    };
+    assert_eq!(value, expected);
+
+    // fsp 6 stores microseconds in 3 bytes
+    let input = [0x99, 0xa6, 0xfe, 0xc8, 0xb8, 0x01, 0xe2, 0x40];
+    let (i, (len, value)) = ColTypes::DateTime2(6).parse(&input).unwrap();
+    assert!(i.is_empty());
+    assert_eq!(len, 8);
+    assert_eq!(
+        value,
+        DateTime {
+            year: 2020,
+            month: 7,
+            day: 31,
+            hour: 12,
+            minute: 34,
+            second: 56,
+            micros: 123456,
+        }
+    );
+    assert!(value.matches_type(&ColTypes::DateTime2(6)));
+}
+
+#[test]
+fn test_time2() {
+    use boxercrab::ColTypes;
+
+    // 01:02:03 and -01:02:03
+    for &(input, negative) in [([0x80, 0x10, 0x83], false), ([0x7f, 0xef, 0x7d], true)].iter() {
+        let (i, (len, value)) = ColTypes::Time2(0).parse(&input).unwrap();
+        assert!(i.is_empty());
+        assert_eq!(len, 3);
+        assert_eq!(
+            value,
+            Time {
+                negative,
+                hour: 1,
+                minute: 2,
+                second: 3,
+                micros: 0
+            }
+        );
+    }
+
+    // -12:34:56.000789, fsp 6 stores microseconds in 3 bytes
+    let input = [0x7f, 0x37, 0x47, 0xff, 0xfc, 0xeb];
+    let (i, (len, value)) = ColTypes::Time2(6).parse(&input).unwrap();
+    assert!(i.is_empty());
+    assert_eq!(len, 6);
+    assert_eq!(
+        value,
+        Time {
+            negative: true,
+            hour: 12,
+            minute: 34,
+            second: 56,
+            micros: 789
+        }
+    );
+    assert!(value.matches_type(&ColTypes::Time2(6)));
+
+    // -00:00:01.50, fsp 2 stores complement of fraction and next second
+    let (_, (len, value)) = ColTypes::Time2(2).parse(&[0x7f, 0xff, 0xfe, 0xce]).unwrap();
+    assert_eq!(len, 4);
+    assert_eq!(
+        value,
+        Time {
+            negative: true,
+            hour: 0,
+            minute: 0,
+            second: 1,
+            micros: 500000
+        }
+    );
+
+    // meta of TIME2 column is kept as TIME2
+    let (_, (_, col)) = ColTypes::Time2(0).parse_def(&[3]).unwrap();
+    assert_eq!(col, ColTypes::Time2(3));
+}
+
+#[test]
+fn test_timestamp2() {
+    use boxercrab::ColTypes;
+
+    // 2020-01-02 03:04:05.123456 UTC
+    let input = [0x5e, 0x0d, 0x5d, 0xa5, 0x01, 0xe2, 0x40];
+    let (i, (len, value)) = ColTypes::Timestamp2(6).parse(&input).unwrap();
+    assert_eq!(i.len(), 0);
+    assert_eq!(len, 7);
+    assert_eq!(
+        value,
+        Timestamp {
+            seconds: 1577934245,
+            micros: 123456
+        }
+    );
+    #[cfg(feature = "chrono")]
+    {
+        let utc = value.as_utc().unwrap();
+        assert_eq!(utc.timestamp(), 1577934245);
+        assert_eq!(utc.timestamp_subsec_micros(), 123456);
+    }
+
+    // fsp 3 stores 1/10000 second in 2 bytes
+    let (_, (len, value)) = ColTypes::Timestamp2(3)
+        .parse(&[0x5e, 0x0d, 0x5d, 0xa5, 0x04, 0xce])
+        .unwrap();
+    assert_eq!(len, 6);
+    assert_eq!(
+        value,
+        Timestamp {
+            seconds: 1577934245,
+            micros: 123000
+        }
+    );
+}
+
+#[test]
+fn test_table_filter() {
+    use boxercrab::{BinlogStream, StreamEvent};
+
+    let mut input = vec![0xfe, b'b', b'i', b'n'];
+    for event in vec![
+        raw_table_map(1, "t1"),
+        raw_write_rows(1, 1),
+        raw_table_map(2, "t2"),
+        // rows of unlisted table are skipped before decoding
+        raw_write_rows(2, 0xff)[..30].to_vec(),
+        Event::xid(1).to_bytes().unwrap(),
+    ] {
+        input.extend(event);
+    }
+    // event_size of truncated rows event
+    let pos = input.len() - 31 - 30;
+    input[pos + 9..pos + 13].copy_from_slice(&30u32.to_le_bytes());
+
+    let events: Vec<_> = BinlogStream::new(&input)
+        .with_table_filter(&[("d", "t1")])
+        .map(|e| match e.unwrap() {
+            StreamEvent::Event(e) => e,
+            _ => panic!("should be event"),
+        })
+        .collect();
+    assert_eq!(events.len(), 3);
+    assert!(matches!(events[0], TableMap { ref table_name, .. } if table_name == "t1"));
+    match &events[1] {
+        WriteRowsV2 { rows, .. } => assert_eq!(rows, &vec![vec![Long(Signed(1))]]),
+        other => panic!("should be write rows: {:?}", other),
+    }
+    assert!(matches!(events[2], XID { .. }));
+}
+
+#[test]
+fn test_next_position() {
+    let input = include_bytes!("events/03_stop/log.bin");
+    let (_, output) = Event::from_bytes(input).unwrap();
+    let stop = output.last().unwrap();
+    assert!(matches!(stop, Stop { .. }));
+    assert_eq!(stop.next_position(), stop.header().log_pos);
+    assert_eq!(stop.next_position() as usize, input.len());
+
+    let input = include_bytes!("events/16_xid/log.bin");
+    let (_, output) = Event::from_bytes(input).unwrap();
+    let xid = output.get(10).unwrap();
+    assert!(matches!(xid, XID { .. }));
+    assert_eq!(xid.next_position(), xid.header().log_pos);
+    let end: usize = output[..=10]
+        .iter()
+        .map(|e| e.header().event_size as usize)
+        .sum();
+    assert_eq!(xid.next_position() as usize, end + 4);
+}
+
+#[test]
+fn test_gtid_commit_info() {
+    use boxercrab::{ParseContext, TableMapCache};
+
+    let mut body = vec![0x00];
+    body.extend_from_slice(&[0xab; 16]);
+    body.extend_from_slice(&7u64.to_le_bytes());
+    body.push(0x02);
+    body.extend_from_slice(&1i64.to_le_bytes());
+    body.extend_from_slice(&2i64.to_le_bytes());
+
+    // 5.7 event ends after logical clock
+    let input = raw_event(0x21, &body);
+    match Event::parse(&input).unwrap() {
+        (
+            i,
+            Gtid {
+                original_commit_timestamp,
+                immediate_commit_timestamp,
+                transaction_length,
+                sequence_number,
+                ..
+            },
+        ) => {
+            assert!(i.is_empty());
+            assert_eq!(sequence_number, 2);
+            assert_eq!(original_commit_timestamp, None);
+            assert_eq!(immediate_commit_timestamp, None);
+            assert_eq!(transaction_length, None);
+        }
+        other => panic!("should be gtid: {:?}", other),
+    }
+
+    // immediate timestamp with original flag, original timestamp, transaction length
+    // and server version
+    let immediate: u64 = 1_600_000_000_123_456;
+    let original: u64 = 1_600_000_000_000_001;
+    body.extend_from_slice(&(immediate | 1 << 55).to_le_bytes()[..7]);
+    body.extend_from_slice(&original.to_le_bytes()[..7]);
+    body.extend_from_slice(&[0xfc, 0x2c, 0x01]);
+    body.extend_from_slice(&80020u32.to_le_bytes());
+    let input = raw_event(0x21, &body);
+    match Event::parse(&input).unwrap() {
+        (
+            i,
+            Gtid {
+                original_commit_timestamp,
+                immediate_commit_timestamp,
+                transaction_length,
+                ..
+            },
+        ) => {
+            assert!(i.is_empty());
+            assert_eq!(original_commit_timestamp, Some(original));
+            assert_eq!(immediate_commit_timestamp, Some(immediate));
+            assert_eq!(transaction_length, Some(300));
+        }
+        other => panic!("should be gtid: {:?}", other),
+    }
+
+    // commit info is not read for servers before 8.0.1
+    let commit_info = |version| {
+        let ctx = ParseContext {
+            server_version: Some(version),
+            ..Default::default()
+        };
+        match Event::parse_with_context(&input, &ctx, &mut TableMapCache::new()).unwrap() {
+            (
+                i,
+                Gtid {
+                    original_commit_timestamp,
+                    transaction_length,
+                    ..
+                },
+            ) => {
+                assert!(i.is_empty());
+                (original_commit_timestamp, transaction_length)
+            }
+            other => panic!("should be gtid: {:?}", other),
+        }
+    };
+    assert_eq!(commit_info((5, 7, 30)), (None, None));
+    assert_eq!(commit_info((8, 0, 21)), (Some(original), Some(300)));
+}
+
+#[test]
+fn test_stream_rotate() {
+    use boxercrab::{BinlogStream, StreamEvent};
+
+    let input = include_bytes!("events/04_rotate/log.bin");
+    let items: Vec<_> = BinlogStream::new(input).collect::<Result<_, _>>().unwrap();
+    match items.last().unwrap() {
+        StreamEvent::RotateTo {
+            header,
+            next_binlog,
+            position,
+        } => {
+            assert_eq!(header.event_type, 0x04);
+            assert_eq!(next_binlog, "mysql_bin.000002");
+            assert_eq!(*position, 4);
+        }
+        other => panic!("stream should end with rotate: {:?}", other),
+    }
+}
+
+#[test]
+fn test_parse_context_checksum() {
+    use boxercrab::{BinlogStream, ChecksumAlg, ParseContext, StreamEvent, TableMapCache};
+
+    // events of a checksum-on stream
+    let xid = Event::xid(9).to_bytes().unwrap();
+    let mut cache = TableMapCache::new();
+    let ctx = ParseContext::default();
+    let (i, event) = Event::parse_with_context(&xid, &ctx, &mut cache).unwrap();
+    assert!(i.is_empty());
+    assert_eq!(event, Event::parse(&xid).unwrap().1);
+
+    // same events without checksum, FormatDesc turns checksum off
+    let fixture = include_bytes!("events/16_xid/log.bin");
+    let fde_size =
+        u32::from_le_bytes([fixture[13], fixture[14], fixture[15], fixture[16]]) as usize;
+    let mut input = fixture[..4 + fde_size].to_vec();
+    input[4 + fde_size - 5] = 0x00;
+    let mut events = vec![];
+    for event in vec![xid, Event::query("db", "BEGIN").to_bytes().unwrap()] {
+        let mut event = event[..event.len() - 4].to_vec();
+        let size = event.len() as u32;
+        event[9..13].copy_from_slice(&size.to_le_bytes());
+        events.push(event.clone());
+        input.extend(event);
+    }
+
+    let items: Vec<_> = BinlogStream::new(&input)
+        .map(|e| match e.unwrap() {
+            StreamEvent::Event(e) => e,
+            other => panic!("should be event: {:?}", other),
+        })
+        .collect();
+    assert_eq!(items.len(), 3);
+    assert_eq!(items[0].checksum_alg(), Some(ChecksumAlg::Off));
+    match &items[1] {
+        XID {
+            header,
+            xid,
+            checksum,
+        } => {
+            assert_eq!(*xid, 9);
+            assert_eq!(*checksum, 0);
+            assert_eq!(header.event_size, 27);
+        }
+        other => panic!("should be xid: {:?}", other),
+    }
+    assert!(matches!(&items[2], Query { query, .. } if query == "BEGIN"));
+
+    let ctx = ParseContext {
+        has_checksum: false,
+        ..Default::default()
+    };
+    let (i, _) = Event::parse_with_context(&events[0], &ctx, &mut cache).unwrap();
+    assert!(i.is_empty());
+}
+
+#[test]
+fn test_validate_size() {
+    use boxercrab::Error;
+
+    let input = Event::xid(1).to_bytes().unwrap();
+    let (i, event) = Event::parse(&input).unwrap();
+    let consumed = input.len() - i.len() - 19;
+    assert_eq!(event.validate_size(consumed), Ok(()));
+
+    // tampered event_size, xid parser still consumes 12 bytes of body
+    let mut tampered = input.clone();
+    tampered.extend_from_slice(&[0x00; 2]);
+    tampered[9..13].copy_from_slice(&(input.len() as u32 + 2).to_le_bytes());
+    let (i, event) = Event::parse(&tampered).unwrap();
+    assert_eq!(
+        event.validate_size(tampered.len() - i.len() - 19),
+        Err(Error::SizeMismatch {
+            expected: 14,
+            actual: 12
+        })
+    );
+}
+
+#[test]
+fn test_text_fields() {
+    let input = Event::query("shop", "DROP TABLE foo").to_bytes().unwrap();
+    let (_, event) = Event::parse(&input).unwrap();
+    let fields = event.text_fields();
+    assert!(fields.contains(&"DROP TABLE foo"));
+    assert!(fields.contains(&"shop"));
+    assert!(Event::xid(1).text_fields().is_empty());
+}
+
+#[test]
+fn test_flag_bits() {
+    use boxercrab::{EmptyFlags, EventFlag, Flags, OptFlags};
+
+    let flag = EventFlag::from_u16(0b10_0010_1001);
+    assert!(flag.in_use && flag.suppress_use && flag.artificial && flag.mts_isolate);
+    assert!(!flag.forced_rotate && !flag.thread_specific && !flag.update_table_map_version);
+    assert!(!flag.relay_log && !flag.ignorable && !flag.no_filter);
+    assert!(EventFlag::from_u16(0x40).relay_log());
+
+    // opt_enclosed is bit 1
+    let opt = OptFlags::from_u8(0b0010);
+    assert!(opt.opt_enclosed && !opt.dump_file && !opt.replace && !opt.ignore);
+    let opt = OptFlags::from_u8(0b1101);
+    assert!(opt.dump_file && !opt.opt_enclosed && opt.replace && opt.ignore);
+
+    let empty = EmptyFlags::from_u8(0b1_0100);
+    assert!(empty.line_term_empty && empty.escape_empty);
+    assert!(!empty.field_term_empty && !empty.enclosed_empty && !empty.line_start_empty);
+
+    // check flags are on unless NO_*_CHECKS bits are set
+    let flags = Flags::from_u16(0b0101);
+    assert!(flags.end_of_stmt && flags.foreign_key_checks && !flags.unique_key_checks);
+    assert!(!flags.has_columns);
+    // COMPLETE_ROWS_F
+    assert!(Flags::from_u16(0b1000).has_columns);
+}
+
+#[test]
+fn test_previous_gtids_set() {
+    let input = include_bytes!("events/33_35_gtid_prev_gtid/log.bin");
+    let (_, output) = Event::from_bytes(input).unwrap();
+    let set = output[1].previous_gtids().unwrap();
+    assert!(set.is_empty());
+    assert_eq!(set.to_string(), "");
+
+    let mut body = 2u64.to_le_bytes().to_vec();
+    body.extend_from_slice(&[
+        0x3e, 0x11, 0xfa, 0x47, 0x71, 0xca, 0x11, 0xe1, 0x9e, 0x33, 0xc8, 0x0a, 0xa9, 0x42, 0x95,
+        0x62,
+    ]);
+    body.extend_from_slice(&2u64.to_le_bytes());
+    for v in [1i64, 6, 7, 8].iter() {
+        body.extend_from_slice(&v.to_le_bytes());
+    }
+    body.extend_from_slice(&[0xab; 16]);
+    body.extend_from_slice(&1u64.to_le_bytes());
+    for v in [1i64, 3].iter() {
+        body.extend_from_slice(&v.to_le_bytes());
+    }
+    let input = raw_event(0x23, &body);
+    let (i, event) = Event::parse(&input).unwrap();
+    assert!(i.is_empty());
+    let set = event.previous_gtids().unwrap();
+    assert!(!set.is_empty());
+    assert_eq!(
+        set.to_string(),
+        "3e11fa47-71ca-11e1-9e33-c80aa9429562:1-5:7,abababab-abab-abab-abab-abababababab:1-2"
+    );
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_value_to_json() {
+    assert_eq!(Long(Signed(-42)).to_json().as_i64(), Some(-42));
+    assert_eq!(
+        LongLong(Unsigned(u64::MAX)).to_json().as_u64(),
+        Some(u64::MAX)
+    );
+    assert_eq!(Double(1.5).to_json().as_f64(), Some(1.5));
+    assert!(Double(f64::NAN).to_json().is_null());
+    assert_eq!(NewDecimal("3.00".into()).to_json().as_str(), Some("3.00"));
+    assert_eq!(Blob(b"ab\xff".to_vec()).to_json().as_str(), Some("YWL/"));
+    assert_eq!(VarChar(b"hi".to_vec()).to_json().as_str(), Some("hi"));
+    assert!(Null.to_json().is_null());
+    let ts = Timestamp {
+        seconds: 951_782_400,
+        micros: 500,
+    };
+    assert_eq!(ts.to_json().as_str(), Some("2000-02-29T00:00:00.000500Z"));
+
+    // DATETIME2 and TIME2 share formatting of DATETIME and TIME
+    let (_, (_, datetime)) = boxercrab::ColTypes::DateTime2(6)
+        .parse(&[0x99, 0xa6, 0xfe, 0xc8, 0xb8, 0x01, 0xe2, 0x40])
+        .unwrap();
+    assert_eq!(
+        datetime.to_json().as_str(),
+        Some("2020-07-31T12:34:56.123456")
+    );
+    let (_, (_, time)) = boxercrab::ColTypes::Time2(0)
+        .parse(&[0x7f, 0xef, 0x7d])
+        .unwrap();
+    assert_eq!(time.to_json().as_str(), Some("-01:02:03"));
+}
+
+#[test]
+#[cfg(feature = "cli")]
+fn test_cli_dump() {
+    use std::process::Command;
+
+    let dump = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_bcrab"))
+            .arg("dump")
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let stdout = std::string::String::from_utf8(output.stdout).unwrap();
+        assert!(stdout.lines().all(|line| line.starts_with('{')));
+        stdout.lines().count()
+    };
+    assert_eq!(dump(&["tests/events/02_query/log.bin"]), 7);
+    assert_eq!(
+        dump(&["tests/events/02_query/log.bin", "--type", "query"]),
+        2
+    );
+    assert_eq!(
+        dump(&[
+            "tests/events/02_query/log.bin",
+            "--type",
+            "gtid",
+            "--type",
+            "Rotate"
+        ]),
+        3
+    );
+
+    for args in &[
+        &["Cargo.toml"][..],
+        &["tests/events/02_query/log.bin", "--type", "nope"],
+    ] {
+        let output = Command::new(env!("CARGO_BIN_EXE_bcrab"))
+            .arg("dump")
+            .args(*args)
+            .output()
+            .unwrap();
+        assert!(!output.status.success());
+    }
+}
+
+/// compare `actual` with `expected`, keys missing in `expected` objects are not compared
+#[cfg(feature = "std")]
+fn json_matches(expected: &serde_json::Value, actual: &serde_json::Value) -> bool {
+    use serde_json::Value;
+
+    match (expected, actual) {
+        (Value::Object(expected), Value::Object(actual)) => expected
+            .iter()
+            .all(|(k, v)| actual.get(k).map_or(false, |a| json_matches(v, a))),
+        (Value::Array(expected), Value::Array(actual)) => {
+            expected.len() == actual.len()
+                && expected.iter().zip(actual).all(|(e, a)| json_matches(e, a))
+        }
+        (expected, actual) => expected == actual,
+    }
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn test_fixtures() {
+    use boxercrab::TableMapCache;
+    use std::fs;
+
+    let mut count = 0;
+    for entry in fs::read_dir("tests/fixtures").unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().map_or(true, |ext| ext != "hex") {
+            continue;
+        }
+        let text = fs::read_to_string(&path).unwrap();
+        let hex: std::string::String = text
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .flat_map(|line| line.split_whitespace())
+            .collect();
+        let input: Vec<u8> = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect();
+        let expected: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(path.with_extension("json")).unwrap())
+                .unwrap();
+
+        let mut cache = TableMapCache::new();
+        let mut events = vec![];
+        let mut remain = input.as_slice();
+        while !remain.is_empty() {
+            let (i, event) = Event::parse_with_cache(remain, &mut cache)
+                .unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+            events.push(serde_json::to_value(&event).unwrap());
+            remain = i;
+        }
+        let actual = serde_json::Value::Array(events);
+        assert!(
+            json_matches(&expected, &actual),
+            "{}: decoded as {}",
+            path.display(),
+            actual
+        );
+        count += 1;
+    }
+    assert!(count > 0);
+}
+
+#[test]
+fn test_decode_rows_validated() {
+    use boxercrab::RowWarning;
+
+    let input = include_bytes!("events/30_write_rows_v2/log.bin");
+    let (_, output) = Event::from_bytes(input).unwrap();
+    let table = output[9].table_schema().unwrap();
+    assert!(output[9].decode_rows_validated(&table).is_none());
+
+    let validated = output[10].decode_rows_validated(&table).unwrap();
+    assert!(validated.is_valid());
+    assert_eq!(validated.rows[0][0].column_type, boxercrab::ColTypes::Long);
+    assert_eq!(validated.rows[0][0].value, Long(Signed(1)));
+
+    // `id` and `title` are NOT NULL, `id` is INT
+    let mut event = output[10].clone();
+    if let WriteRowsV2 { rows, .. } = &mut event {
+        *rows = vec![
+            vec![Long(Signed(2)), Null],
+            vec![VarChar(b"3".to_vec()), VarChar(b"x".to_vec())],
+            vec![Long(Signed(3))],
+        ];
+    }
+    let validated = event.decode_rows_validated(&table).unwrap();
+    assert!(!validated.is_valid());
+    assert_eq!(
+        validated.warnings,
+        vec![
+            RowWarning::UnexpectedNull { row: 0, column: 1 },
+            RowWarning::TypeMismatch { row: 1, column: 0 },
+            RowWarning::ColumnCount {
+                row: 2,
+                expected: 2,
+                actual: 1
+            },
+        ]
+    );
+}
+
+#[test]
+#[cfg(feature = "gzip")]
+fn test_gzip_binlog() {
+    use boxercrab::{parse_binlog_path, read_binlog_path, StreamEvent};
+
+    let plain = parse_binlog_path("tests/events/04_rotate/log.bin").unwrap();
+    let gzipped = parse_binlog_path("tests/events/04_rotate/log.bin.gz").unwrap();
+    assert_eq!(plain, gzipped);
+    assert!(matches!(gzipped.last(), Some(StreamEvent::RotateTo { .. })));
+    assert_eq!(
+        read_binlog_path("tests/events/04_rotate/log.bin.gz").unwrap(),
+        &include_bytes!("events/04_rotate/log.bin")[..]
+    );
+}
+
+#[test]
+fn test_raw_stream() {
+    use boxercrab::{event_slices, BinlogStream, StreamEvent};
+
+    let input = include_bytes!("events/30_write_rows_v2/log.bin");
+    let items: Vec<_> = BinlogStream::new(input)
+        .raw()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    let mut i = &input[4..];
+    for (event, raw) in items.iter() {
+        let (remain, (header, _, _)) = event_slices(i).unwrap();
+        assert_eq!(*raw, &i[..header.event_size as usize]);
+        match event {
+            StreamEvent::Event(event) => assert_eq!(event.header(), &header),
+            StreamEvent::Stop { header: h }
+            | StreamEvent::RotateTo { header: h, .. }
+            | StreamEvent::Heartbeat { header: h, .. } => assert_eq!(h, &header),
+            StreamEvent::Skipped { .. } => panic!("nothing to skip"),
+        }
+        i = remain;
+    }
+    assert!(i.is_empty());
+    let forwarded: Vec<u8> = items
+        .iter()
+        .flat_map(|(_, raw)| raw.iter())
+        .copied()
+        .collect();
+    assert_eq!(forwarded, &input[4..]);
+}
+
+#[test]
+fn test_parse_with_handler() {
+    let mut seen = vec![];
+    let mut handler = |t: u8, body: &[u8]| seen.push((t, body.to_vec()));
+
+    let input = raw_event(0x99, &[1, 2, 3]);
+    let (i, event) = Event::parse_with_handler(&input, &mut handler).unwrap();
+    assert!(i.is_empty());
+    match event {
+        Unknown { header, .. } => assert_eq!(header.event_type, 0x99),
+        other => panic!("should be unknown: {:?}", other),
+    }
+
+    // supported types are parsed as usual
+    let input = Event::xid(3).to_bytes().unwrap();
+    let (_, event) = Event::parse_with_handler(&input, &mut handler).unwrap();
+    assert!(matches!(event, XID { xid: 3, .. }));
+    assert_eq!(seen, vec![(0x99, vec![1, 2, 3])]);
+}
+
+#[test]
+fn test_collation_name() {
+    use boxercrab::charset::{charset_name, collation_name};
+    use boxercrab::QueryStatusVar;
+
+    assert_eq!(collation_name(33), Some("utf8_general_ci"));
+    assert_eq!(collation_name(255), Some("utf8mb4_0900_ai_ci"));
+    assert_eq!(collation_name(1000), None);
+    assert_eq!(charset_name(45), Some("utf8mb4"));
+    assert_eq!(charset_name(63), Some("binary"));
+
+    let var = QueryStatusVar::Q_CHARSET_CODE(33, 33, 8);
+    assert_eq!(var.client_charset(), Some("utf8"));
+    assert_eq!(var.collation_connection(), Some("utf8_general_ci"));
+    assert_eq!(var.collation_server(), Some("latin1_swedish_ci"));
+    assert_eq!(var.collation_database(), None);
+}
+
+#[test]
+fn test_stream_filter() {
+    use boxercrab::{BinlogStream, EventType, Filter, StreamEvent};
+
+    let input = include_bytes!("events/30_write_rows_v2/log.bin");
+    let writes = Filter::event_type(EventType::WriteRowsV2);
+    let events: Vec<_> = BinlogStream::new(input)
+        .with_filter(writes.clone().and(Filter::table("default", "boxercrab")))
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    assert!(matches!(
+        &events[0],
+        StreamEvent::Event(WriteRowsV2 { table_id: 111, .. })
+    ));
+
+    let count = |filter: Filter| BinlogStream::new(input).with_filter(filter).count();
+    assert_eq!(
+        count(writes.clone().and(Filter::table("default", "other"))),
+        0
+    );
+    assert_eq!(count(Filter::schema("default").and(!writes.clone())), 4);
+    assert_eq!(count(writes.or(Filter::event_type(EventType::Xid))), 2);
+    assert_eq!(count(Filter::position(0, 200)), 3);
+}
+
+#[test]
+fn test_commit_order() {
+    use boxercrab::analyze::commit_order;
+
+    let gtid = |last_committed: i64, sequence_number: i64| {
+        let mut body = vec![0x00];
+        body.extend_from_slice(&[0xab; 16]);
+        body.extend_from_slice(&sequence_number.to_le_bytes());
+        body.push(0x02);
+        body.extend_from_slice(&last_committed.to_le_bytes());
+        body.extend_from_slice(&sequence_number.to_le_bytes());
+        Event::parse(&raw_event(0x21, &body)).unwrap().1
+    };
+    // 2 and 3 were committed together after 1, 4 depends on 3
+    let events = vec![gtid(0, 1), gtid(1, 2), gtid(1, 3), gtid(3, 4)];
+    assert_eq!(commit_order(&events), vec![vec![1], vec![2, 3], vec![4]]);
+    assert!(commit_order(&[]).is_empty());
+}
+
+#[test]
+fn test_table_map_terminator() {
+    use boxercrab::{Error, TableMapCache};
+
+    // table_id, flags, schema `d`, table `t`, one LONG column without meta
+    let body = [1, 0, 0, 0, 0, 0, 1, 0, 1, b'd', 0, 1, b't', 0, 1, 3, 0, 0];
+    let input = raw_event(0x13, &body);
+    assert!(matches!(
+        Event::parse_checked(&input),
+        Ok((_, TableMap { .. }, _))
+    ));
+
+    for pos in [10, 13].iter() {
+        let mut corrupted = body;
+        corrupted[*pos] = 0x01;
+        let input = raw_event(0x13, &corrupted);
+        assert_eq!(
+            Event::parse_checked(&input).unwrap_err(),
+            Error::UnexpectedTerminator { offset: 0 }
+        );
+        assert!(matches!(
+            Event::parse_with_cache(&input, &mut TableMapCache::new()),
+            Err(nom::Err::Error((_, nom::error::ErrorKind::Char)))
+        ));
+    }
+}
+
+#[test]
+fn test_headers_iter() {
+    use boxercrab::{headers_iter, BinlogStream, EventType, StreamEvent};
+
+    let input = include_bytes!("events/30_write_rows_v2/log.bin");
+    let headers: Vec<_> = headers_iter(input).collect::<Result<_, _>>().unwrap();
+    let events: Vec<_> = BinlogStream::new(input)
+        .map(|e| match e.unwrap() {
+            StreamEvent::Event(event) => event.header().clone(),
+            StreamEvent::Stop { header }
+            | StreamEvent::RotateTo { header, .. }
+            | StreamEvent::Heartbeat { header, .. } => header,
+            StreamEvent::Skipped { .. } => panic!("nothing to skip"),
+        })
+        .collect();
+    assert_eq!(headers, events);
+    assert_eq!(headers[0].kind(), EventType::FormatDescription);
+    assert_eq!(headers.last().unwrap().kind(), EventType::Rotate);
+    assert_eq!(
+        headers
+            .iter()
+            .filter(|h| h.kind() == EventType::WriteRowsV2)
+            .count(),
+        1
+    );
+
+    let mut iter = headers_iter(&input[..input.len() - 1]);
+    assert!(matches!(
+        iter.by_ref().last(),
+        Some(Err(boxercrab::Error::Truncated { .. }))
+    ));
+    assert!(iter.next().is_none());
+}
+
+#[test]
+fn test_query_schema_and_table() {
+    let input = include_bytes!("events/02_query/log.bin");
+    let (_, events) = Event::from_bytes(input).unwrap();
+    let queries: Vec<_> = events
+        .iter()
+        .filter(|e| matches!(e, Query { .. }))
+        .map(|e| e.schema_and_table())
+        .collect();
+    assert_eq!(queries.len(), 2);
+    assert!(queries.iter().all(|q| q == &Some(("default", None))));
+    assert_eq!(Event::xid(1).schema_and_table(), None);
+}
+
+#[test]
+fn test_redacted_debug() {
+    let input = include_bytes!("events/30_write_rows_v2/log.bin");
+    let (_, events) = Event::from_bytes(input).unwrap();
+    let event = events
+        .iter()
+        .find(|e| matches!(e, WriteRowsV2 { .. }))
+        .unwrap();
+    let redacted = format!("{:?}", event.redacted());
+    // event_size 46 - 19 header - 10 fixed part - 1 column count - 1 image bits - 4 checksum
+    assert!(redacted.starts_with("WriteRowsV2 {"));
+    assert!(
+        redacted.contains("rows: <1 rows, 11 bytes>"),
+        "{}",
+        redacted
+    );
+    assert!(redacted.contains("table_id: 111"));
+    assert!(!redacted.contains("VarChar"));
+    assert!(format!("{:?}", event).contains("VarChar"));
+
+    let xid = Event::xid(1);
+    assert_eq!(format!("{:?}", xid.redacted()), format!("{:?}", xid));
+}
+
+#[test]
+fn test_json_member_order() {
+    use boxercrab::json::{parse_json, JsonValue};
+
+    // small object {"b": 1, "aa": 2}, keys are stored sorted by length first
+    let input = [
+        0x00, 2, 0, 21, 0, 18, 0, 1, 0, 19, 0, 2, 0, 0x05, 1, 0, 0x05, 2, 0, b'b', b'a', b'a',
+    ];
+    let (_, value) = parse_json(&input).unwrap();
+    assert_eq!(
+        value,
+        JsonValue::Object(vec![
+            ("b".into(), JsonValue::Int(1)),
+            ("aa".into(), JsonValue::Int(2))
+        ])
+    );
+    assert_eq!(value.to_string(), r#"{"b": 1, "aa": 2}"#);
+}
+
+#[test]
+fn test_rows_column_count_too_large() {
+    use boxercrab::Error;
+
+    // table_id, flags, extra_data_len, then column_count far beyond rest of event
+    let mut body = vec![1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0xfe];
+    body.extend_from_slice(&(u64::MAX - 3).to_le_bytes());
+    body.extend_from_slice(&[0xff; 4]);
+    for &event_type in &[0x1e, 0x1f, 0x20] {
+        let input = raw_event(event_type, &body);
+        match Event::parse_checked(&input) {
+            Err(Error::Parse {
+                offset: 0,
+                kind,
+                context,
+            }) => {
+                assert_eq!(kind, nom::error::ErrorKind::TooLarge);
+                assert!(context.unwrap().ends_with(": reading image bits"));
+            }
+            other => panic!("should fail on column_count: {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn test_affected_tables() {
+    use boxercrab::analyze::affected_tables;
+
+    let input = include_bytes!("events/30_write_rows_v2/log.bin");
+    let (_, events) = Event::from_bytes(input).unwrap();
+    let mut txn: Vec<_> = events
+        .into_iter()
+        .skip_while(|e| !matches!(e, TableMap { .. }))
+        .collect();
+    assert_eq!(affected_tables(&txn), vec![111].into_iter().collect());
+
+    // same rows written to another table, then the first one again
+    let mut other = txn[1].clone();
+    if let WriteRowsV2 { table_id, .. } = &mut other {
+        *table_id = 112;
+    }
+    txn.insert(2, other);
+    txn.insert(3, txn[1].clone());
+    let tables = affected_tables(&txn);
+    assert_eq!(tables.len(), 2);
+    assert!(tables.contains(&111) && tables.contains(&112));
+    assert!(affected_tables(&[Event::xid(1)]).is_empty());
+}
+
+#[test]
+fn test_stream_heartbeat() {
+    use boxercrab::{BinlogStream, StreamEvent};
+
+    let mut input = vec![0xfe, b'b', b'i', b'n'];
+    let mut heartbeat = raw_event(0x1b, &[]);
+    heartbeat[13..17].copy_from_slice(&1024u32.to_le_bytes());
+    input.extend_from_slice(&heartbeat);
+    input.extend_from_slice(&Event::xid(1).to_bytes().unwrap());
+    let items: Vec<_> = BinlogStream::new(&input).collect::<Result<_, _>>().unwrap();
+    assert_eq!(items.len(), 2);
+    match &items[0] {
+        StreamEvent::Heartbeat { header, log_pos } => {
+            assert_eq!(*log_pos, 1024);
+            assert_eq!(header.event_type, 0x1b);
+        }
+        other => panic!("should be heartbeat: {:?}", other),
+    }
+    assert!(matches!(items[1], StreamEvent::Event(XID { xid: 1, .. })));
+}
+
+#[test]
+fn test_max_event_size() {
+    use boxercrab::ParseOptions;
+
+    let options = ParseOptions::default();
+    assert_eq!(options.max_event_size, 256 * 1024 * 1024);
+    assert!(Event::parse_with_options(&Event::xid(1).to_bytes().unwrap(), options).is_ok());
+
+    // header claims 4 GiB, only header is there
+    let mut input = raw_event(0x10, &[]);
+    input[9..13].copy_from_slice(&u32::MAX.to_le_bytes());
+    match Event::parse_with_options(&input, options) {
+        Err(nom::Err::Error((_, kind))) => assert_eq!(kind, nom::error::ErrorKind::TooLarge),
+        other => panic!("should reject event_size: {:?}", other),
+    }
+
+    let small = ParseOptions {
+        max_event_size: 31,
+        ..Default::default()
+    };
+    assert!(Event::parse_with_options(&Event::xid(1).to_bytes().unwrap(), small).is_ok());
+    let query = Event::query("db", "BEGIN").to_bytes().unwrap();
+    assert!(Event::parse_with_options(&query, small).is_err());
+}
+
+#[test]
+fn test_binlog_reader_max_event_size() {
+    use boxercrab::{BinlogReader, Error, ParseOptions};
+
+    // header claims 4 GiB, reader must give up before buffering body
+    let mut header = raw_event(0x10, &[]);
+    header[9..13].copy_from_slice(&u32::MAX.to_le_bytes());
+    let mut input = vec![0xfe, 0x62, 0x69, 0x6e];
+    input.extend_from_slice(&header[..19]);
+    let mut reader = BinlogReader::new(std::io::Cursor::new(input));
+    assert_eq!(
+        reader.next(),
+        Some(Err(Error::TooLarge {
+            offset: 4,
+            size: u32::MAX as usize
+        }))
+    );
+    assert!(reader.next().is_none());
+
+    let mut input = vec![0xfe, 0x62, 0x69, 0x6e];
+    input.extend(Event::xid(1).to_bytes().unwrap());
+    let small = ParseOptions {
+        max_event_size: 30,
+        ..Default::default()
+    };
+    let mut reader = BinlogReader::new(std::io::Cursor::new(input)).with_options(small);
+    assert!(matches!(
+        reader.next(),
+        Some(Err(Error::TooLarge { size: 31, .. }))
+    ));
+}
+
+#[test]
+fn test_dialect_detection() {
+    use boxercrab::{Dialect, ParseContext};
+
+    let input = include_bytes!("events/15_format_desc/log.bin");
+    let (_, events) = Event::from_bytes(input).unwrap();
+    let mut format_desc = events[0].clone();
+    let mut ctx = ParseContext::default();
+    assert_eq!(ctx.dialect, Dialect::MySQL);
+    ctx.update(&format_desc);
+    assert_eq!(ctx.dialect, Dialect::MySQL);
+
+    if let FormatDesc {
+        mysql_server_version,
+        ..
+    } = &mut format_desc
+    {
+        *mysql_server_version = "10.5.8-MariaDB-log".into();
+    }
+    ctx.update(&format_desc);
+    assert_eq!(ctx.dialect, Dialect::MariaDB);
+    assert_eq!(ctx.server_version, Some((10, 5, 8)));
+    // other events leave dialect alone
+    ctx.update(&Event::xid(1));
+    assert_eq!(ctx.dialect, Dialect::MariaDB);
+}
+
+#[test]
+fn test_content_hash() {
+    use boxercrab::{event_slices, ParseContext};
+
+    let ctx = ParseContext::default();
+    let input = include_bytes!("events/30_write_rows_v2/log.bin");
+    let mut hashes = vec![];
+    let mut i = &input[4..];
+    while !i.is_empty() {
+        let (remain, (header, _, _)) = event_slices(i).unwrap();
+        let event = &i[..header.event_size as usize];
+        hashes.push(Event::content_hash(event, &ctx).unwrap());
+        i = remain;
+    }
+    let count = hashes.len();
+    hashes.sort_unstable();
+    hashes.dedup();
+    assert_eq!(hashes.len(), count);
+
+    // same event captured later, with other timestamp, log_pos, flags and checksum
+    let xid = Event::xid(7).to_bytes().unwrap();
+    let mut recaptured = xid.clone();
+    recaptured[0..4].copy_from_slice(&1_600_000_000u32.to_le_bytes());
+    recaptured[13..17].copy_from_slice(&1234u32.to_le_bytes());
+    recaptured[17..19].copy_from_slice(&0x0008u16.to_le_bytes());
+    let len = recaptured.len();
+    recaptured[len - 4..].copy_from_slice(&[0xde, 0xad, 0xbe, 0xef]);
+    let hash = Event::content_hash(&xid, &ctx);
+    assert!(hash.is_some());
+    assert_eq!(hash, Event::content_hash(&recaptured, &ctx));
+
+    // re-encoded with checksum turned off
+    let mut unchecked = xid[..len - 4].to_vec();
+    unchecked[9..13].copy_from_slice(&((len - 4) as u32).to_le_bytes());
+    let off = ParseContext {
+        has_checksum: false,
+        ..Default::default()
+    };
+    assert_eq!(hash, Event::content_hash(&unchecked, &off));
+
+    let other = Event::xid(8).to_bytes().unwrap();
+    assert_ne!(hash, Event::content_hash(&other, &ctx));
+    assert_eq!(Event::content_hash(&xid[..len - 1], &ctx), None);
+}
+
+#[test]
+fn test_parse_error_context() {
+    use boxercrab::Error;
+
+    // table_id, flags, schema `d`, table `t`, column_count needs 8 more bytes
+    let body = [1, 0, 0, 0, 0, 0, 1, 0, 1, b'd', 0, 1, b't', 0, 0xfe];
+    let err = Event::parse_checked(&raw_event(0x13, &body)).unwrap_err();
+    match &err {
+        Error::Parse { context, .. } => assert_eq!(
+            context.as_deref(),
+            Some("parse_table_map: reading column_count")
+        ),
+        other => panic!("should be parse error: {:?}", other),
+    }
+    assert!(err
+        .to_string()
+        .contains("in parse_table_map: reading column_count"));
+
+    // context of previous failure doesn't leak into next one
+    let err = Event::parse_checked(&raw_event(0x10, &[0; 3])).unwrap_err();
+    assert!(matches!(err, Error::Parse { context: None, .. }));
+}
+
+#[test]
+fn test_binlog_reader_short_reads() {
+    use boxercrab::{BinlogReader, BinlogStream, Error};
+    use std::io::Read;
+
+    // hands out one byte per read
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            match (self.0.split_first(), buf.first_mut()) {
+                (Some((b, rest)), Some(slot)) => {
+                    *slot = *b;
+                    self.0 = rest;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    let input = include_bytes!("events/30_write_rows_v2/log.bin");
+    let expected: Vec<_> = BinlogStream::new(input).collect();
+    let mut reader = BinlogReader::new(Trickle(input));
+    let items: Vec<_> = reader.by_ref().collect();
+    assert_eq!(items, expected);
+    assert_eq!(reader.offset(), input.len());
+
+    let cut = input.len() - 10;
+    let items: Vec<_> = BinlogReader::new(Trickle(&input[..cut])).collect();
+    assert!(matches!(items.last(), Some(Err(Error::Truncated { .. }))));
+    let items: Vec<_> = BinlogReader::new(Trickle(&[0xfe, b'b'])).collect();
+    assert_eq!(items, vec![Err(Error::BadMagic)]);
+}
+
+#[test]
+fn test_ignorable() {
+    let mut input = raw_event(0x1c, &[1, 2, 3, 4, 5]);
+    // LOG_EVENT_IGNORABLE_F
+    input[17..19].copy_from_slice(&0x80u16.to_le_bytes());
+    let (i, event) = Event::parse(&input).unwrap();
+    assert!(i.is_empty());
+    match &event {
+        Ignorable {
+            header, payload, ..
+        } => {
+            assert!(header.flags.ignorable);
+            assert_eq!(payload, &vec![1, 2, 3, 4, 5]);
+        }
+        other => panic!("should be ignorable: {:?}", other),
+    }
+
+    // parsed by parser, never passed to handler
+    let mut seen = 0;
+    let (_, handled) = Event::parse_with_handler(&input, &mut |_, _| seen += 1).unwrap();
+    assert_eq!(handled, event);
+    assert_eq!(seen, 0);
+}
+
+#[test]
+fn test_minimal_image_delete() {
+    use boxercrab::{sql::to_sql, ColTypes, TableMapCache};
+
+    let table_map = Event::table_map(
+        3,
+        "db",
+        "t",
+        vec![ColTypes::Long, ColTypes::VarChar(20), ColTypes::Long],
+    );
+    let table = table_map.table_schema().unwrap();
+    let mut cache = TableMapCache::new();
+    cache.insert(&table_map);
+    // binlog_row_image=MINIMAL, only primary key `id` is in before image
+    let mut body = vec![3, 0, 0, 0, 0, 0, 0x01, 0, 2, 0, 3, 0b001];
+    body.extend_from_slice(&[0, 7, 0, 0, 0]);
+    body.extend_from_slice(&[0, 8, 0, 0, 0]);
+    let input = raw_event(0x20, &body);
+    let (remain, event) = Event::parse_with_cache(&input, &mut cache).unwrap();
+    assert!(remain.is_empty());
+    match &event {
+        DeleteRowsV2 { flags, rows, .. } => {
+            assert!(!flags.has_columns);
+            assert_eq!(rows, &vec![vec![Long(Signed(7))], vec![Long(Signed(8))]]);
+        }
+        other => panic!("should be delete_rows_v2: {:?}", other),
+    }
+
+    let validated = event.decode_rows_validated(&table).unwrap();
+    assert!(validated.is_valid());
+    assert_eq!(validated.rows[1][0].value, Long(Signed(8)));
+    assert_eq!(to_sql(&event, &table)[0], "DELETE FROM `db`.`t` WHERE @1=7");
+
+    // primary key is the last column
+    body[11] = 0b100;
+    let input = raw_event(0x20, &body);
+    let (_, event) = Event::parse_with_cache(&input, &mut cache).unwrap();
+    assert_eq!(to_sql(&event, &table)[1], "DELETE FROM `db`.`t` WHERE @3=8");
+}
+
+#[test]
+fn test_position_key_sort() {
+    use boxercrab::PositionKey;
+
+    assert_eq!(PositionKey::file_seq("mysql-bin.000002"), Some(2));
+    assert_eq!(PositionKey::file_seq("mysql-bin"), None);
+
+    let input = include_bytes!("events/30_write_rows_v2/log.bin");
+    let (_, mut events) = Event::from_bytes(input).unwrap();
+    let next_binlog = match events.last() {
+        Some(Rotate { next_binlog, .. }) => next_binlog.clone(),
+        other => panic!("should end with rotate: {:?}", other),
+    };
+    // beginning of next binlog, positions are smaller than the ones of previous file
+    events.extend(events[..3].to_vec());
+    let keys = PositionKey::assign(1, &events);
+    let next = PositionKey::file_seq(&next_binlog).unwrap();
+    assert_eq!(
+        keys[12],
+        PositionKey {
+            file: 1,
+            pos: events[12].header().log_pos
+        }
+    );
+    assert_eq!(keys[13].file, next);
+    assert!(keys[13] > keys[12]);
+
+    let mut shuffled: Vec<_> = keys.iter().copied().zip(events.iter().cloned()).collect();
+    shuffled.reverse();
+    shuffled.rotate_left(5);
+    shuffled.sort_by_key(|(key, _)| *key);
+    let sorted: Vec<_> = shuffled.into_iter().map(|(_, event)| event).collect();
+    assert_eq!(sorted, events);
+}
+
+#[test]
+fn test_escape_terminator() {
+    use boxercrab::utils::escape_terminator;
+
+    assert_eq!(escape_terminator(9), "\\t");
+    assert_eq!(escape_terminator(b'\n'), "\\n");
+    assert_eq!(escape_terminator(b'\\'), "\\\\");
+    assert_eq!(escape_terminator(b','), ",");
+    assert_eq!(
+        format!("FIELDS TERMINATED BY '{}'", escape_terminator(b'\t')),
+        "FIELDS TERMINATED BY '\\t'"
+    );
+}
+
+#[test]
+fn test_check_gtid_gaps() {
+    use boxercrab::analyze::{check_gtid_gaps, GtidGap};
+
+    let sid = [0x3e; 16];
+    let gtid = |gno: i64| {
+        let mut body = vec![0x00];
+        body.extend_from_slice(&sid);
+        body.extend_from_slice(&gno.to_le_bytes());
+        body.push(0x02);
+        body.extend_from_slice(&(gno - 1).to_le_bytes());
+        body.extend_from_slice(&gno.to_le_bytes());
+        Event::parse(&raw_event(0x21, &body)).unwrap().1
+    };
+    // 1-3 executed before this binlog
+    let mut body = 1u64.to_le_bytes().to_vec();
+    body.extend_from_slice(&sid);
+    body.extend_from_slice(&1u64.to_le_bytes());
+    body.extend_from_slice(&1i64.to_le_bytes());
+    body.extend_from_slice(&4i64.to_le_bytes());
+    let previous = Event::parse(&raw_event(0x23, &body)).unwrap().1;
+
+    let sid = "3e3e3e3e-3e3e-3e3e-3e3e-3e3e3e3e3e3e";
+    match gtid(4) {
+        Gtid {
+            source_id,
+            transaction_id,
+            ..
+        } => assert_eq!((source_id.as_str(), transaction_id), (sid, 4)),
+        other => panic!("should be gtid: {:?}", other),
+    }
+    let events = vec![previous.clone(), gtid(4), gtid(5), gtid(6)];
+    assert!(check_gtid_gaps(&events).is_empty());
+
+    // 5 is skipped
+    let events = vec![previous, gtid(4), gtid(6), gtid(7)];
+    assert_eq!(
+        check_gtid_gaps(&events),
+        vec![GtidGap {
+            sid: sid.to_string(),
+            start: 5,
+            end: 6
+        }]
+    );
+}
+
+#[test]
+fn test_event_header_length() {
+    use boxercrab::{event_slices, utils::crc32, ParseContext, TableMapCache};
+
+    let input = include_bytes!("events/30_write_rows_v2/log.bin");
+    let (_, expected) = Event::from_bytes(input).unwrap();
+
+    // announce 23 bytes header, then pad header of following events with 4 bytes
+    let mut raw = vec![];
+    let mut original = vec![];
+    let mut i = &input[4..];
+    while !i.is_empty() {
+        let (remain, (header, _, _)) = event_slices(i).unwrap();
+        original.push(&i[..header.event_size as usize]);
+        let mut event = i[..header.event_size as usize].to_vec();
+        if header.event_type == 0x0f {
+            event[19 + 2 + 50 + 4] = 23;
+        } else {
+            event.splice(19..19, vec![0xee; 4]);
+            let size = event.len() as u32;
+            event[9..13].copy_from_slice(&size.to_le_bytes());
+        }
+        let len = event.len();
+        let checksum = crc32(&event[..len - 4]);
+        event[len - 4..].copy_from_slice(&checksum.to_le_bytes());
+        raw.push(event);
+        i = remain;
+    }
+
+    let mut ctx = ParseContext::default();
+    let mut cache = TableMapCache::new();
+    for (idx, event) in raw.iter().enumerate() {
+        // extra header bytes are not part of content, FormatDesc itself is changed
+        if idx > 0 {
+            assert_eq!(
+                Event::content_hash(event, &ctx),
+                Event::content_hash(original[idx], &ParseContext::default())
+            );
+        }
+        let (remain, output) = Event::parse_with_context(event, &ctx, &mut cache).unwrap();
+        ctx.update(&output);
+        assert!(remain.is_empty());
+        assert_eq!(output.header().event_size as usize, event.len());
+        assert_eq!(output.header().kind(), expected[idx].header().kind());
+        assert_eq!(output.table_id(), expected[idx].table_id());
+        if idx == 0 {
+            assert_eq!(ctx.header_len, 23);
+        }
+    }
+}
+
+#[test]
+fn test_value_display() {
+    use boxercrab::sql::literal;
+
+    assert_eq!(VarChar(b"it's".to_vec()).to_string(), "'it\\'s'");
+    assert_eq!(String(b"a\\b".to_vec()).to_string(), "'a\\\\b'");
+    assert_eq!(Blob(vec![0x00, 0xab, b'\'']).to_string(), "x'00AB27'");
+    assert_eq!(Null.to_string(), "NULL");
+    assert_eq!(Long(Signed(-3)).to_string(), "-3");
+    assert_eq!(
+        Date {
+            year: 2020,
+            month: 7,
+            day: 31
+        }
+        .to_string(),
+        "'2020-07-31'"
+    );
+    assert_eq!(NewDecimal("-1.50".to_string()).to_string(), "-1.50");
+
+    // DATETIME2 and TIME2 are decoded, so they're quoted like DATETIME and TIME
+    let (_, (_, datetime)) = boxercrab::ColTypes::DateTime2(0)
+        .parse(&[0x99, 0xa6, 0xfe, 0xc8, 0xb8])
+        .unwrap();
+    assert_eq!(datetime.to_string(), "'2020-07-31 12:34:56'");
+    let (_, (_, time)) = boxercrab::ColTypes::Time2(0)
+        .parse(&[0x7f, 0xef, 0x7d])
+        .unwrap();
+    assert_eq!(literal(&time), Some("'-01:02:03'".to_string()));
+
+    // raw bytes and internal types have no literal, they are never faked
+    assert_eq!(literal(&Decimal(vec![0; 4])), None);
+    assert_eq!(Decimal(vec![0; 4]).to_string(), "<DECIMAL>");
+    assert_eq!(TinyBlob.to_string(), "<TINY_BLOB>");
+    assert_eq!(literal(&Null), Some("NULL".to_string()));
+}
+
+#[test]
+fn test_parse_binlog_index() {
+    use boxercrab::parse_binlog_index;
+
+    let index = b"./mysql-bin.000001\n./mysql-bin.000002\r\n\n/var/lib/mysql/mysql-bin.000003\n";
+    assert_eq!(
+        parse_binlog_index(&index[..]).unwrap(),
+        vec![
+            "./mysql-bin.000001",
+            "./mysql-bin.000002",
+            "/var/lib/mysql/mysql-bin.000003"
+        ]
+    );
+    assert!(parse_binlog_index(&b""[..]).unwrap().is_empty());
+}
+
+#[test]
+fn test_decimal_binary_size() {
+    use boxercrab::decimal::binary_size;
+
+    // sizes of DECIMAL columns reported by MySQL
+    for &(precision, scale, size) in &[
+        (1, 0, 1),
+        (4, 2, 2),
+        (10, 0, 5),
+        (10, 2, 5),
+        (14, 4, 7),
+        (18, 9, 8),
+        (20, 10, 10),
+        (65, 30, 30),
+    ] {
+        assert_eq!(
+            binary_size(precision, scale),
+            size,
+            "{},{}",
+            precision,
+            scale
+        );
+    }
+}
+
+#[test]
+fn test_stream_resync() {
+    use boxercrab::{headers_iter, BinlogStream, StreamEvent};
+
+    let input = include_bytes!("events/30_write_rows_v2/log.bin");
+    let headers: Vec<_> = headers_iter(input).collect::<Result<_, _>>().unwrap();
+    let expected: Vec<_> = BinlogStream::new(input).collect::<Result<_, _>>().unwrap();
+    // overwrite middle of the first Query event, then of the RowsQuery event followed by
+    // TableMap and WriteRowsV2
+    for &idx in [2, 7].iter() {
+        let start = headers[idx].log_pos as usize;
+        let end = headers[idx + 1].log_pos as usize;
+        let mut corrupted = input.to_vec();
+        for b in corrupted[start + 19..end - 10].iter_mut() {
+            *b = 0xff;
+        }
+        assert!(BinlogStream::new(&corrupted)
+            .collect::<Result<Vec<_>, _>>()
+            .is_err());
+
+        let events: Vec<_> = BinlogStream::new(&corrupted)
+            .with_resync()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(events.len(), expected.len());
+        assert_eq!(events[..idx + 1], expected[..idx + 1]);
+        assert_eq!(events[idx + 1], StreamEvent::Skipped { start, end });
+        assert_eq!(events[idx + 2..], expected[idx + 2..]);
+    }
+}
+
+#[test]
+fn test_ends_statement_and_transaction() {
+    let input = include_bytes!("events/30_write_rows_v2/log.bin");
+    let (_, events) = Event::from_bytes(input).unwrap();
+    // TableMap, WriteRowsV2 with end_of_stmt, Xid
+    match &events[10] {
+        WriteRowsV2 { flags, .. } => assert!(flags.end_of_stmt),
+        other => panic!("should be write_rows_v2: {:?}", other),
+    }
+    assert!(!events[9].ends_statement());
+    assert!(events[10].ends_statement());
+    assert!(!events[10].ends_transaction());
+    assert!(events[11].ends_statement());
+    assert!(events[11].ends_transaction());
+
+    let mut rows = events[10].clone();
+    if let WriteRowsV2 { flags, .. } = &mut rows {
+        flags.end_of_stmt = false;
+    }
+    assert!(!rows.ends_statement());
+
+    assert!(Event::query("db", "COMMIT").ends_transaction());
+    assert!(Event::query("db", "CREATE TABLE t (id INT)").ends_transaction());
+    assert!(!Event::query("db", "BEGIN").ends_transaction());
+    assert!(Event::xid(1).ends_transaction());
+}